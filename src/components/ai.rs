@@ -2,10 +2,17 @@ use rand::Rng;
 
 use crate::core::common::{self, current_time_ms};
 
-use super::input::State;
+use super::{bullet_pattern::BulletPattern, input::State, path::Path};
 
 pub type DestinationPoint = (f32, f32);
 
+// Shot power matching the nominal speed of the bullet emitters
+pub const DEFAULT_SHOT_POWER: f32 = 5.0;
+// The speed on a path is the distance covered in this time (a frame at 60 FPS)
+const PATH_STEP_MS: f32 = 1000.0 / 60.0;
+// Longer frames, e.g. after a pause, are cut short to avoid jumping along the path
+const MAX_PATH_STEPS_PER_FRAME: f32 = 4.0;
+
 pub struct TrajectorySequence {
 	points: Vec<DestinationPoint>,
 	start_time_ms: u64,
@@ -16,6 +23,10 @@ pub struct TrajectorySequence {
 	shoot_delay_ms: u64,
 	pub shoot_interval_ms: u64,
 	pub loop_count: u16,
+	// Pattern fired during this segment, the enemy one when None
+	pattern: Option<BulletPattern>,
+	// Smooth path followed instead of the points
+	path: Option<Path>,
 }
 
 impl TrajectorySequence {
@@ -32,9 +43,17 @@ impl TrajectorySequence {
 			shoot_num: 0,
 			shoot_index: usize::MAX,
 			shoot_interval_ms: 200,
-			loop_count: 1
+			loop_count: 1,
+			pattern: None,
+			path: None
 		}
 	}
+	// Follows the path from its start, which should be reached by the previous sequence
+	pub fn path(path: Path) -> Self {
+		let mut sequence = Self::new();
+		sequence.path = Some(path);
+		sequence
+	}
 	pub fn push(&mut self, point: DestinationPoint) {
 		self.points.push(point);
 	}
@@ -43,6 +62,23 @@ impl TrajectorySequence {
 		self.points.last()
 	}
 
+	pub fn translate(&mut self, offset: (f32, f32)) {
+		for point in self.points.iter_mut() {
+			point.0 += offset.0;
+			point.1 += offset.1;
+		}
+		if let Some(path) = self.path.as_mut() {
+			path.translate(offset);
+		}
+	}
+
+	fn is_finished(&self, current_point: usize, path_distance: f32) -> bool {
+		match &self.path {
+			Some(path) => path_distance >= path.length(),
+			None => current_point >= self.points.len()
+		}
+	}
+
 	pub fn can_shoot(&mut self, frequency_factor: f32) -> bool {
 		if self.shoot_delay_ms == 0 {
 			return false;
@@ -74,6 +110,10 @@ impl TrajectorySequence {
 		self.shoot_start_time_ms = common::current_time_ms() + rng.gen_range(0, self.shoot_delay_ms.clone());
 		self.shoot_start_interval_time_ms = self.shoot_start_time_ms.clone();
 	}
+
+	pub fn set_pattern(&mut self, pattern: BulletPattern) {
+		self.pattern = Some(pattern);
+	}
 }
 
 pub struct AIComponent {
@@ -81,6 +121,12 @@ pub struct AIComponent {
 	current_trajectory: usize,
 	current_point: usize,
 	current_loop_count: u16,
+	// Distance covered on the path of the current trajectory
+	path_distance: f32,
+	// Time of the last move on the path, the distance covered depends on the time elapsed since
+	path_last_update_ms: Option<u64>,
+	// Direction followed on an oriented path, in degrees
+	heading_degrees: Option<f32>,
 	pub state: State,
 	pub last_state: State,
 	pub shot_power: f32,
@@ -95,9 +141,12 @@ impl AIComponent {
 			current_point: 0,
 			current_trajectory: 0,
 			current_loop_count: 1,
+			path_distance: 0.0,
+			path_last_update_ms: None,
+			heading_degrees: None,
 			state: State::Stand,
 			last_state: State::Stand,
-			shot_power: 5.0,
+			shot_power: DEFAULT_SHOT_POWER,
 			shot_frequency_factor: 1.0,
 			speed : 5.0
 		}
@@ -108,8 +157,11 @@ impl AIComponent {
 		} else {
 			let pattern = &self.trajectories[self.current_trajectory];
 			if current_time_ms() >= pattern.start_time_ms {
-				if self.current_point >= pattern.points.len() {
+				if pattern.is_finished(self.current_point, self.path_distance) {
 					self.current_point = 0;
+					self.path_distance = 0.0;
+					self.path_last_update_ms = None;
+					self.heading_degrees = None;
 					if self.current_loop_count >= pattern.loop_count {
 						self.current_trajectory += 1;
 						self.current_loop_count = 1;
@@ -117,6 +169,15 @@ impl AIComponent {
 						self.current_loop_count += 1;
 					}
 					self.next_position(actual_pos, tolerance)
+				} else if let Some(path) = &pattern.path {
+					// Moves "speed" further on the path every PATH_STEP_MS, whatever the frame rate
+					let now = current_time_ms();
+					let steps = self.path_last_update_ms.map_or(1.0, |last_update_ms| ((now - last_update_ms) as f32 / PATH_STEP_MS).min(MAX_PATH_STEPS_PER_FRAME));
+					self.path_last_update_ms = Some(now);
+					self.path_distance = (self.path_distance + self.speed * steps).min(path.length());
+					let (target_pos, heading_degrees) = path.sample(self.path_distance);
+					self.heading_degrees = if path.orient { Some(heading_degrees) } else { None };
+					Some(target_pos)
 				} else {
 					let point = self.current_point;
 					let target_pos = pattern.points[point];
@@ -135,8 +196,24 @@ impl AIComponent {
 		self.trajectories.push(pattern);
 	}
 
+	// Replaces the trajectories, and restarts from the first one
 	pub fn set_movement_patterns(&mut self, patterns: Vec<TrajectorySequence>) {
 		self.trajectories = patterns;
+		self.current_trajectory = 0;
+		self.current_point = 0;
+		self.current_loop_count = 1;
+		self.path_distance = 0.0;
+		self.path_last_update_ms = None;
+		self.heading_degrees = None;
+	}
+
+	// The position returned by next_position must be reached exactly, its distance varying with the easing
+	pub fn on_path(&self) -> bool {
+		self.trajectories.get(self.current_trajectory).is_some_and(|trajectory| trajectory.path.is_some())
+	}
+
+	pub fn heading_degrees(&self) -> Option<f32> {
+		self.heading_degrees
 	}
 
 	pub fn can_shoot(&mut self) -> bool {
@@ -146,6 +223,10 @@ impl AIComponent {
 			self.trajectories[self.current_trajectory].can_shoot(self.shot_frequency_factor)
 		}
 	}
+
+	pub fn current_pattern(&self) -> Option<&BulletPattern> {
+		self.trajectories.get(self.current_trajectory).and_then(|trajectory| trajectory.pattern.as_ref())
+	}
 }
 
 impl Default for AIComponent {
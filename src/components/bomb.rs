@@ -0,0 +1,28 @@
+pub const BOMB_START_STOCK: u32 = 2;
+pub const BOMB_MAX_STOCK: u32 = 5;
+
+// Screen-clearing bombs of a player
+pub struct BombComponent {
+	pub stock: u32,
+	// A held bomb input only drops one bomb, the input has to be released first
+	pub armed: bool
+}
+
+impl BombComponent {
+	pub fn new(stock: u32) -> Self {
+		BombComponent {
+			stock,
+			armed: true
+		}
+	}
+
+	pub fn replenish(&mut self) {
+		self.stock = (self.stock + 1).min(BOMB_MAX_STOCK);
+	}
+}
+
+impl Default for BombComponent {
+	fn default() -> Self {
+		BombComponent::new(BOMB_START_STOCK)
+	}
+}
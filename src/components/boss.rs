@@ -0,0 +1,76 @@
+use super::{ai::{DestinationPoint, TrajectorySequence}, bullet_pattern::BulletPattern};
+
+#[derive(Clone)]
+pub struct BossPhase {
+	// The phase starts once the boss health ratio falls to this value (1.0 for the first phase)
+	pub health_threshold: f32,
+	// Looped movement, as fractions of the screen size
+	pub waypoints: Vec<DestinationPoint>,
+	pub speed: f32,
+	pub pattern: BulletPattern,
+	pub shoot_delay_ms: u64,
+	pub shoot_num: usize,
+	pub shoot_interval_ms: u64,
+}
+
+impl BossPhase {
+	pub fn new(health_threshold: f32, waypoints: Vec<DestinationPoint>, speed: f32, pattern: BulletPattern) -> Self {
+		BossPhase {
+			health_threshold,
+			waypoints,
+			speed,
+			pattern,
+			shoot_delay_ms: 1500,
+			shoot_num: 1,
+			shoot_interval_ms: 200
+		}
+	}
+
+	pub fn trajectory(&self, screen_width: u32, screen_height: u32) -> TrajectorySequence {
+		let mut sequence = TrajectorySequence::new();
+		for waypoint in &self.waypoints {
+			sequence.push((waypoint.0 * screen_width as f32, waypoint.1 * screen_height as f32));
+		}
+		sequence.loop_count = u16::MAX;
+		sequence.set_shoot_delay(self.shoot_delay_ms);
+		sequence.shoot_num = self.shoot_num;
+		sequence.shoot_interval_ms = self.shoot_interval_ms;
+		sequence.set_pattern(self.pattern.clone());
+		sequence
+	}
+}
+
+pub struct BossComponent {
+	pub name: String,
+	// Sorted by decreasing health threshold
+	pub phases: Vec<BossPhase>,
+	pub current_phase: Option<usize>,
+	pub max_health: i64,
+}
+
+impl BossComponent {
+	pub fn new(name: &str, phases: Vec<BossPhase>, max_health: i64) -> Self {
+		BossComponent {
+			name: name.to_string(),
+			phases,
+			current_phase: None,
+			max_health
+		}
+	}
+
+	pub fn health_ratio(&self, health_points: i64) -> f32 {
+		(health_points.max(0) as f32 / self.max_health as f32).min(1.0)
+	}
+
+	// Last phase whose threshold has been reached
+	pub fn phase_for(&self, health_points: i64) -> usize {
+		let ratio = self.health_ratio(health_points);
+		self.phases.iter().rposition(|phase| ratio <= phase.health_threshold).unwrap_or(0)
+	}
+}
+
+impl Default for BossComponent {
+	fn default() -> Self {
+		BossComponent::new("", Vec::new(), 1)
+	}
+}
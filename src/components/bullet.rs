@@ -0,0 +1,30 @@
+// Enemy bullet whose speed and direction change over time
+pub struct BulletComponent {
+	pub speed: f32,
+	pub acceleration: f32,
+	// In degrees
+	pub direction: f32,
+	pub angular_velocity: f32,
+}
+
+impl BulletComponent {
+	pub fn new(speed: f32, acceleration: f32, direction: f32, angular_velocity: f32) -> Self {
+		BulletComponent {
+			speed,
+			acceleration,
+			direction,
+			angular_velocity
+		}
+	}
+
+	pub fn velocity(&self) -> (f32, f32) {
+		let radians = self.direction.to_radians();
+		(self.speed * f32::cos(radians), self.speed * f32::sin(radians))
+	}
+}
+
+impl Default for BulletComponent {
+	fn default() -> Self {
+		BulletComponent::new(0.0, 0.0, 0.0, 0.0)
+	}
+}
@@ -0,0 +1,173 @@
+// Enemy bullet patterns : an emitter fires a volley of bullets, a pattern fires all its emitters at once
+// Angles are in degrees, 0 is towards the right and 90 towards the bottom of the screen
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EmitterShape {
+	// "count" bullets spread over "spread_degrees", centered on a fixed direction
+	NWay { count: u32, spread_degrees: f32, direction_degrees: f32 },
+	// "count" bullets spread over "spread_degrees", centered on the targeted player
+	AimedFan { count: u32, spread_degrees: f32 },
+	// "arms" evenly spaced bullets, the whole spiral turns by "step_degrees" after each volley
+	Spiral { arms: u32, step_degrees: f32 },
+	// "count" bullets evenly spaced on a full circle
+	Ring { count: u32 },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct BulletEmitter {
+	pub shape: EmitterShape,
+	pub speed: f32,
+	// Speed added each frame, can be negative to slow the bullets down
+	pub acceleration: f32,
+	// Rotation of the bullets direction each frame, in degrees
+	pub angular_velocity: f32,
+	pub angle_offset_degrees: f32,
+}
+
+impl BulletEmitter {
+	pub fn new(shape: EmitterShape) -> Self {
+		BulletEmitter {
+			shape,
+			speed: 5.0,
+			acceleration: 0.0,
+			angular_velocity: 0.0,
+			angle_offset_degrees: 0.0
+		}
+	}
+
+	pub fn aimed() -> Self {
+		Self::new(EmitterShape::AimedFan { count: 1, spread_degrees: 0.0 })
+	}
+
+	pub fn aimed_fan(count: u32, spread_degrees: f32) -> Self {
+		Self::new(EmitterShape::AimedFan { count, spread_degrees })
+	}
+
+	pub fn n_way(count: u32, spread_degrees: f32, direction_degrees: f32) -> Self {
+		Self::new(EmitterShape::NWay { count, spread_degrees, direction_degrees })
+	}
+
+	pub fn spiral(arms: u32, step_degrees: f32) -> Self {
+		Self::new(EmitterShape::Spiral { arms, step_degrees })
+	}
+
+	pub fn ring(count: u32) -> Self {
+		Self::new(EmitterShape::Ring { count })
+	}
+
+	pub fn speed(&mut self, speed: f32) -> &mut Self {
+		self.speed = speed;
+		self
+	}
+
+	pub fn acceleration(&mut self, acceleration: f32) -> &mut Self {
+		self.acceleration = acceleration;
+		self
+	}
+
+	pub fn angular_velocity(&mut self, angular_velocity: f32) -> &mut Self {
+		self.angular_velocity = angular_velocity;
+		self
+	}
+
+	pub fn offset(&mut self, angle_offset_degrees: f32) -> &mut Self {
+		self.angle_offset_degrees = angle_offset_degrees;
+		self
+	}
+
+	// Shape of the definition files : "aimed", "fan:<count>:<spread degrees>", "ring:<count>" or "spiral:<arms>:<step degrees>"
+	pub fn parse(value: &str) -> Result<Self, String> {
+		let error = || format!("invalid pattern \"{}\"", value);
+		let parts: Vec<&str> = value.split(':').collect();
+		match parts.as_slice() {
+			["aimed"] => Ok(BulletEmitter::aimed()),
+			["fan", count, spread] => Ok(BulletEmitter::aimed_fan(count.parse().map_err(|_| error())?, spread.parse().map_err(|_| error())?)),
+			["ring", count] => Ok(BulletEmitter::ring(count.parse().map_err(|_| error())?)),
+			["spiral", arms, step] => Ok(BulletEmitter::spiral(arms.parse().map_err(|_| error())?, step.parse().map_err(|_| error())?)),
+			_ => Err(error())
+		}
+	}
+
+	fn fan(center_degrees: f32, count: u32, spread_degrees: f32) -> Vec<f32> {
+		if count <= 1 {
+			return vec![center_degrees];
+		}
+		let step = spread_degrees / (count - 1) as f32;
+		(0..count).map(|i| center_degrees - spread_degrees / 2.0 + step * i as f32).collect()
+	}
+
+	fn circle(start_degrees: f32, count: u32) -> Vec<f32> {
+		(0..count).map(|i| start_degrees + 360.0 * i as f32 / count as f32).collect()
+	}
+
+	// Directions of the bullets of one volley
+	// aim_degrees : direction of the targeted player, rotation_degrees : current rotation of the spirals
+	pub fn angles(&self, aim_degrees: f32, rotation_degrees: f32) -> Vec<f32> {
+		let angles = match self.shape {
+			EmitterShape::NWay { count, spread_degrees, direction_degrees } => Self::fan(direction_degrees, count, spread_degrees),
+			EmitterShape::AimedFan { count, spread_degrees } => Self::fan(aim_degrees, count, spread_degrees),
+			EmitterShape::Spiral { arms, step_degrees: _ } => Self::circle(rotation_degrees, arms),
+			EmitterShape::Ring { count } => Self::circle(0.0, count),
+		};
+		angles.iter().map(|angle| angle + self.angle_offset_degrees).collect()
+	}
+
+	// Rotation to apply to the spirals after a volley
+	pub fn rotation_step(&self) -> f32 {
+		match self.shape {
+			EmitterShape::Spiral { arms: _, step_degrees } => step_degrees,
+			_ => 0.0
+		}
+	}
+}
+
+#[derive(Clone, PartialEq)]
+pub struct BulletPattern {
+	pub emitters: Vec<BulletEmitter>
+}
+
+impl BulletPattern {
+	pub fn new() -> Self {
+		BulletPattern {
+			emitters: Vec::new()
+		}
+	}
+
+	// A single bullet aimed at a player
+	pub fn aimed() -> Self {
+		Self::new().emitter(BulletEmitter::aimed()).clone()
+	}
+
+	pub fn emitter(&mut self, emitter: BulletEmitter) -> &mut Self {
+		self.emitters.push(emitter);
+		self
+	}
+}
+
+impl Default for BulletPattern {
+	fn default() -> Self {
+		BulletPattern::new()
+	}
+}
+
+pub struct BulletPatternComponent {
+	// Fired when the current trajectory segment does not have its own pattern
+	pub pattern: BulletPattern,
+	// Accumulated rotation of the spiral emitters
+	pub rotation_degrees: f32,
+}
+
+impl BulletPatternComponent {
+	pub fn new(pattern: BulletPattern) -> Self {
+		BulletPatternComponent {
+			pattern,
+			rotation_degrees: 0.0
+		}
+	}
+}
+
+impl Default for BulletPatternComponent {
+	fn default() -> Self {
+		BulletPatternComponent::new(BulletPattern::aimed())
+	}
+}
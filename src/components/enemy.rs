@@ -0,0 +1,44 @@
+// Enemies spawned together by a spawner
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Wave {
+	pub id: u32,
+	pub size: u16
+}
+
+// Entity spawned from an enemy type of the registry
+pub struct EnemyComponent {
+	pub enemy_type: String,
+	pub wave: Option<Wave>,
+	// Set by the level spawners, for the phase completion conditions
+	pub tag: Option<String>
+}
+
+impl EnemyComponent {
+	pub fn new(enemy_type: &str) -> Self {
+		EnemyComponent {
+			enemy_type: enemy_type.to_string(),
+			wave: None,
+			tag: None
+		}
+	}
+}
+
+impl Default for EnemyComponent {
+	fn default() -> Self {
+		EnemyComponent::new("")
+	}
+}
+
+
+// An enemy of a wave left the screen without being destroyed
+pub struct EnemyEscapedEvent {
+	pub wave: Wave
+}
+
+// Every enemy of a wave has been destroyed by the players
+pub struct WaveClearedEvent {
+	// Index of the player who destroyed the last enemy, see PlayerComponent
+	pub killer: usize,
+	pub position: (f32, f32),
+	pub size: u16
+}
@@ -1,13 +1,18 @@
 use crate::core::{ecs::EntityId};
 
+use super::enemy::Wave;
+
 pub struct HealthComponent {
-	pub health_points: i64
+	pub health_points: i64,
+	// Index of the player whose shot or bomb hit this entity last, see PlayerComponent
+	pub last_damager: Option<usize>
 }
 
 impl HealthComponent {
 	pub fn new(health_points: i64) -> Self {
 		HealthComponent {
-			health_points: health_points
+			health_points,
+			last_damager: None
 		}
 	}
 }
@@ -19,5 +24,22 @@ impl Default for HealthComponent {
 }
 
 pub struct DeathEvent {
-	pub entity: EntityId
+	pub entity: EntityId,
+	// Index of the player who destroyed the entity : the ship entity changes when it respawns, not the index
+	pub killer: Option<usize>,
+	// Center of the dead entity
+	pub position: (f32, f32),
+	// The dead entity is a player
+	pub player: bool,
+	// Registry type and wave of a dead enemy
+	pub enemy_type: Option<String>,
+	pub wave: Option<Wave>,
+	pub tag: Option<String>
+}
+
+pub struct DamageEvent {
+	pub entity: EntityId,
+	pub damages: i32,
+	// The damaged entity is the player
+	pub player: bool
 }
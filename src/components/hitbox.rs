@@ -1,15 +1,22 @@
 use sdl2::rect::Rect;
 
 pub struct HitboxComponent {
-	pub hitbox: Rect
+	pub hitbox: Rect,
+	// Additional hitboxes of large entities, relative to the entity position like the main one
+	pub parts: Vec<Rect>
 }
 
 impl HitboxComponent {
 	pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
 		HitboxComponent {
-			hitbox: Rect::new(x, y, width, height)
+			hitbox: Rect::new(x, y, width, height),
+			parts: Vec::new()
 		}
 	}
+
+	pub fn add_part(&mut self, x: i32, y: i32, width: u32, height: u32) {
+		self.parts.push(Rect::new(x, y, width, height));
+	}
 }
 
 impl Default for HitboxComponent {
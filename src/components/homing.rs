@@ -0,0 +1,20 @@
+pub struct HomingComponent {
+	pub speed: f32,
+	// Maximum change of direction per frame, in radians
+	pub turn_rate: f32
+}
+
+impl HomingComponent {
+	pub fn new(speed: f32, turn_rate: f32) -> Self {
+		HomingComponent {
+			speed,
+			turn_rate
+		}
+	}
+}
+
+impl Default for HomingComponent {
+	fn default() -> Self {
+		HomingComponent::new(0.0, 0.0)
+	}
+}
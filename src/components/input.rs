@@ -7,7 +7,7 @@ pub enum State {
 	Stand,
 }
 pub enum PlayerInput {
-	LEFT, RIGHT, UP, DOWN, SHOOT, LAST
+	LEFT, RIGHT, UP, DOWN, SHOOT, BOMB, LAST
 }
 
 impl TryFrom<&usize> for PlayerInput {
@@ -20,16 +20,16 @@ impl TryFrom<&usize> for PlayerInput {
 			2 => Ok(PlayerInput::UP),
 			3 => Ok(PlayerInput::DOWN),
 			4 => Ok(PlayerInput::SHOOT),
+			5 => Ok(PlayerInput::BOMB),
 			_ => Err(()),
 		}
 	}
 }
 pub struct InputComponent {
-	pub inputs: [bool; PlayerInput::LAST as usize],
+	// Intensity of each input, from 0.0 (released) to 1.0 (fully pressed, or stick fully tilted)
+	pub inputs: [f32; PlayerInput::LAST as usize],
 	pub keyboard: bool,
 	pub power: f32,
-	pub shot_timer_start: u64,
-	pub shot_frequency_ms: u64,
 	pub state: State,
 	pub last_state: State,
 }
@@ -37,10 +37,8 @@ pub struct InputComponent {
 impl InputComponent {
 	pub fn new(power: f32, keyboard: bool) -> Self {
 		InputComponent {
-			inputs: [false; PlayerInput::LAST as usize],
+			inputs: [0.0; PlayerInput::LAST as usize],
 			power: power,
-			shot_timer_start : 0,
-			shot_frequency_ms: 150,
 			keyboard: keyboard,
 			state: State::Stand,
 			last_state: State::Stand
@@ -0,0 +1,20 @@
+// Shots go through the entity until "end_ms", while its sprite blinks
+pub struct InvulnerabilityComponent {
+	pub end_ms: u64,
+	pub blink_period_ms: u64
+}
+
+impl InvulnerabilityComponent {
+	pub fn new(end_ms: u64, blink_period_ms: u64) -> Self {
+		InvulnerabilityComponent {
+			end_ms,
+			blink_period_ms
+		}
+	}
+}
+
+impl Default for InvulnerabilityComponent {
+	fn default() -> Self {
+		InvulnerabilityComponent::new(0, 100)
+	}
+}
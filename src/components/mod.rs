@@ -9,3 +9,15 @@ pub mod spawner;
 pub mod ai;
 pub mod health;
 pub mod animation;
+
+pub mod player;
+pub mod invulnerability;
+pub mod weapon;
+pub mod homing;
+pub mod powerup;
+pub mod bullet_pattern;
+pub mod bullet;
+pub mod boss;
+pub mod enemy;
+pub mod bomb;
+pub mod path;
\ No newline at end of file
@@ -0,0 +1,181 @@
+use super::ai::DestinationPoint;
+
+// Samples per curve segment of the arc length table
+const ARC_LENGTH_SAMPLES: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+	Linear,
+	// Starts slowly
+	EaseIn,
+	// Ends slowly
+	EaseOut,
+	// Starts and ends slowly
+	EaseInOut
+}
+
+impl Easing {
+	// Maps the traveled ratio of the path (0.0 to 1.0) to the ratio actually reached
+	pub fn apply(&self, t: f32) -> f32 {
+		let t = t.clamp(0.0, 1.0);
+		match self {
+			Easing::Linear => t,
+			Easing::EaseIn => t * t,
+			Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+			Easing::EaseInOut => t * t * (3.0 - 2.0 * t)
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+enum CurveSegment {
+	// Goes from the second point to the third one, the first and last ones giving the tangents
+	CatmullRom([DestinationPoint; 4]),
+	// Goes from the first point to the last one, pulled by the two control points
+	CubicBezier([DestinationPoint; 4])
+}
+
+impl CurveSegment {
+	fn translate(&mut self, offset: (f32, f32)) {
+		let points = match self {
+			CurveSegment::CatmullRom(points) | CurveSegment::CubicBezier(points) => points
+		};
+		for point in points.iter_mut() {
+			point.0 += offset.0;
+			point.1 += offset.1;
+		}
+	}
+
+	fn point(&self, t: f32) -> DestinationPoint {
+		match self {
+			CurveSegment::CatmullRom([p0, p1, p2, p3]) => {
+				let (t2, t3) = (t * t, t * t * t);
+				let axis = |a: f32, b: f32, c: f32, d: f32| 0.5 * (2.0 * b + (-a + c) * t + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2 + (-a + 3.0 * b - 3.0 * c + d) * t3);
+				(axis(p0.0, p1.0, p2.0, p3.0), axis(p0.1, p1.1, p2.1, p3.1))
+			},
+			CurveSegment::CubicBezier([p0, p1, p2, p3]) => {
+				let u = 1.0 - t;
+				let axis = |a: f32, b: f32, c: f32, d: f32| u * u * u * a + 3.0 * u * u * t * b + 3.0 * u * t * t * c + t * t * t * d;
+				(axis(p0.0, p1.0, p2.0, p3.0), axis(p0.1, p1.1, p2.1, p3.1))
+			}
+		}
+	}
+
+	fn tangent(&self, t: f32) -> (f32, f32) {
+		match self {
+			CurveSegment::CatmullRom([p0, p1, p2, p3]) => {
+				let axis = |a: f32, b: f32, c: f32, d: f32| 0.5 * ((-a + c) + 2.0 * (2.0 * a - 5.0 * b + 4.0 * c - d) * t + 3.0 * (-a + 3.0 * b - 3.0 * c + d) * t * t);
+				(axis(p0.0, p1.0, p2.0, p3.0), axis(p0.1, p1.1, p2.1, p3.1))
+			},
+			CurveSegment::CubicBezier([p0, p1, p2, p3]) => {
+				let u = 1.0 - t;
+				let axis = |a: f32, b: f32, c: f32, d: f32| 3.0 * u * u * (b - a) + 6.0 * u * t * (c - b) + 3.0 * t * t * (d - c);
+				(axis(p0.0, p1.0, p2.0, p3.0), axis(p0.1, p1.1, p2.1, p3.1))
+			}
+		}
+	}
+}
+
+// Smooth path followed at a constant speed along the curve (arc length parameterization), then eased
+#[derive(Clone)]
+pub struct Path {
+	segments: Vec<CurveSegment>,
+	// (distance from the path start, segment index, segment parameter), by increasing distance
+	arc_lengths: Vec<(f32, usize, f32)>,
+	pub easing: Easing,
+	// The sprite is rotated to face where it goes
+	pub orient: bool
+}
+
+impl Path {
+	// Curve going through every point
+	pub fn catmull_rom(points: &[DestinationPoint]) -> Result<Self, String> {
+		if points.len() < 2 {
+			return Err(format!("a Catmull-Rom path needs at least 2 points, got {}", points.len()));
+		}
+		// The end points are repeated, so that the curve starts and ends on them
+		let point = |index: isize| points[index.clamp(0, points.len() as isize - 1) as usize];
+		let segments = (0..points.len() as isize - 1)
+			.map(|i| CurveSegment::CatmullRom([point(i - 1), point(i), point(i + 1), point(i + 2)]))
+			.collect();
+		Ok(Self::new(segments))
+	}
+
+	// Chained cubic Bézier curves : start point, then (control, control, end) for each curve
+	pub fn bezier(points: &[DestinationPoint]) -> Result<Self, String> {
+		if points.len() < 4 || !(points.len() - 1).is_multiple_of(3) {
+			return Err(format!("a Bézier path needs 3 points per curve after the start point, got {} points", points.len()));
+		}
+		let segments = points.windows(4).step_by(3)
+			.map(|p| CurveSegment::CubicBezier([p[0], p[1], p[2], p[3]]))
+			.collect();
+		Ok(Self::new(segments))
+	}
+
+	fn new(segments: Vec<CurveSegment>) -> Self {
+		let mut arc_lengths = vec![(0.0, 0, 0.0)];
+		let mut length = 0.0;
+		let mut last_point = segments[0].point(0.0);
+		for (index, segment) in segments.iter().enumerate() {
+			for sample in 1..=ARC_LENGTH_SAMPLES {
+				let t = sample as f32 / ARC_LENGTH_SAMPLES as f32;
+				let point = segment.point(t);
+				length += f32::sqrt((point.0 - last_point.0) * (point.0 - last_point.0) + (point.1 - last_point.1) * (point.1 - last_point.1));
+				arc_lengths.push((length, index, t));
+				last_point = point;
+			}
+		}
+		Path {
+			segments,
+			arc_lengths,
+			easing: Easing::Linear,
+			orient: false
+		}
+	}
+
+	pub fn easing(mut self, easing: Easing) -> Self {
+		self.easing = easing;
+		self
+	}
+
+	pub fn oriented(mut self) -> Self {
+		self.orient = true;
+		self
+	}
+
+	pub fn length(&self) -> f32 {
+		self.arc_lengths.last().unwrap().0
+	}
+
+	// Moving the path keeps its arc lengths
+	pub fn translate(&mut self, offset: (f32, f32)) {
+		for segment in self.segments.iter_mut() {
+			segment.translate(offset);
+		}
+	}
+
+	pub fn start(&self) -> DestinationPoint {
+		self.segments[0].point(0.0)
+	}
+
+	// (segment index, segment parameter) at "distance" from the path start
+	fn locate(&self, distance: f32) -> (usize, f32) {
+		let distance = distance.clamp(0.0, self.length());
+		let next = self.arc_lengths.iter().position(|(length, _, _)| *length >= distance).unwrap_or(self.arc_lengths.len() - 1).max(1);
+		let (start_length, _, start_t) = self.arc_lengths[next - 1];
+		let (end_length, segment, end_t) = self.arc_lengths[next];
+		// The previous sample may belong to the previous segment
+		let start_t = if self.arc_lengths[next - 1].1 == segment { start_t } else { 0.0 };
+		let ratio = if end_length > start_length { (distance - start_length) / (end_length - start_length) } else { 0.0 };
+		(segment, start_t + (end_t - start_t) * ratio)
+	}
+
+	// Position and heading (in degrees, 0 towards the right and 90 towards the bottom) once "traveled" pixels are covered
+	pub fn sample(&self, traveled: f32) -> (DestinationPoint, f32) {
+		let length = self.length();
+		let distance = if length > 0.0 { self.easing.apply(traveled / length) * length } else { 0.0 };
+		let (segment, t) = self.locate(distance);
+		let tangent = self.segments[segment].tangent(t);
+		(self.segments[segment].point(t), f32::atan2(tangent.1, tangent.0).to_degrees())
+	}
+}
@@ -0,0 +1,19 @@
+// Marks the entities controlled by a local player
+pub struct PlayerComponent {
+	// 0 for the first player
+	pub index: usize
+}
+
+impl PlayerComponent {
+	pub fn new(index: usize) -> Self {
+		PlayerComponent {
+			index
+		}
+	}
+}
+
+impl Default for PlayerComponent {
+	fn default() -> Self {
+		PlayerComponent::new(0)
+	}
+}
@@ -0,0 +1,37 @@
+use super::weapon::WeaponType;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+	Weapon(WeaponType),
+	// One more bomb in stock
+	Bomb
+}
+
+impl PowerUpKind {
+	// Frame in the "powerup" texture : the weapon types first, then the bomb
+	pub fn frame(&self) -> usize {
+		match self {
+			PowerUpKind::Weapon(weapon_type) => WeaponType::ALL.iter().position(|t| t == weapon_type).unwrap(),
+			PowerUpKind::Bomb => WeaponType::ALL.len()
+		}
+	}
+}
+
+// Item picked by the players
+pub struct PowerUpComponent {
+	pub kind: PowerUpKind
+}
+
+impl PowerUpComponent {
+	pub fn new(kind: PowerUpKind) -> Self {
+		PowerUpComponent {
+			kind
+		}
+	}
+}
+
+impl Default for PowerUpComponent {
+	fn default() -> Self {
+		PowerUpComponent::new(PowerUpKind::Weapon(WeaponType::Single))
+	}
+}
@@ -1,4 +1,7 @@
 
+use crate::core::ecs::EntityId;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ShotType {
 	PLAYER,
 	ENEMY
@@ -6,20 +9,36 @@ pub enum ShotType {
 
 pub struct ShotComponent {
 	pub shot_type: ShotType,
-	pub damages: i32
+	pub damages: i32,
+	// Entity that fired the shot
+	pub owner: EntityId,
+	// Index of the player who fired the shot, see PlayerComponent
+	pub owner_player: Option<usize>,
+	// A piercing shot is not destroyed on hit, and hits each entity only once
+	pub piercing: bool,
+	pub hit_entities: Vec<EntityId>
 }
 
 impl ShotComponent {
-	pub fn new(shot_type: ShotType, damages: i32) -> Self {
+	pub fn new(shot_type: ShotType, damages: i32, owner: EntityId) -> Self {
 		ShotComponent {
 			shot_type: shot_type,
-			damages: damages
+			damages,
+			owner,
+			owner_player: None,
+			piercing: false,
+			hit_entities: Vec::new()
 		}
 	}
 }
 
 impl Default for ShotComponent {
 	fn default() -> Self {
-		ShotComponent::new(ShotType::PLAYER, 0)
+		ShotComponent::new(ShotType::PLAYER, 0, 0)
 	}
 }
+
+pub struct ShotEvent {
+	pub entity: EntityId,
+	pub shot_type: ShotType
+}
\ No newline at end of file
@@ -1,10 +1,58 @@
-use crate::levels::phases::TrajectoryType;
+use crate::{components::enemy::Wave, levels::phases::TrajectoryType};
 
+pub const DEFAULT_ENEMY_TYPE: &str = "invader_1";
+pub const DEFAULT_FORMATION_SPACING: f32 = 60.0;
+pub const DEFAULT_FORMATION_COLUMNS: u16 = 4;
+pub const DEFAULT_TRAIN_DELAY_MS: u64 = 400;
+// Delay between two enemies of a point or circle spawn, at propulsion 1.0
+const POINT_SPAWN_DELAY_MS: f32 = 300.0;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SpawnerType {
+	// Around the spawner, leaving one after another
 	CIRCLE,
+	// On the spawner, leaving one after another
 	POINT,
-	LINEAR
+	// Horizontal line, moving together
+	LINEAR,
+	// Rows of "columns" enemies, moving together
+	GRID,
+	// V shape behind a leader, moving together
+	VSHAPE,
+	// Each enemy follows the previous one "train_delay_ms" later
+	SNAKE
+}
+
+impl SpawnerType {
+	pub fn from_name(name: &str) -> Result<Self, String> {
+		match name {
+			"point" => Ok(SpawnerType::POINT),
+			"circle" => Ok(SpawnerType::CIRCLE),
+			"line" => Ok(SpawnerType::LINEAR),
+			"grid" => Ok(SpawnerType::GRID),
+			"vshape" => Ok(SpawnerType::VSHAPE),
+			"snake" => Ok(SpawnerType::SNAKE),
+			_ => Err(format!("unknown formation \"{}\", expected point, circle, line, grid, vshape or snake", name))
+		}
+	}
+}
+
+// Place of one enemy in a spawned formation
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FormationSlot {
+	// Spawn position, from the spawner origin
+	pub spawn_offset: (f32, f32),
+	// Applied to the whole trajectory, so that the formation keeps its shape while moving
+	pub trajectory_offset: (f32, f32),
+	pub delay_ms: u64
+}
+
+// Enemy of a spawn waiting for its delay to expire, see SpawnerComponent::delays_spawns
+pub struct PendingEnemy {
+	pub spawn_ms: u64,
+	pub position: (f32, f32),
+	pub trajectory_offset: (f32, f32),
+	pub wave: Wave
 }
 
 pub struct SpawnerComponent {
@@ -19,7 +67,22 @@ pub struct SpawnerComponent {
 	pub propulsion: f32,
 	pub max_angle: f32,
 	pub randomize_pos: bool,
-	pub symetric_alternate_pos: bool
+	// Every other enemy of a spawn is mirrored horizontally on the screen
+	pub symetric_alternate_pos: bool,
+	// Every other spawn is mirrored horizontally on the screen, its whole formation included
+	pub mirror_alternate_spawns: bool,
+	// Formations spawned so far
+	pub spawns: u32,
+	pub pending: Vec<PendingEnemy>,
+	// Distance between two enemies of a line, grid or V shape
+	pub spacing: f32,
+	// Enemies per row of a grid
+	pub columns: u16,
+	pub train_delay_ms: u64,
+	// Id in the enemy types registry
+	pub enemy_type: String,
+	// Given to the spawned enemies
+	pub tag: Option<String>
 }
 
 impl SpawnerComponent {
@@ -34,11 +97,59 @@ impl SpawnerComponent {
 			max_angle: max_angle,
 			randomize_pos: false,
 			symetric_alternate_pos: false,
+			mirror_alternate_spawns: false,
+			spawns: 0,
+			pending: Vec::new(),
+			spacing: DEFAULT_FORMATION_SPACING,
+			columns: DEFAULT_FORMATION_COLUMNS,
+			train_delay_ms: DEFAULT_TRAIN_DELAY_MS,
 			countdown: usize::MAX,
 			shot_frequency_factor: 1.0,
-			trajectory_type: trajectory_type
+			trajectory_type,
+			enemy_type: DEFAULT_ENEMY_TYPE.to_string(),
+			tag: None
 		}
 	}
+
+	// The enemies of a snake share their spawn position : instead of waiting on top of each other, each one is spawned once its delay expired
+	pub fn delays_spawns(&self) -> bool {
+		self.spawner_type == SpawnerType::SNAKE
+	}
+
+	// One slot per enemy, "area_offset" being the distance from the origin of the point and circle spawns
+	pub fn formation(&self, area_offset: (f32, f32)) -> Vec<FormationSlot> {
+		let point_delay_ms = |index: u16| (index as f32 * POINT_SPAWN_DELAY_MS / self.propulsion) as u64;
+		// Centers "count" enemies spaced along an axis
+		let centered = |index: u16, count: u16| (index as f32 - (count as f32 - 1.0) / 2.0) * self.spacing;
+		(0..self.num).map(|index| {
+			match self.spawner_type {
+				SpawnerType::POINT => FormationSlot { spawn_offset: (area_offset.0, 0.0), trajectory_offset: (0.0, 0.0), delay_ms: point_delay_ms(index) },
+				SpawnerType::CIRCLE => {
+					let angle = (index as f32 * self.max_angle / self.num as f32) % (2.0 * std::f32::consts::PI);
+					FormationSlot { spawn_offset: (angle.cos() * area_offset.0, angle.sin() * area_offset.1), trajectory_offset: (0.0, 0.0), delay_ms: point_delay_ms(index) }
+				},
+				SpawnerType::LINEAR => {
+					let offset = (centered(index, self.num), 0.0);
+					FormationSlot { spawn_offset: offset, trajectory_offset: offset, delay_ms: 0 }
+				},
+				SpawnerType::GRID => {
+					let columns = self.columns.max(1).min(self.num);
+					let rows = self.num.div_ceil(columns);
+					// The first row leads, the next ones are above it
+					let offset = (centered(index % columns, columns), centered(rows - 1 - index / columns, rows));
+					FormationSlot { spawn_offset: offset, trajectory_offset: offset, delay_ms: 0 }
+				},
+				SpawnerType::VSHAPE => {
+					// Leader at the tip, then alternately on the left and right wings
+					let rank = index.div_ceil(2) as f32;
+					let side = if index % 2 == 1 { -1.0 } else { 1.0 };
+					let offset = (side * rank * self.spacing, - rank * self.spacing);
+					FormationSlot { spawn_offset: offset, trajectory_offset: offset, delay_ms: 0 }
+				},
+				SpawnerType::SNAKE => FormationSlot { spawn_offset: (0.0, 0.0), trajectory_offset: (0.0, 0.0), delay_ms: index as u64 * self.train_delay_ms }
+			}
+		}).collect()
+	}
 }
 
 impl Default for SpawnerComponent {
@@ -46,3 +157,86 @@ impl Default for SpawnerComponent {
 		SpawnerComponent::new(SpawnerType::POINT, 0, 0.0, 0, 0.0, std::f32::consts::PI * 2.0, TrajectoryType::BasicLinear)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn spawner(spawner_type: SpawnerType, num: u16) -> SpawnerComponent {
+		let mut spawner = SpawnerComponent::new(spawner_type, 1000, 100.0, num, 2.0, std::f32::consts::PI * 2.0, TrajectoryType::BasicLinear);
+		spawner.spacing = 10.0;
+		spawner
+	}
+
+	fn offsets(slots: &[FormationSlot]) -> Vec<(f32, f32)> {
+		slots.iter().map(|slot| slot.spawn_offset).collect()
+	}
+
+	fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+		assert!((actual.0 - expected.0).abs() < 0.001 && (actual.1 - expected.1).abs() < 0.001, "{:?} != {:?}", actual, expected);
+	}
+
+	#[test]
+	fn point_spawns_one_after_another() {
+		let slots = spawner(SpawnerType::POINT, 3).formation((40.0, 20.0));
+		assert_eq!(offsets(&slots), vec![(40.0, 0.0); 3]);
+		assert_eq!(slots.iter().map(|slot| slot.delay_ms).collect::<Vec<u64>>(), vec![0, 150, 300]);
+		assert!(slots.iter().all(|slot| slot.trajectory_offset == (0.0, 0.0)));
+	}
+
+	#[test]
+	fn circle_spreads_around_the_origin() {
+		let slots = spawner(SpawnerType::CIRCLE, 4).formation((40.0, 20.0));
+		for (slot, expected) in slots.iter().zip(&[(40.0, 0.0), (0.0, 20.0), (-40.0, 0.0), (0.0, -20.0)]) {
+			assert_near(slot.spawn_offset, *expected);
+			assert_eq!(slot.trajectory_offset, (0.0, 0.0));
+		}
+		assert_eq!(slots.iter().map(|slot| slot.delay_ms).collect::<Vec<u64>>(), vec![0, 150, 300, 450]);
+	}
+
+	#[test]
+	fn line_is_centered_on_the_origin() {
+		let slots = spawner(SpawnerType::LINEAR, 3).formation((0.0, 0.0));
+		assert_eq!(offsets(&slots), vec![(-10.0, 0.0), (0.0, 0.0), (10.0, 0.0)]);
+		assert!(slots.iter().all(|slot| slot.trajectory_offset == slot.spawn_offset && slot.delay_ms == 0));
+
+		let slots = spawner(SpawnerType::LINEAR, 2).formation((0.0, 0.0));
+		assert_eq!(offsets(&slots), vec![(-5.0, 0.0), (5.0, 0.0)]);
+	}
+
+	#[test]
+	fn grid_with_more_columns_than_enemies_is_a_line() {
+		let mut spawner = spawner(SpawnerType::GRID, 3);
+		spawner.columns = 8;
+		assert_eq!(offsets(&spawner.formation((0.0, 0.0))), vec![(-10.0, 0.0), (0.0, 0.0), (10.0, 0.0)]);
+	}
+
+	#[test]
+	fn grid_fills_the_last_row_partially() {
+		let mut spawner = spawner(SpawnerType::GRID, 5);
+		spawner.columns = 3;
+		let slots = spawner.formation((0.0, 0.0));
+		// The first row leads, below the second one
+		assert_eq!(offsets(&slots), vec![(-10.0, 5.0), (0.0, 5.0), (10.0, 5.0), (-10.0, -5.0), (0.0, -5.0)]);
+		assert!(slots.iter().all(|slot| slot.trajectory_offset == slot.spawn_offset && slot.delay_ms == 0));
+	}
+
+	#[test]
+	fn vshape_alternates_wings_behind_the_leader() {
+		let slots = spawner(SpawnerType::VSHAPE, 5).formation((0.0, 0.0));
+		assert_eq!(offsets(&slots), vec![(0.0, 0.0), (-10.0, -10.0), (10.0, -10.0), (-20.0, -20.0), (20.0, -20.0)]);
+		assert!(slots.iter().all(|slot| slot.trajectory_offset == slot.spawn_offset && slot.delay_ms == 0));
+	}
+
+	#[test]
+	fn snake_delays_each_enemy() {
+		let mut spawner = spawner(SpawnerType::SNAKE, 4);
+		spawner.train_delay_ms = 250;
+		let slots = spawner.formation((40.0, 20.0));
+		assert_eq!(slots.iter().map(|slot| slot.delay_ms).collect::<Vec<u64>>(), vec![0, 250, 500, 750]);
+		assert!(slots.iter().all(|slot| slot.spawn_offset == (0.0, 0.0) && slot.trajectory_offset == (0.0, 0.0)));
+		// Sharing their spawn position, they are spawned one after another
+		assert!(spawner.delays_spawns());
+		assert!(!self::spawner(SpawnerType::POINT, 4).delays_spawns());
+	}
+}
@@ -1,5 +1,7 @@
 use sdl2::rect::Rect;
 
+use crate::core::manifest::SpritesheetGrid;
+
 #[derive(PartialEq, Eq, Hash)]
 pub enum SpritesheetOrientation {
 	HORIZONTAL,
@@ -26,12 +28,21 @@ impl Spritesheet {
 	}
 }
 
+impl From<SpritesheetGrid> for Spritesheet {
+	fn from(grid: SpritesheetGrid) -> Self {
+		Spritesheet::new(grid.num_width, grid.num_height, SpritesheetOrientation::HORIZONTAL, grid.width, grid.height)
+	}
+}
+
 pub struct SpriteComponent {
 	pub sprite: i64,
 	pub graphic_box: Rect,
 	pub spritesheet: Option<Spritesheet>,
 	pub spritesheet_index: (usize, usize),
 	pub z: i64,
+	pub visible: bool,
+	// Clockwise rotation around the center
+	pub angle_degrees: u64,
 }
 
 impl Default for SpriteComponent {
@@ -42,6 +53,8 @@ impl Default for SpriteComponent {
 			graphic_box: Rect::new(0,0, 1, 1),
 			spritesheet_index: (0, 0),
 			spritesheet: None,
+			visible: true,
+			angle_degrees: 0,
 		}
 	}
 }
@@ -53,7 +66,9 @@ impl SpriteComponent {
 			z: z,
 			graphic_box: Rect::new(0,0, width, height),
 			spritesheet: None,
-			spritesheet_index: (0, 0)
+			spritesheet_index: (0, 0),
+			visible: true,
+			angle_degrees: 0
 		}
 	}
 }
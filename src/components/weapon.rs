@@ -0,0 +1,149 @@
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WeaponType {
+	Single,
+	Double,
+	Spread,
+	Rapid,
+	Laser,
+	Homing
+}
+
+impl WeaponType {
+	pub const ALL: [WeaponType; 6] = [WeaponType::Single, WeaponType::Double, WeaponType::Spread, WeaponType::Rapid, WeaponType::Laser, WeaponType::Homing];
+}
+
+// What a weapon fires
+pub struct ProjectilePrefab {
+	pub texture_id: &'static str,
+	pub width: u32,
+	pub height: u32,
+	pub speed: f32,
+	pub lifetime_ms: u64,
+	// Goes through the enemies instead of stopping at the first one
+	pub piercing: bool,
+	// Steers towards the closest enemy
+	pub homing: bool,
+}
+
+pub struct WeaponTier {
+	pub fire_rate_ms: u64,
+	pub damages: i32,
+	pub projectiles: u32,
+	// Angle of the fan formed by the projectiles, side by side when 0
+	pub spread_degrees: f32,
+}
+
+pub struct WeaponDefinition {
+	pub projectile: ProjectilePrefab,
+	// One tier per weapon level
+	pub tiers: &'static [WeaponTier],
+}
+
+const SHOT: ProjectilePrefab = ProjectilePrefab { texture_id: "shot", width: 16, height: 32, speed: 20.0, lifetime_ms: 2000, piercing: false, homing: false };
+
+const SINGLE: WeaponDefinition = WeaponDefinition {
+	projectile: SHOT,
+	tiers: &[
+		WeaponTier { fire_rate_ms: 150, damages: 1, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 130, damages: 2, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 110, damages: 3, projectiles: 1, spread_degrees: 0.0 },
+	]
+};
+
+const DOUBLE: WeaponDefinition = WeaponDefinition {
+	projectile: SHOT,
+	tiers: &[
+		WeaponTier { fire_rate_ms: 170, damages: 1, projectiles: 2, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 160, damages: 1, projectiles: 3, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 150, damages: 2, projectiles: 4, spread_degrees: 0.0 },
+	]
+};
+
+const SPREAD: WeaponDefinition = WeaponDefinition {
+	projectile: SHOT,
+	tiers: &[
+		WeaponTier { fire_rate_ms: 200, damages: 1, projectiles: 3, spread_degrees: 30.0 },
+		WeaponTier { fire_rate_ms: 190, damages: 1, projectiles: 5, spread_degrees: 45.0 },
+		WeaponTier { fire_rate_ms: 180, damages: 2, projectiles: 7, spread_degrees: 60.0 },
+	]
+};
+
+const RAPID: WeaponDefinition = WeaponDefinition {
+	projectile: SHOT,
+	tiers: &[
+		WeaponTier { fire_rate_ms: 70, damages: 1, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 55, damages: 1, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 45, damages: 1, projectiles: 2, spread_degrees: 0.0 },
+	]
+};
+
+const LASER: WeaponDefinition = WeaponDefinition {
+	projectile: ProjectilePrefab { texture_id: "laser", width: 16, height: 64, speed: 28.0, lifetime_ms: 1500, piercing: true, homing: false },
+	tiers: &[
+		WeaponTier { fire_rate_ms: 250, damages: 2, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 220, damages: 3, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 190, damages: 4, projectiles: 1, spread_degrees: 0.0 },
+	]
+};
+
+const HOMING: WeaponDefinition = WeaponDefinition {
+	projectile: ProjectilePrefab { texture_id: "missile", width: 24, height: 24, speed: 12.0, lifetime_ms: 3000, piercing: false, homing: true },
+	tiers: &[
+		WeaponTier { fire_rate_ms: 300, damages: 2, projectiles: 1, spread_degrees: 0.0 },
+		WeaponTier { fire_rate_ms: 260, damages: 2, projectiles: 2, spread_degrees: 40.0 },
+		WeaponTier { fire_rate_ms: 220, damages: 3, projectiles: 3, spread_degrees: 60.0 },
+	]
+};
+
+pub fn weapon_definition(weapon_type: WeaponType) -> &'static WeaponDefinition {
+	match weapon_type {
+		WeaponType::Single => &SINGLE,
+		WeaponType::Double => &DOUBLE,
+		WeaponType::Spread => &SPREAD,
+		WeaponType::Rapid => &RAPID,
+		WeaponType::Laser => &LASER,
+		WeaponType::Homing => &HOMING,
+	}
+}
+
+#[derive(Clone, Copy)]
+pub struct WeaponComponent {
+	pub weapon_type: WeaponType,
+	// Index of the current tier
+	pub level: usize,
+	pub shot_timer_start: u64,
+}
+
+impl WeaponComponent {
+	pub fn new(weapon_type: WeaponType) -> Self {
+		WeaponComponent {
+			weapon_type,
+			level: 0,
+			shot_timer_start: 0
+		}
+	}
+
+	pub fn definition(&self) -> &'static WeaponDefinition {
+		weapon_definition(self.weapon_type)
+	}
+
+	pub fn tier(&self) -> &'static WeaponTier {
+		&self.definition().tiers[self.level]
+	}
+
+	// Picking the current weapon levels it up, picking another one swaps to it and keeps the level
+	pub fn pick(&mut self, weapon_type: WeaponType) {
+		if self.weapon_type == weapon_type {
+			self.level = (self.level + 1).min(self.definition().tiers.len() - 1);
+		} else {
+			self.weapon_type = weapon_type;
+			self.level = self.level.min(self.definition().tiers.len() - 1);
+		}
+	}
+}
+
+impl Default for WeaponComponent {
+	fn default() -> Self {
+		WeaponComponent::new(WeaponType::Single)
+	}
+}
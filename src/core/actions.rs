@@ -0,0 +1,522 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use sdl2::{controller::{Axis, Button}, event::Event, keyboard::Keycode};
+
+// User configuration, read from the working directory
+pub const ACTION_MAP_FILE: &str = "input.cfg";
+// Game controller buttons are prefixed in the configuration file, e.g. "pad:a"
+const BUTTON_PREFIX: &str = "pad:";
+const DEFAULT_DEADZONE: f32 = 0.25;
+// The stick amplitude is rescaled over what lies past the deadzone, some has to be left
+const MAX_DEADZONE: f32 = 0.95;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Action {
+	MoveLeft,
+	MoveRight,
+	MoveUp,
+	MoveDown,
+	Shoot,
+	Bomb,
+	Pause,
+	Confirm,
+	Quit
+}
+
+const ACTIONS: [(Action, &str); 9] = [
+	(Action::MoveLeft, "move_left"),
+	(Action::MoveRight, "move_right"),
+	(Action::MoveUp, "move_up"),
+	(Action::MoveDown, "move_down"),
+	(Action::Shoot, "shoot"),
+	(Action::Bomb, "bomb"),
+	(Action::Pause, "pause"),
+	(Action::Confirm, "confirm"),
+	(Action::Quit, "quit"),
+];
+
+impl Action {
+	pub fn name(&self) -> &'static str {
+		ACTIONS.iter().find(|(action, _)| action == self).unwrap().1
+	}
+
+	pub fn from_name(name: &str) -> Option<Action> {
+		ACTIONS.iter().find(|(_, action_name)| *action_name == name).map(|(action, _)| *action)
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ActionEvent {
+	// Index of the player whose bindings triggered the action, 0 for the first player
+	pub player: usize,
+	pub action: Action,
+	pub pressed: bool,
+	// Intensity between 0.0 (released) and 1.0, only analog sticks give values in between
+	pub value: f32
+}
+
+impl ActionEvent {
+	pub fn new(player: usize, action: Action, pressed: bool) -> Self {
+		ActionEvent {
+			player,
+			action,
+			pressed,
+			value: if pressed { 1.0 } else { 0.0 }
+		}
+	}
+
+	pub fn analog(player: usize, action: Action, value: f32) -> Self {
+		ActionEvent {
+			player,
+			action,
+			pressed: value > 0.0,
+			value
+		}
+	}
+}
+
+// What holds an action down, a key being shared by every player
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum InputSource {
+	Key(Keycode),
+	// Controller slot and button
+	Button(usize, Button),
+	Axis(usize, Axis)
+}
+
+// Keys, buttons and game controllers of one player
+pub struct PlayerBindings {
+	keys: HashMap<Keycode, Vec<Action>>,
+	buttons: HashMap<Button, Vec<Action>>,
+	// Slots of the game controllers driving this player, 0 being the first controller plugged
+	controllers: Vec<usize>
+}
+
+impl PlayerBindings {
+	pub fn new() -> Self {
+		PlayerBindings {
+			keys: HashMap::new(),
+			buttons: HashMap::new(),
+			controllers: Vec::new()
+		}
+	}
+
+	pub fn bind(&mut self, action: Action, key: Keycode) {
+		let actions = self.keys.entry(key).or_default();
+		if !actions.contains(&action) {
+			actions.push(action);
+		}
+	}
+
+	pub fn unbind(&mut self, action: Action, key: Keycode) {
+		if let Some(actions) = self.keys.get_mut(&key) {
+			actions.retain(|bound| *bound != action);
+		}
+	}
+
+	// Replaces every key of the action by this one, its buttons are kept
+	pub fn rebind(&mut self, action: Action, key: Keycode) {
+		for key in self.keys(action) {
+			self.unbind(action, key);
+		}
+		self.bind(action, key);
+	}
+
+	// Keys of the action, sorted so that saving gives the same file for the same bindings
+	pub fn keys(&self, action: Action) -> Vec<Keycode> {
+		let mut keys: Vec<Keycode> = self.keys.iter().filter(|(_, actions)| actions.contains(&action)).map(|(key, _)| *key).collect();
+		keys.sort_by_key(|key| *key as i32);
+		keys
+	}
+
+	// Removes every key and button of the action
+	pub fn unbind_all(&mut self, action: Action) {
+		for actions in self.keys.values_mut().chain(self.buttons.values_mut()) {
+			actions.retain(|bound| *bound != action);
+		}
+	}
+
+	pub fn bind_button(&mut self, action: Action, button: Button) {
+		let actions = self.buttons.entry(button).or_default();
+		if !actions.contains(&action) {
+			actions.push(action);
+		}
+	}
+
+	pub fn unbind_button(&mut self, action: Action, button: Button) {
+		if let Some(actions) = self.buttons.get_mut(&button) {
+			actions.retain(|bound| *bound != action);
+		}
+	}
+
+	pub fn buttons(&self, action: Action) -> Vec<Button> {
+		let mut buttons: Vec<Button> = self.buttons.iter().filter(|(_, actions)| actions.contains(&action)).map(|(button, _)| *button).collect();
+		buttons.sort_by_key(|button| *button as i32);
+		buttons
+	}
+
+	pub fn controllers(&self) -> &[usize] {
+		&self.controllers
+	}
+
+	pub fn set_controllers(&mut self, controllers: Vec<usize>) {
+		self.controllers = controllers;
+	}
+
+	fn bind_default_buttons(&mut self) {
+		self.bind_button(Action::MoveLeft, Button::DPadLeft);
+		self.bind_button(Action::MoveRight, Button::DPadRight);
+		self.bind_button(Action::MoveUp, Button::DPadUp);
+		self.bind_button(Action::MoveDown, Button::DPadDown);
+		self.bind_button(Action::Shoot, Button::A);
+		self.bind_button(Action::Bomb, Button::B);
+		self.bind_button(Action::Confirm, Button::A);
+		self.bind_button(Action::Pause, Button::Start);
+		self.bind_button(Action::Confirm, Button::Start);
+	}
+
+	// Return both pauses and confirms, Space and the A button both shoot and confirm :
+	// a state must not listen to both actions of these keys, except the pause menu where either one resumes
+	fn bind_menu_keys(&mut self) {
+		self.bind(Action::Pause, Keycode::Return);
+		self.bind(Action::Pause, Keycode::P);
+		self.bind(Action::Confirm, Keycode::Space);
+		self.bind(Action::Confirm, Keycode::Return);
+		self.bind(Action::Quit, Keycode::Escape);
+	}
+}
+
+impl Default for PlayerBindings {
+	fn default() -> Self {
+		PlayerBindings::new()
+	}
+}
+
+/*
+	Maps the raw device events to actions of each player.
+	Bindings are listed under a "[player <number>]" header (lines before the first header are for the first player),
+	one action per line : "<action> <key name> [<key name> ...]"
+	Each player starts from the default bindings, a listed action replaces every key and button of that action only.
+	Key names are the SDL ones ("Left", "Space", "Return", "A"...), game controller buttons are the SDL ones
+	prefixed by "pad:" ("pad:a", "pad:start", "pad:dpleft"...). A key can trigger several actions
+	(e.g. Space is both "shoot" and "confirm"), the current state picks the ones it cares about.
+	"controllers <slot> [<slot> ...]" sets the game controllers of the player, 0 being the first plugged one.
+	The left stick always moves, "deadzone <0.0..0.95>" sets the stick amplitude ignored around its center, for every player.
+	Empty lines and lines starting with '#' are ignored.
+*/
+pub struct ActionMap {
+	players: Vec<PlayerBindings>,
+	deadzone: f32,
+	// Value of every source holding each action of each player (stick values after the deadzone is applied)
+	held: HashMap<(usize, Action), HashMap<InputSource, f32>>
+}
+
+impl ActionMap {
+	pub fn new() -> Self {
+		ActionMap {
+			players: Vec::new(),
+			deadzone: DEFAULT_DEADZONE,
+			held: HashMap::new()
+		}
+	}
+
+	// Alone, the player gets the arrows, WASD and ZQSD (so that both QWERTY and AZERTY keyboards work out of the box) and every controller.
+	// With several players, the first one gets WASD / ZQSD, the second one the arrows, and each one its own controller.
+	pub fn default_bindings(players: usize) -> Self {
+		let mut map = ActionMap::new();
+		for index in 0..players.max(1) {
+			map.players.push(Self::default_player_bindings(index, players));
+		}
+		map
+	}
+
+	fn default_player_bindings(index: usize, players: usize) -> PlayerBindings {
+		let mut bindings = PlayerBindings::new();
+		bindings.bind_default_buttons();
+		if players <= 1 {
+			for key in &[Keycode::Left, Keycode::A, Keycode::Q] { bindings.bind(Action::MoveLeft, *key); }
+			for key in &[Keycode::Right, Keycode::D] { bindings.bind(Action::MoveRight, *key); }
+			for key in &[Keycode::Up, Keycode::W, Keycode::Z] { bindings.bind(Action::MoveUp, *key); }
+			for key in &[Keycode::Down, Keycode::S] { bindings.bind(Action::MoveDown, *key); }
+			bindings.bind(Action::Shoot, Keycode::Space);
+			for key in &[Keycode::B, Keycode::LShift] { bindings.bind(Action::Bomb, *key); }
+			bindings.bind_menu_keys();
+			bindings.set_controllers(vec![0, 1, 2, 3]);
+			return bindings;
+		}
+
+		match index {
+			0 => {
+				for key in &[Keycode::A, Keycode::Q] { bindings.bind(Action::MoveLeft, *key); }
+				bindings.bind(Action::MoveRight, Keycode::D);
+				for key in &[Keycode::W, Keycode::Z] { bindings.bind(Action::MoveUp, *key); }
+				bindings.bind(Action::MoveDown, Keycode::S);
+				bindings.bind(Action::Shoot, Keycode::Space);
+				bindings.bind(Action::Bomb, Keycode::LShift);
+				bindings.bind_menu_keys();
+			},
+			1 => {
+				bindings.bind(Action::MoveLeft, Keycode::Left);
+				bindings.bind(Action::MoveRight, Keycode::Right);
+				bindings.bind(Action::MoveUp, Keycode::Up);
+				bindings.bind(Action::MoveDown, Keycode::Down);
+				bindings.bind(Action::Shoot, Keycode::RCtrl);
+				bindings.bind(Action::Bomb, Keycode::RShift);
+			},
+			_ => {}
+		}
+		bindings.set_controllers(vec![index]);
+		bindings
+	}
+
+	// The default bindings are used when the file does not exist, or for the actions it does not list
+	pub fn load(filename: &Path, players: usize) -> Result<Self, String> {
+		if !filename.is_file() {
+			return Ok(Self::default_bindings(players));
+		}
+		let content = fs::read_to_string(filename).map_err(|e| format!("{} : {}", filename.display(), e))?;
+		Self::parse(&content, players).map_err(|e| format!("{}:{}", filename.display(), e))
+	}
+
+	fn parse_player_header(line: &str) -> Option<Result<usize, String>> {
+		if !line.starts_with('[') {
+			return None;
+		}
+		let player = line.trim_start_matches('[').trim_end_matches(']').trim();
+		let number = player.strip_prefix("player").map(str::trim).and_then(|number| number.parse::<usize>().ok()).filter(|number| *number > 0);
+		Some(number.map(|number| number - 1).ok_or_else(|| format!("invalid section \"{}\", expected [player <number>]", line)))
+	}
+
+	pub fn parse(content: &str, players: usize) -> Result<Self, String> {
+		let mut map = Self::default_bindings(players);
+		let mut player = 0;
+		for (line_index, line) in content.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			if let Some(header) = Self::parse_player_header(line) {
+				player = header.map_err(|e| format!("{}: {}", line_index + 1, e))?;
+				continue;
+			}
+			map.parse_line(player, line).map_err(|e| format!("{}: {}", line_index + 1, e))?;
+		}
+		Ok(map)
+	}
+
+	fn parse_line(&mut self, player: usize, line: &str) -> Result<(), String> {
+		let mut tokens = line.split_whitespace();
+		let action_name = tokens.next().unwrap();
+		if action_name == "deadzone" {
+			let deadzone = tokens.next().unwrap_or("");
+			self.deadzone = deadzone.parse::<f32>().ok().filter(|deadzone| (0.0..=MAX_DEADZONE).contains(deadzone))
+				.ok_or_else(|| format!("invalid deadzone \"{}\", expected a number between 0.0 and {}", deadzone, MAX_DEADZONE))?;
+			return Ok(());
+		}
+
+		let bindings = self.player_mut(player);
+		if action_name == "controllers" {
+			let controllers: Result<Vec<usize>, String> = tokens.map(|slot| slot.parse::<usize>().map_err(|_| format!("invalid controller slot \"{}\"", slot))).collect();
+			bindings.set_controllers(controllers?);
+			return Ok(());
+		}
+
+		let action = Action::from_name(action_name).ok_or_else(|| format!("unknown action \"{}\"", action_name))?;
+		bindings.unbind_all(action);
+		for key_name in tokens {
+			if let Some(button_name) = key_name.strip_prefix(BUTTON_PREFIX) {
+				let button = Button::from_string(button_name).ok_or_else(|| format!("unknown button \"{}\"", key_name))?;
+				bindings.bind_button(action, button);
+			} else {
+				let key = Keycode::from_name(key_name).ok_or_else(|| format!("unknown key \"{}\"", key_name))?;
+				bindings.bind(action, key);
+			}
+		}
+		Ok(())
+	}
+
+	// Writes every binding of every player, in the format read by "load"
+	pub fn save(&self, filename: &Path) -> Result<(), String> {
+		fs::write(filename, self.to_cfg()).map_err(|e| format!("{} : {}", filename.display(), e))
+	}
+
+	fn to_cfg(&self) -> String {
+		let mut content = format!("deadzone {}\n", self.deadzone);
+		for (index, bindings) in self.players.iter().enumerate() {
+			content.push_str(&format!("\n[player {}]\n", index + 1));
+			for (action, name) in ACTIONS.iter() {
+				let mut keys: Vec<String> = bindings.keys(*action).iter().map(|key| key.name()).collect();
+				keys.extend(bindings.buttons(*action).iter().map(|button| format!("{}{}", BUTTON_PREFIX, button.string())));
+				content.push_str(&format!("{} {}\n", name, keys.join(" ")));
+			}
+			let controllers: Vec<String> = bindings.controllers.iter().map(|slot| slot.to_string()).collect();
+			content.push_str(&format!("controllers {}\n", controllers.join(" ")));
+		}
+		content
+	}
+
+	pub fn deadzone(&self) -> f32 {
+		self.deadzone
+	}
+
+	pub fn set_deadzone(&mut self, deadzone: f32) {
+		self.deadzone = deadzone.clamp(0.0, MAX_DEADZONE);
+	}
+
+	pub fn players(&self) -> usize {
+		self.players.len()
+	}
+
+	pub fn player(&self, player: usize) -> Option<&PlayerBindings> {
+		self.players.get(player)
+	}
+
+	// Creates the missing players up to this one, with the default bindings of a game with that many players
+	pub fn player_mut(&mut self, player: usize) -> &mut PlayerBindings {
+		while self.players.len() <= player {
+			self.players.push(Self::default_player_bindings(self.players.len(), player + 1));
+		}
+		&mut self.players[player]
+	}
+
+	// controller_slot : slot of the game controller that sent the event, if any
+	pub fn translate(&mut self, event: &Event, controller_slot: Option<usize>) -> Vec<ActionEvent> {
+		let mut events = Vec::new();
+		match event {
+			// Closing the window is always bound to quit
+			Event::Quit { .. } => events.push(ActionEvent::new(0, Action::Quit, true)),
+			Event::KeyDown { keycode: Some(key), repeat: false, .. } | Event::KeyUp { keycode: Some(key), repeat: false, .. } => {
+				let value = if matches!(event, Event::KeyDown { .. }) { 1.0 } else { 0.0 };
+				let actions: Vec<(usize, Action)> = self.players.iter().enumerate()
+					.flat_map(|(player, bindings)| bindings.keys.get(key).into_iter().flatten().map(move |action| (player, *action)))
+					.collect();
+				for (player, action) in actions {
+					events.extend(self.hold(player, action, InputSource::Key(*key), value));
+				}
+			},
+			Event::ControllerButtonDown { button, .. } | Event::ControllerButtonUp { button, .. } => {
+				if let Some(slot) = controller_slot {
+					let value = if matches!(event, Event::ControllerButtonDown { .. }) { 1.0 } else { 0.0 };
+					let actions: Vec<(usize, Action)> = self.players.iter().enumerate()
+						.filter(|(_, bindings)| bindings.controllers.contains(&slot))
+						.flat_map(|(player, bindings)| bindings.buttons.get(button).into_iter().flatten().map(move |action| (player, *action)))
+						.collect();
+					for (player, action) in actions {
+						events.extend(self.hold(player, action, InputSource::Button(slot, *button), value));
+					}
+				}
+			},
+			Event::ControllerAxisMotion { axis, value, .. } => {
+				if let Some(slot) = controller_slot {
+					events = self.translate_axis(slot, *axis, *value);
+				}
+			},
+			_ => {}
+		}
+		events
+	}
+
+	// Rescales the stick amplitude so that it goes from 0.0 at the deadzone border to 1.0
+	fn apply_deadzone(&self, value: i16) -> f32 {
+		let value = (value as f32 / i16::MAX as f32).clamp(-1.0, 1.0);
+		if value.abs() < self.deadzone {
+			0.0
+		} else {
+			value.signum() * (value.abs() - self.deadzone) / (1.0 - self.deadzone)
+		}
+	}
+
+	fn translate_axis(&mut self, slot: usize, axis: Axis, value: i16) -> Vec<ActionEvent> {
+		let (negative, positive) = match axis {
+			Axis::LeftX => (Action::MoveLeft, Action::MoveRight),
+			Axis::LeftY => (Action::MoveUp, Action::MoveDown),
+			_ => return Vec::new()
+		};
+		let value = self.apply_deadzone(value);
+		let players: Vec<usize> = (0..self.players.len()).filter(|player| self.players[*player].controllers.contains(&slot)).collect();
+		let mut events = Vec::new();
+		for player in players {
+			events.extend(self.hold(player, negative, InputSource::Axis(slot, axis), (-value).max(0.0)));
+			events.extend(self.hold(player, positive, InputSource::Axis(slot, axis), value.max(0.0)));
+		}
+		events
+	}
+
+	/*
+		Records the value of the source (0.0 once released), the action takes the highest value among its sources.
+		So a stick going back to its deadzone does not release a direction still held with the keyboard or the d-pad.
+		Returns the event to send when the value of the action changed.
+	*/
+	fn hold(&mut self, player: usize, action: Action, source: InputSource, value: f32) -> Option<ActionEvent> {
+		let sources = self.held.entry((player, action)).or_default();
+		let last_value = sources.values().fold(0.0, |max: f32, value| max.max(*value));
+		if value > 0.0 {
+			sources.insert(source, value);
+		} else {
+			sources.remove(&source);
+		}
+		let value = sources.values().fold(0.0, |max: f32, value| max.max(*value));
+		if value == last_value {
+			None
+		} else {
+			Some(ActionEvent::analog(player, action, value))
+		}
+	}
+}
+
+impl Default for ActionMap {
+	fn default() -> Self {
+		ActionMap::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_same_bindings(expected: &ActionMap, actual: &ActionMap) {
+		assert_eq!(expected.players(), actual.players());
+		assert_eq!(expected.deadzone(), actual.deadzone());
+		for player in 0..expected.players() {
+			let (expected, actual) = (expected.player(player).unwrap(), actual.player(player).unwrap());
+			for (action, _) in ACTIONS.iter() {
+				assert_eq!(expected.keys(*action), actual.keys(*action), "keys of {:?}", action);
+				assert_eq!(expected.buttons(*action), actual.buttons(*action), "buttons of {:?}", action);
+			}
+			assert_eq!(expected.controllers(), actual.controllers());
+		}
+	}
+
+	#[test]
+	fn saved_defaults_parse_back() {
+		for players in 1..=2 {
+			let map = ActionMap::default_bindings(players);
+			assert_same_bindings(&map, &ActionMap::parse(&map.to_cfg(), players).unwrap());
+		}
+	}
+
+	#[test]
+	fn rebound_actions_parse_back() {
+		let mut map = ActionMap::default_bindings(2);
+		map.set_deadzone(0.4);
+		map.player_mut(0).rebind(Action::Shoot, Keycode::LCtrl);
+		map.player_mut(0).unbind_button(Action::Bomb, Button::B);
+		map.player_mut(1).bind_button(Action::Bomb, Button::RightShoulder);
+		map.player_mut(1).set_controllers(vec![2, 3]);
+		// An action left without any key or button is saved too
+		map.player_mut(1).unbind_all(Action::Pause);
+
+		let parsed = ActionMap::parse(&map.to_cfg(), 2).unwrap();
+		assert_same_bindings(&map, &parsed);
+		assert_eq!(parsed.player(0).unwrap().keys(Action::Shoot), vec![Keycode::LCtrl]);
+		assert!(parsed.player(1).unwrap().keys(Action::Pause).is_empty());
+	}
+
+	#[test]
+	fn deadzone_is_clamped() {
+		let mut map = ActionMap::new();
+		map.set_deadzone(2.0);
+		assert_eq!(map.deadzone(), MAX_DEADZONE);
+		assert!(ActionMap::parse("deadzone 2.0", 1).is_err());
+	}
+}
@@ -0,0 +1,361 @@
+use std::{collections::HashMap, ffi::c_void, os::raw::c_int, ptr, slice, sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}};
+
+use sdl2::{AudioSubsystem, mixer::{self, Channel, Chunk, LoaderRWops, Music}, rwops::RWops, sys};
+
+use super::{filesystem::{AssetFileSystem, AssetSource}, manifest::{AssetManifest, SoundAsset, SoundKind}, renderers::SdlDrawContext};
+
+// Plays the "sound" and "music" assets of the manifest, by id
+pub trait AudioManager {
+	fn play_sound(&mut self, asset_id: &str);
+	// loops : -1 to play forever
+	fn play_music(&mut self, asset_id: &str, loops: i32);
+	// Fades the current music out, then fades the new one in. Nothing happens if this music is already the one playing
+	fn crossfade_music(&mut self, asset_id: &str, loops: i32, fade_ms: i32);
+	// Lowers the music volume, e.g. while the game is paused
+	fn duck_music(&mut self, ducked: bool);
+	fn stop_music(&mut self);
+	// Called once per frame, starts the next music once the previous one faded out
+	fn update(&mut self);
+}
+
+const MIXER_CHANNELS: i32 = 16;
+const MIXER_CHUNK_SIZE: i32 = 1024;
+// Percentage of the music volume kept when ducked
+const MUSIC_DUCK_PERCENTS: i32 = 30;
+
+struct MusicRequest {
+	asset_id: String,
+	loops: i32,
+	fade_ms: i32
+}
+
+// Set from the mixer thread when the music ends, SDL mixer functions can't be called from there
+static MUSIC_FINISHED: AtomicBool = AtomicBool::new(false);
+
+fn on_music_finished() {
+	MUSIC_FINISHED.store(true, Ordering::SeqCst);
+}
+
+/*
+	A music with a loop point, decoded in the mixer output format.
+	The mixer thread plays it in place of the SDL mixer music (see Mix_HookMusic) and jumps back to the loop point
+	within the same audio buffer, so that no gap can be heard : restarting the SDL mixer music would have to wait for the game loop.
+*/
+struct LoopingMusic {
+	samples: Arc<Vec<u8>>,
+	// Byte offsets in the samples
+	position: usize,
+	loop_start: usize,
+	format: u16,
+	volume: i32,
+	// (remaining bytes, total bytes, fading out)
+	fade: Option<(usize, usize, bool)>
+}
+
+impl LoopingMusic {
+	// Adds the next samples to the stream, returns false once faded out
+	fn mix(&mut self, stream: &mut [u8]) -> bool {
+		let mut volume = self.volume;
+		let mut playing = true;
+		if let Some((remaining, total, fading_out)) = self.fade {
+			let remaining = remaining.saturating_sub(stream.len());
+			let progress = 1.0 - remaining as f32 / total as f32;
+			volume = (volume as f32 * if fading_out { 1.0 - progress } else { progress }) as i32;
+			self.fade = if remaining > 0 { Some((remaining, total, fading_out)) } else { None };
+			playing = !fading_out || remaining > 0;
+		}
+
+		let mut written = 0;
+		while written < stream.len() {
+			let count = (stream.len() - written).min(self.samples.len() - self.position);
+			unsafe {
+				sys::SDL_MixAudioFormat(stream[written..].as_mut_ptr(), self.samples[self.position..].as_ptr(), self.format, count as u32, volume);
+			}
+			written += count;
+			self.position += count;
+			if self.position == self.samples.len() {
+				self.position = self.loop_start;
+			}
+		}
+		playing
+	}
+}
+
+static LOOPING_MUSIC: Mutex<Option<LoopingMusic>> = Mutex::new(None);
+
+// Called from the mixer thread, on a stream already filled with silence
+unsafe extern "C" fn mix_looping_music(_data: *mut c_void, stream: *mut u8, len: c_int) {
+	let mut looping_music = match LOOPING_MUSIC.lock() {
+		Ok(looping_music) => looping_music,
+		Err(_) => return
+	};
+	if let Some(music) = looping_music.as_mut() {
+		if !music.mix(slice::from_raw_parts_mut(stream, len as usize)) {
+			*looping_music = None;
+			MUSIC_FINISHED.store(true, Ordering::SeqCst);
+		}
+	}
+}
+
+// Does nothing when no looping music is playing
+fn with_looping_music(f: impl FnOnce(&mut LoopingMusic)) {
+	if let Some(music) = LOOPING_MUSIC.lock().ok().as_mut().and_then(|looping_music| looping_music.as_mut()) {
+		f(music);
+	}
+}
+
+fn is_looping_music_playing() -> bool {
+	LOOPING_MUSIC.lock().map(|looping_music| looping_music.is_some()).unwrap_or(false)
+}
+
+fn stop_looping_music() {
+	unsafe {
+		sys::mixer::Mix_HookMusic(None, ptr::null_mut());
+	}
+	if let Ok(mut looping_music) = LOOPING_MUSIC.lock() {
+		*looping_music = None;
+	}
+}
+
+// Size of that much of the mixer output, in whole sample frames
+fn output_bytes(duration_ms: u32) -> Result<usize, String> {
+	let (frequency, format, channels) = mixer::query_spec()?;
+	let frame_bytes = (format & 0xFF) as usize / 8 * channels as usize;
+	Ok(duration_ms as usize * frequency as usize / 1000 * frame_bytes)
+}
+
+pub struct SdlAudioManager<'sdl_all> {
+	files: &'sdl_all AssetFileSystem,
+	assets: HashMap<String, SoundAsset>,
+	chunks: HashMap<String, Chunk>,
+	musics: HashMap<String, Music<'static>>,
+	// Musics with a loop point, decoded
+	looping_musics: HashMap<String, Arc<Vec<u8>>>,
+	current_music: Option<MusicRequest>,
+	next_music: Option<MusicRequest>,
+	ducked: bool,
+	_audio_subsystem: AudioSubsystem
+}
+
+impl<'sdl_all> SdlAudioManager<'sdl_all> {
+	// Fails when there is no audio device, see NullAudioManager
+	pub fn new(draw_context: &SdlDrawContext, files: &'sdl_all AssetFileSystem, manifest: &AssetManifest) -> Result<Self, String> {
+		let audio_subsystem = draw_context.audio_subsystem()?;
+		mixer::open_audio(mixer::DEFAULT_FREQUENCY, mixer::DEFAULT_FORMAT, mixer::DEFAULT_CHANNELS, MIXER_CHUNK_SIZE)?;
+		mixer::allocate_channels(MIXER_CHANNELS);
+		Music::hook_finished(on_music_finished);
+		Ok(SdlAudioManager {
+			files,
+			assets: manifest.sounds().map(|(id, asset)| (id.clone(), asset.clone())).collect(),
+			chunks: HashMap::new(),
+			musics: HashMap::new(),
+			looping_musics: HashMap::new(),
+			current_music: None,
+			next_music: None,
+			ducked: false,
+			_audio_subsystem: audio_subsystem
+		})
+	}
+
+	fn asset(&self, asset_id: &str, kind: SoundKind) -> Result<&SoundAsset, String> {
+		match self.assets.get(asset_id) {
+			Some(asset) if asset.kind == kind => Ok(asset),
+			_ => Err(format!("Unknown {} asset \"{}\"", if kind == SoundKind::Effect { "sound" } else { "music" }, asset_id))
+		}
+	}
+
+	fn load_chunk(&mut self, asset_id: &str) -> Result<&Chunk, String> {
+		if !self.chunks.contains_key(asset_id) {
+			let asset = self.asset(asset_id, SoundKind::Effect)?;
+			let mut chunk = match self.files.open(&asset.path)? {
+				AssetSource::File(file) => Chunk::from_file(file)?,
+				AssetSource::Memory(content) => RWops::from_bytes(content)?.load_wav()?
+			};
+			chunk.set_volume(asset.volume as i32);
+			println!("LOADED A SOUND");
+			self.chunks.insert(asset_id.to_string(), chunk);
+		}
+		Ok(&self.chunks[asset_id])
+	}
+
+	fn load_music(&mut self, asset_id: &str) -> Result<&Music<'static>, String> {
+		if !self.musics.contains_key(asset_id) {
+			let asset = self.asset(asset_id, SoundKind::Music)?;
+			let music = match self.files.open(&asset.path)? {
+				AssetSource::File(file) => Music::from_file(file)?,
+				AssetSource::Memory(content) => Music::from_static_bytes(content)?
+			};
+			println!("LOADED A MUSIC");
+			self.musics.insert(asset_id.to_string(), music);
+		}
+		Ok(&self.musics[asset_id])
+	}
+
+	// Decoded like a sound, a few megabytes per minute
+	fn load_looping_music(&mut self, asset_id: &str) -> Result<Arc<Vec<u8>>, String> {
+		if !self.looping_musics.contains_key(asset_id) {
+			let asset = self.asset(asset_id, SoundKind::Music)?;
+			let chunk = match self.files.open(&asset.path)? {
+				AssetSource::File(file) => Chunk::from_file(file)?,
+				AssetSource::Memory(content) => RWops::from_bytes(content)?.load_wav()?
+			};
+			let samples = unsafe { slice::from_raw_parts((*chunk.raw).abuf, (*chunk.raw).alen as usize) }.to_vec();
+			self.looping_musics.insert(asset_id.to_string(), Arc::new(samples));
+		}
+		Ok(self.looping_musics[asset_id].clone())
+	}
+
+	fn start_looping_music(&mut self, request: &MusicRequest, loop_start_ms: u32) -> Result<(), String> {
+		let samples = self.load_looping_music(&request.asset_id)?;
+		let loop_start = output_bytes(loop_start_ms)?;
+		if loop_start >= samples.len() {
+			return Err(format!("loop start {} ms is past the end of the music", loop_start_ms));
+		}
+		let fade_bytes = output_bytes(request.fade_ms.max(0) as u32)?;
+		let music = LoopingMusic {
+			samples,
+			position: 0,
+			loop_start,
+			format: mixer::query_spec()?.1,
+			volume: self.music_volume(&request.asset_id),
+			fade: if fade_bytes > 0 { Some((fade_bytes, fade_bytes, false)) } else { None }
+		};
+		Music::halt();
+		*LOOPING_MUSIC.lock().map_err(|e| e.to_string())? = Some(music);
+		unsafe {
+			sys::mixer::Mix_HookMusic(Some(mix_looping_music), ptr::null_mut());
+		}
+		Ok(())
+	}
+
+	fn set_music_volume(&self, volume: i32) {
+		Music::set_volume(volume);
+		with_looping_music(|music| music.volume = volume);
+	}
+
+	fn music_volume(&self, asset_id: &str) -> i32 {
+		let volume = self.asset(asset_id, SoundKind::Music).map(|asset| asset.volume as i32).unwrap_or(mixer::MAX_VOLUME);
+		if self.ducked { volume * MUSIC_DUCK_PERCENTS / 100 } else { volume }
+	}
+
+	// A music looping forever with a loop point is played by the mixer thread, see LoopingMusic
+	fn start_music(&mut self, request: MusicRequest) {
+		stop_looping_music();
+		let loop_start_ms = self.asset(&request.asset_id, SoundKind::Music).ok().and_then(|asset| asset.loop_start_ms).filter(|_| request.loops < 0);
+		let result = match loop_start_ms {
+			Some(loop_start_ms) => self.start_looping_music(&request, loop_start_ms),
+			None => {
+				// Fading in goes up to the volume set beforehand
+				Music::set_volume(self.music_volume(&request.asset_id));
+				self.load_music(&request.asset_id).and_then(|music| music.fade_in(request.loops, request.fade_ms))
+			}
+		};
+		// Starting a music may report the end of the previous one
+		MUSIC_FINISHED.store(false, Ordering::SeqCst);
+		match result {
+			Ok(_) => self.current_music = Some(request),
+			Err(err) => {
+				println!("Unable to play music {} : {}", request.asset_id, err);
+				self.current_music = None;
+			}
+		}
+	}
+}
+
+impl<'sdl_all> AudioManager for SdlAudioManager<'sdl_all> {
+	fn play_sound(&mut self, asset_id: &str) {
+		// Every channel busy is not an error : the sound is just skipped
+		if let Err(err) = self.load_chunk(asset_id).map(|chunk| Channel::all().play(chunk, 0)) {
+			println!("Unable to play sound {} : {}", asset_id, err);
+		}
+	}
+
+	fn play_music(&mut self, asset_id: &str, loops: i32) {
+		self.next_music = None;
+		self.start_music(MusicRequest { asset_id: asset_id.to_string(), loops, fade_ms: 0 });
+	}
+
+	fn crossfade_music(&mut self, asset_id: &str, loops: i32, fade_ms: i32) {
+		let target = self.next_music.as_ref().or(self.current_music.as_ref()).map(|request| request.asset_id.as_str());
+		if target == Some(asset_id) {
+			return;
+		}
+		let request = MusicRequest { asset_id: asset_id.to_string(), loops, fade_ms };
+		let looping = is_looping_music_playing();
+		if (Music::is_playing() || looping) && self.current_music.is_some() {
+			// SDL mixer has a single music stream : the new music starts once the fade out is over
+			if self.next_music.is_none() && looping {
+				let fade_bytes = output_bytes(fade_ms.max(1) as u32).unwrap_or(1).max(1);
+				with_looping_music(|music| music.fade = Some((fade_bytes, fade_bytes, true)));
+			} else if self.next_music.is_none() {
+				if let Err(err) = Music::fade_out(fade_ms) {
+					println!("Unable to fade music out : {}", err);
+					Music::halt();
+				}
+			}
+			self.next_music = Some(request);
+		} else {
+			self.start_music(request);
+		}
+	}
+
+	fn duck_music(&mut self, ducked: bool) {
+		self.ducked = ducked;
+		if let Some(asset_id) = self.current_music.as_ref().map(|request| request.asset_id.clone()) {
+			self.set_music_volume(self.music_volume(&asset_id));
+		}
+	}
+
+	fn stop_music(&mut self) {
+		self.current_music = None;
+		self.next_music = None;
+		stop_looping_music();
+		Music::halt();
+		MUSIC_FINISHED.store(false, Ordering::SeqCst);
+	}
+
+	fn update(&mut self) {
+		if !MUSIC_FINISHED.swap(false, Ordering::SeqCst) {
+			return;
+		}
+		match self.next_music.take() {
+			Some(request) => self.start_music(request),
+			None => self.current_music = None
+		}
+	}
+}
+
+impl<'sdl_all> Drop for SdlAudioManager<'sdl_all> {
+	fn drop(&mut self) {
+		Music::unhook_finished();
+		stop_looping_music();
+		self.chunks.clear();
+		self.musics.clear();
+		mixer::close_audio();
+	}
+}
+
+// Silent backend, for headless runs and machines without audio device
+pub struct NullAudioManager {
+}
+
+impl NullAudioManager {
+	pub fn new() -> Self {
+		NullAudioManager {}
+	}
+}
+
+impl Default for NullAudioManager {
+	fn default() -> Self {
+		NullAudioManager::new()
+	}
+}
+
+impl AudioManager for NullAudioManager {
+	fn play_sound(&mut self, _asset_id: &str) {}
+	fn play_music(&mut self, _asset_id: &str, _loops: i32) {}
+	fn crossfade_music(&mut self, _asset_id: &str, _loops: i32, _fade_ms: i32) {}
+	fn duck_music(&mut self, _ducked: bool) {}
+	fn stop_music(&mut self) {}
+	fn update(&mut self) {}
+}
@@ -1,28 +1,61 @@
-use std::{time::{SystemTime, UNIX_EPOCH}};
+use std::{sync::Arc, time::{SystemTime, UNIX_EPOCH}};
 
-use super::{ecs::{World}, events::EventDispatcher, renderers::{SdlDrawContext, SdlRenderer, SdlResourceManager}};
+use crate::levels::definitions::LevelRegistry;
+
+use super::{actions::ActionMap, audio::AudioManager, difficulty::Difficulty, ecs::{World}, enemies::EnemyRegistry, events::EventDispatcher, rank::Rank, renderers::{SdlDrawContext, SdlRenderer, SdlResourceManager}};
+
+// Options the game is started with, from the command line and the input configuration
+pub struct GameSettings {
+	pub action_map: ActionMap,
+	pub difficulty: Difficulty,
+	// Dynamic difficulty, None when disabled
+	pub rank: Option<Rank>,
+	// Interval of the frame capture started with F11
+	pub frame_capture_every_n_frames: u32
+}
 
 pub struct GameServices<'sdl_all, 'parent> {
 	world: &'parent mut World,
 	pub draw_context: &'parent SdlDrawContext,
 	pub renderer: &'parent mut SdlRenderer,
 	pub resource_manager: &'parent mut SdlResourceManager<'sdl_all>,
-	pub event_dispatcher: EventDispatcher
+	pub audio_manager: &'parent mut (dyn AudioManager + 'sdl_all),
+	pub event_dispatcher: EventDispatcher,
+	pub action_map: ActionMap,
+	pub enemy_types: Arc<EnemyRegistry>,
+	pub levels: Arc<LevelRegistry>,
+	pub difficulty: Difficulty,
+	// Dynamic difficulty, None when disabled
+	pub rank: Option<Rank>,
+	pub frame_capture_every_n_frames: u32
 }
 
 impl<'sdl_all, 'parent> GameServices<'sdl_all, 'parent> {
-	pub fn new(world: &'parent mut World, resource_manager: &'parent mut SdlResourceManager<'sdl_all>, renderer: &'parent mut SdlRenderer, draw_context: &'parent SdlDrawContext) -> Self {
+	#[allow(clippy::too_many_arguments)]
+	pub fn new(world: &'parent mut World, resource_manager: &'parent mut SdlResourceManager<'sdl_all>, audio_manager: &'parent mut (dyn AudioManager + 'sdl_all), renderer: &'parent mut SdlRenderer, draw_context: &'parent SdlDrawContext, settings: GameSettings, enemy_types: Arc<EnemyRegistry>, levels: Arc<LevelRegistry>) -> Self {
 		GameServices {
 			world: world,
 			resource_manager: resource_manager,
+			audio_manager,
 			renderer: renderer,
 			draw_context: draw_context,
 			event_dispatcher: EventDispatcher::new(),
+			action_map: settings.action_map,
+			enemy_types,
+			levels,
+			difficulty: settings.difficulty,
+			rank: settings.rank,
+			frame_capture_every_n_frames: settings.frame_capture_every_n_frames
 		}
 	}
 
 	pub fn get_world(&self) -> &World { self.world }
 	pub fn get_world_mut(&mut self) -> &mut World { self.world }
+
+	// 1.0 when the rank is disabled
+	pub fn rank_factor(&self) -> f32 {
+		self.rank.as_ref().map_or(1.0, |rank| rank.factor())
+	}
 }
 
 pub fn current_time_ms() -> u64 {
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use sdl2::{GameControllerSubsystem, controller::GameController, event::Event};
+
+use super::renderers::SdlDrawContext;
+
+// Keeps the plugged game controllers opened, so that SDL sends their events
+pub struct GameControllers {
+	subsystem: GameControllerSubsystem,
+	// By joystick instance id
+	controllers: HashMap<u32, GameController>,
+	// Instance id of the controller in each slot. A controller plugged takes the first free slot,
+	// so that a player getting their controller back after unplugging it keeps controlling the same ship
+	slots: Vec<Option<u32>>
+}
+
+impl GameControllers {
+	// Controllers already plugged at startup are reported by SDL as "device added" events too
+	pub fn new(draw_context: &SdlDrawContext) -> Result<Self, String> {
+		Ok(GameControllers {
+			subsystem: draw_context.game_controller_subsystem()?,
+			controllers: HashMap::new(),
+			slots: Vec::new()
+		})
+	}
+
+	pub fn handle_event(&mut self, event: &Event) {
+		match event {
+			Event::ControllerDeviceAdded { which, .. } => {
+				match self.subsystem.open(*which) {
+					Ok(controller) => {
+						let instance_id = controller.instance_id();
+						let slot = match self.slots.iter().position(Option::is_none) {
+							Some(slot) => slot,
+							None => {
+								self.slots.push(None);
+								self.slots.len() - 1
+							}
+						};
+						self.slots[slot] = Some(instance_id);
+						println!("CONTROLLER CONNECTED : {} (SLOT {})", controller.name(), slot);
+						self.controllers.insert(instance_id, controller);
+					},
+					Err(err) => println!("Unable to open controller {} : {}", which, err)
+				}
+			},
+			Event::ControllerDeviceRemoved { which, .. } => {
+				if let Some(controller) = self.controllers.remove(which) {
+					println!("CONTROLLER DISCONNECTED : {}", controller.name());
+					for slot in self.slots.iter_mut().filter(|slot| **slot == Some(*which)) {
+						*slot = None;
+					}
+				}
+			},
+			_ => {}
+		}
+	}
+
+	// Slot of the controller that sent this event
+	pub fn event_slot(&self, event: &Event) -> Option<usize> {
+		let which = match event {
+			Event::ControllerAxisMotion { which, .. }
+			| Event::ControllerButtonDown { which, .. }
+			| Event::ControllerButtonUp { which, .. } => which,
+			_ => return None
+		};
+		self.slots.iter().position(|slot| *slot == Some(*which))
+	}
+
+	pub fn len(&self) -> usize {
+		self.controllers.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.controllers.is_empty()
+	}
+}
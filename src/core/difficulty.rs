@@ -0,0 +1,62 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+	Easy,
+	#[default]
+	Normal,
+	Hard,
+	Insane,
+}
+
+// Multipliers applied on top of the values set by the levels and the enemy types
+pub struct DifficultySettings {
+	// Chance for each enemy of a spawn to appear
+	pub spawn_luck: f32,
+	// Delay between two spawns, below 1.0 spawns more often
+	pub spawn_delay: f32,
+	pub shot_power: f32,
+	pub shot_frequency: f32,
+	pub enemy_health: f32,
+	pub player_lives: u32,
+}
+
+const DIFFICULTY_SETTINGS: [DifficultySettings; 4] = [
+	DifficultySettings { spawn_luck: 0.7, spawn_delay: 1.3, shot_power: 0.8, shot_frequency: 0.6, enemy_health: 0.75, player_lives: 5 },
+	DifficultySettings { spawn_luck: 1.0, spawn_delay: 1.0, shot_power: 1.0, shot_frequency: 1.0, enemy_health: 1.0, player_lives: 3 },
+	DifficultySettings { spawn_luck: 1.0, spawn_delay: 0.8, shot_power: 1.2, shot_frequency: 1.4, enemy_health: 1.5, player_lives: 3 },
+	DifficultySettings { spawn_luck: 1.0, spawn_delay: 0.6, shot_power: 1.5, shot_frequency: 2.0, enemy_health: 2.0, player_lives: 1 },
+];
+
+impl Difficulty {
+	pub const ALL: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Insane];
+
+	pub fn settings(&self) -> &'static DifficultySettings {
+		&DIFFICULTY_SETTINGS[*self as usize]
+	}
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Difficulty::Easy => "easy",
+			Difficulty::Normal => "normal",
+			Difficulty::Hard => "hard",
+			Difficulty::Insane => "insane",
+		}
+	}
+
+	pub fn from_name(name: &str) -> Result<Self, String> {
+		Self::ALL.iter().copied().find(|difficulty| difficulty.name() == name.to_lowercase())
+			.ok_or_else(|| format!("unknown difficulty \"{}\", expected easy, normal, hard or insane", name))
+	}
+
+	pub fn spawn_luck(&self, luck_percents: f32) -> f32 {
+		(luck_percents * self.settings().spawn_luck).min(100.0)
+	}
+
+	pub fn spawn_frequency_ms(&self, frequency_ms: u32) -> u32 {
+		(frequency_ms as f32 * self.settings().spawn_delay) as u32
+	}
+
+	// Never down to 0, an enemy always takes a hit
+	pub fn enemy_health(&self, health: i64) -> i64 {
+		((health as f32 * self.settings().enemy_health).round() as i64).max(1)
+	}
+}
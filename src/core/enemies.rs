@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use sdl2::rect::Rect;
+
+use crate::components::bullet_pattern::{BulletEmitter, BulletPattern};
+
+use super::{filesystem::AssetFileSystem, manifest::AssetManifest};
+
+pub const ENEMY_TYPES_FILE: &str = "enemies.def";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EnemyAnimation {
+	// No animation, the first frame of the texture
+	None,
+	// 3x2 grid : banking to the right on the first row, to the left on the second one
+	Ship,
+}
+
+#[derive(Clone)]
+pub struct EnemyType {
+	pub id: String,
+	pub texture_id: String,
+	pub width: u32,
+	pub height: u32,
+	// Relative to the sprite position
+	pub hitbox: Rect,
+	pub animation: EnemyAnimation,
+	pub health: i64,
+	pub speed: f32,
+	pub shot_power: f32,
+	// Multiplies the shot frequency of the trajectories
+	pub shot_frequency: f32,
+	pub pattern: BulletPattern,
+	pub score: u32,
+}
+
+/*
+	One enemy type per line : "<id> <texture id> [property=value ...]"
+	Properties :
+		size=<width>x<height> (mandatory)
+		hitbox=<x>,<y>,<width>,<height> (the whole sprite by default)
+		animation=none|ship, health=<points>, speed=<pixels per frame>, score=<points>
+		shot_power=<bullets speed>, shot_frequency=<factor>
+		pattern=aimed|fan:<count>:<spread degrees>|ring:<count>|spiral:<arms>:<step degrees>
+	Empty lines and lines starting with '#' are ignored.
+*/
+pub struct EnemyRegistry {
+	types: HashMap<String, EnemyType>,
+}
+
+impl EnemyRegistry {
+	pub fn new() -> Self {
+		EnemyRegistry {
+			types: HashMap::new()
+		}
+	}
+
+	pub fn load(files: &AssetFileSystem, filename: &str) -> Result<Self, String> {
+		let content = files.read_to_string(filename)?;
+		Self::parse(&content).map_err(|e| format!("{}:{}", filename, e))
+	}
+
+	pub fn parse(content: &str) -> Result<Self, String> {
+		let mut registry = EnemyRegistry::new();
+		for (line_index, line) in content.lines().enumerate() {
+			registry.parse_line(line).map_err(|e| format!("{}: {}", line_index + 1, e))?;
+		}
+		Ok(registry)
+	}
+
+	fn parse_line(&mut self, line: &str) -> Result<(), String> {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			return Ok(());
+		}
+
+		let mut tokens = line.split_whitespace();
+		let id = tokens.next().unwrap();
+		let texture_id = tokens.next().ok_or_else(|| format!("missing texture for enemy type \"{}\"", id))?;
+		if self.types.contains_key(id) {
+			return Err(format!("duplicated enemy type \"{}\"", id));
+		}
+
+		let mut properties = HashMap::new();
+		for token in tokens {
+			let mut key_value = token.splitn(2, '=');
+			let (key, value) = match (key_value.next(), key_value.next()) {
+				(Some(key), Some(value)) if !key.is_empty() && !value.is_empty() => (key, value),
+				_ => return Err(format!("invalid property \"{}\", expected key=value", token))
+			};
+			if properties.insert(key, value).is_some() {
+				return Err(format!("property \"{}\" is set twice", key));
+			}
+		}
+
+		let size = properties.remove("size").ok_or_else(|| format!("enemy type \"{}\" has no size", id))?;
+		let (width, height) = Self::parse_size(size)?;
+		let hitbox = match properties.remove("hitbox") {
+			Some(hitbox) => Self::parse_hitbox(hitbox)?,
+			None => Rect::new(0, 0, width, height),
+		};
+		let animation = match properties.remove("animation") {
+			Some("none") | None => EnemyAnimation::None,
+			Some("ship") => EnemyAnimation::Ship,
+			Some(other) => return Err(format!("unknown animation \"{}\"", other)),
+		};
+		let pattern = match properties.remove("pattern") {
+			Some(pattern) => Self::parse_pattern(pattern)?,
+			None => BulletPattern::aimed(),
+		};
+
+		let enemy_type = EnemyType {
+			id: id.to_string(),
+			texture_id: texture_id.to_string(),
+			width,
+			height,
+			hitbox,
+			animation,
+			health: Self::parse_number(properties.remove("health"), 1)?,
+			speed: Self::parse_number(properties.remove("speed"), 5.0)?,
+			shot_power: Self::parse_number(properties.remove("shot_power"), 5.0)?,
+			shot_frequency: Self::parse_number(properties.remove("shot_frequency"), 1.0)?,
+			pattern,
+			score: Self::parse_number(properties.remove("score"), 0)?,
+		};
+		if let Some(key) = properties.keys().next() {
+			return Err(format!("unknown property \"{}\" for enemy type \"{}\"", key, id));
+		}
+		self.types.insert(id.to_string(), enemy_type);
+		Ok(())
+	}
+
+	fn parse_number<T: std::str::FromStr>(value: Option<&str>, default: T) -> Result<T, String> {
+		match value {
+			Some(value) => value.parse::<T>().map_err(|_| format!("invalid number \"{}\"", value)),
+			None => Ok(default),
+		}
+	}
+
+	fn parse_size(value: &str) -> Result<(u32, u32), String> {
+		let error = || format!("invalid size \"{}\", expected <width>x<height>", value);
+		let mut parts = value.splitn(2, 'x');
+		let width = parts.next().and_then(|width| width.parse::<u32>().ok()).ok_or_else(error)?;
+		let height = parts.next().and_then(|height| height.parse::<u32>().ok()).ok_or_else(error)?;
+		Ok((width, height))
+	}
+
+	fn parse_hitbox(value: &str) -> Result<Rect, String> {
+		let numbers: Vec<i32> = value.split(',').filter_map(|number| number.parse::<i32>().ok()).collect();
+		match numbers.as_slice() {
+			[x, y, width, height] if *width > 0 && *height > 0 => Ok(Rect::new(*x, *y, *width as u32, *height as u32)),
+			_ => Err(format!("invalid hitbox \"{}\", expected <x>,<y>,<width>,<height>", value))
+		}
+	}
+
+	fn parse_pattern(value: &str) -> Result<BulletPattern, String> {
+		Ok(BulletPattern::new().emitter(BulletEmitter::parse(value)?).clone())
+	}
+
+	// Every enemy texture must be declared in the asset manifest
+	pub fn validate(&self, manifest: &AssetManifest) -> Result<(), String> {
+		for enemy_type in self.types.values() {
+			manifest.texture(&enemy_type.texture_id).map_err(|e| format!("enemy type \"{}\" : {}", enemy_type.id, e))?;
+		}
+		Ok(())
+	}
+
+	pub fn get(&self, id: &str) -> Result<&EnemyType, String> {
+		self.types.get(id).ok_or_else(|| format!("Unknown enemy type \"{}\"", id))
+	}
+
+	pub fn score(&self, id: &str) -> u32 {
+		self.types.get(id).map_or(0, |enemy_type| enemy_type.score)
+	}
+}
+
+impl Default for EnemyRegistry {
+	fn default() -> Self {
+		EnemyRegistry::new()
+	}
+}
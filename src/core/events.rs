@@ -5,23 +5,23 @@ use once_cell::sync::OnceCell;
 use super::meta::{self, Holder, IdCounter, Initable, InitableHolder};
 
 
-pub trait EventObserver<T> {
+pub trait EventObserver<T: ?Sized> {
 	fn on_event_mut(&mut self, data: &T);
 }
 
 pub type Observer<T> = RwLock<dyn EventObserver<T>>;
-pub struct EventBusBase<T: 'static> {
+pub struct EventBusBase<T: ?Sized + 'static> {
 	subscribers: Vec<Weak<Observer<T>>>,
 	init: bool
 }
 
-impl<T> EventBusBase<T> {
+impl<T: ?Sized> EventBusBase<T> {
 	pub fn new() -> Self {
 		EventBusBase { subscribers: Vec::new(), init: true }
 	}
 }
 
-impl<T> Default for EventBusBase<T> {
+impl<T: ?Sized> Default for EventBusBase<T> {
 	fn default() -> Self {
 		EventBusBase { subscribers: Vec::new(), init: false }
 	}
@@ -46,13 +46,13 @@ impl<T> Holder for EventBusBase<T> {
 	}
 }
 
-pub trait EventBus<T> {
+pub trait EventBus<T: ?Sized> {
 	fn register(&mut self, observer: Arc<Observer<T>>);
 	fn unregister(&mut self, observer: Arc<Observer<T>>);
 	fn notify(&mut self, data: &T);
 }
 
-impl<T> EventBus<T> for EventBusBase<T> {
+impl<T: ?Sized> EventBus<T> for EventBusBase<T> {
 	fn register(&mut self, observer: Arc<Observer<T>>) {
 		self.subscribers.push(Arc::downgrade(&observer));
 	}
@@ -67,7 +67,7 @@ impl<T> EventBus<T> for EventBusBase<T> {
 			let obs = &self.subscribers[i];
 			if let Some(listener_rc) = obs.upgrade() {
 				let mut listener = listener_rc.write().unwrap();
-				listener.on_event_mut(&data);
+				listener.on_event_mut(data);
 				i += 1;
 			} else {
 				self.subscribers.remove(i);
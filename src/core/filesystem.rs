@@ -0,0 +1,171 @@
+use std::{collections::HashMap, convert::TryInto, env, fs, io::Write, path::{Path, PathBuf}};
+
+pub const DEFAULT_ARCHIVE_FILE: &str = "rspace.pak";
+// Additional asset roots, separated like the PATH environment variable
+pub const ASSET_PATH_ENV: &str = "RSPACE_ASSET_PATH";
+
+const PAK_MAGIC: &[u8; 4] = b"RPAK";
+const PAK_VERSION: u32 = 1;
+
+// Where the content of an asset can be read from
+pub enum AssetSource {
+	File(PathBuf),
+	Memory(&'static [u8])
+}
+
+/*
+	Packed archive of assets :
+	"RPAK", version (u32), entries count (u32),
+	then for each entry : name length (u32), name (utf-8), offset (u64), size (u64),
+	then the content of all entries. Every number is little endian.
+*/
+pub struct PakArchive {
+	entries: HashMap<String, &'static [u8]>
+}
+
+impl PakArchive {
+	// The archive content is kept in memory for the whole game execution,
+	// so that assets such as fonts can be streamed from it by SDL at any time
+	pub fn open(filename: &Path) -> Result<Self, String> {
+		let content: &'static [u8] = Box::leak(fs::read(filename).map_err(|e| format!("{} : {}", filename.display(), e))?.into_boxed_slice());
+		Self::parse(content).map_err(|e| format!("{} : {}", filename.display(), e))
+	}
+
+	fn read<'a>(content: &'a [u8], cursor: &mut usize, size: usize) -> Result<&'a [u8], String> {
+		let end = cursor.checked_add(size).filter(|end| *end <= content.len()).ok_or_else(|| "truncated archive".to_string())?;
+		let data = &content[*cursor..end];
+		*cursor = end;
+		Ok(data)
+	}
+
+	fn read_u32(content: &[u8], cursor: &mut usize) -> Result<u32, String> {
+		Ok(u32::from_le_bytes(Self::read(content, cursor, 4)?.try_into().unwrap()))
+	}
+
+	fn read_u64(content: &[u8], cursor: &mut usize) -> Result<u64, String> {
+		Ok(u64::from_le_bytes(Self::read(content, cursor, 8)?.try_into().unwrap()))
+	}
+
+	fn parse(content: &'static [u8]) -> Result<Self, String> {
+		let mut cursor = 0;
+		if Self::read(content, &mut cursor, 4)? != PAK_MAGIC {
+			return Err("not an asset archive".to_string());
+		}
+		let version = Self::read_u32(content, &mut cursor)?;
+		if version != PAK_VERSION {
+			return Err(format!("unsupported archive version {}", version));
+		}
+
+		let count = Self::read_u32(content, &mut cursor)?;
+		let mut entries = HashMap::new();
+		for _ in 0..count {
+			let name_size = Self::read_u32(content, &mut cursor)? as usize;
+			let name = String::from_utf8(Self::read(content, &mut cursor, name_size)?.to_vec()).map_err(|_| "invalid entry name".to_string())?;
+			let mut offset = Self::read_u64(content, &mut cursor)? as usize;
+			let size = Self::read_u64(content, &mut cursor)? as usize;
+			entries.insert(name, Self::read(content, &mut offset, size)?);
+		}
+		Ok(PakArchive { entries })
+	}
+
+	pub fn get(&self, name: &str) -> Option<&'static [u8]> {
+		self.entries.get(name).copied()
+	}
+
+	// Writes an archive containing each (name in the archive, file to read) pair
+	pub fn write(filename: &Path, files: &[(String, PathBuf)]) -> Result<(), String> {
+		let mut contents = Vec::with_capacity(files.len());
+		for (_, path) in files {
+			contents.push(fs::read(path).map_err(|e| format!("{} : {}", path.display(), e))?);
+		}
+
+		let header_size: usize = 12 + files.iter().map(|(name, _)| 4 + name.len() + 16).sum::<usize>();
+		let mut data = Vec::new();
+		data.extend_from_slice(PAK_MAGIC);
+		data.extend_from_slice(&PAK_VERSION.to_le_bytes());
+		data.extend_from_slice(&(files.len() as u32).to_le_bytes());
+		let mut offset = header_size as u64;
+		for ((name, _), content) in files.iter().zip(contents.iter()) {
+			data.extend_from_slice(&(name.len() as u32).to_le_bytes());
+			data.extend_from_slice(name.as_bytes());
+			data.extend_from_slice(&offset.to_le_bytes());
+			data.extend_from_slice(&(content.len() as u64).to_le_bytes());
+			offset += content.len() as u64;
+		}
+		for content in contents {
+			data.extend_from_slice(&content);
+		}
+
+		let mut file = fs::File::create(filename).map_err(|e| format!("{} : {}", filename.display(), e))?;
+		file.write_all(&data).map_err(|e| format!("{} : {}", filename.display(), e))
+	}
+}
+
+// Resolves asset paths : loose files in the search roots first (so that they can override packed ones), then the mounted archives
+pub struct AssetFileSystem {
+	roots: Vec<PathBuf>,
+	archives: Vec<PakArchive>
+}
+
+impl AssetFileSystem {
+	pub fn new(roots: Vec<PathBuf>) -> Self {
+		AssetFileSystem {
+			roots,
+			archives: Vec::new()
+		}
+	}
+
+	// Roots are, by priority : the ones from RSPACE_ASSET_PATH, the working directory and the executable directory.
+	// The first DEFAULT_ARCHIVE_FILE found in those roots is mounted.
+	pub fn from_env() -> Result<Self, String> {
+		let mut roots: Vec<PathBuf> = match env::var_os(ASSET_PATH_ENV) {
+			Some(paths) => env::split_paths(&paths).collect(),
+			None => Vec::new()
+		};
+		roots.push(PathBuf::from("."));
+		if let Some(executable_dir) = env::current_exe().ok().and_then(|executable| executable.parent().map(Path::to_path_buf)) {
+			roots.push(executable_dir);
+		}
+
+		let mut files = AssetFileSystem::new(roots);
+		if let Some(archive) = files.find_file(DEFAULT_ARCHIVE_FILE) {
+			files.mount_archive(&archive)?;
+		}
+		Ok(files)
+	}
+
+	pub fn mount_archive(&mut self, filename: &Path) -> Result<(), String> {
+		println!("MOUNTING ARCHIVE {}", filename.display());
+		self.archives.push(PakArchive::open(filename)?);
+		Ok(())
+	}
+
+	// Loose file only, archives are ignored
+	pub fn find_file(&self, path: &str) -> Option<PathBuf> {
+		self.roots.iter().map(|root| root.join(path)).find(|candidate| candidate.is_file())
+	}
+
+	pub fn open(&self, path: &str) -> Result<AssetSource, String> {
+		if let Some(file) = self.find_file(path) {
+			return Ok(AssetSource::File(file));
+		}
+		self.archives.iter().find_map(|archive| archive.get(path))
+			.map(AssetSource::Memory)
+			.ok_or_else(|| format!("Asset file \"{}\" not found", path))
+	}
+
+	pub fn exists(&self, path: &str) -> bool {
+		self.open(path).is_ok()
+	}
+
+	pub fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+		match self.open(path)? {
+			AssetSource::File(file) => fs::read(&file).map_err(|e| format!("{} : {}", file.display(), e)),
+			AssetSource::Memory(content) => Ok(content.to_vec())
+		}
+	}
+
+	pub fn read_to_string(&self, path: &str) -> Result<String, String> {
+		String::from_utf8(self.read(path)?).map_err(|_| format!("{} is not a valid text file", path))
+	}
+}
@@ -0,0 +1,231 @@
+use std::{collections::BTreeMap, env, fs::{self, File}, io::Write, path::{Path, PathBuf}};
+
+pub const HIGH_SCORES_FILE: &str = "highscores.txt";
+pub const HIGH_SCORES_PER_TABLE: usize = 10;
+pub const HIGH_SCORE_NAME_LENGTH: usize = 3;
+// Directory created in the user data directory
+const DATA_DIRECTORY_NAME: &str = "rspace";
+
+// "$RSPACE_DATA_DIR", or the platform user data directory, or the working directory when there is none
+pub fn data_directory() -> PathBuf {
+	if let Some(directory) = env::var_os("RSPACE_DATA_DIR") {
+		PathBuf::from(directory)
+	} else if let Some(directory) = env::var_os("XDG_DATA_HOME") {
+		PathBuf::from(directory).join(DATA_DIRECTORY_NAME)
+	} else if let Some(directory) = env::var_os("APPDATA") {
+		PathBuf::from(directory).join(DATA_DIRECTORY_NAME)
+	} else if let Some(home) = env::var_os("HOME") {
+		PathBuf::from(home).join(".local").join("share").join(DATA_DIRECTORY_NAME)
+	} else {
+		PathBuf::from(".")
+	}
+}
+
+/*
+	Writes a temporary file, flushed to the disk, then renames it over the file : a crash while saving leaves either
+	the previous file or the new one, never a truncated one. The directory is flushed too, for the rename to be kept.
+*/
+pub fn write_atomically(path: &Path, content: &str) -> Result<(), String> {
+	let directory = match path.parent() {
+		Some(directory) if !directory.as_os_str().is_empty() => directory,
+		_ => Path::new(".")
+	};
+	let error = |path: &Path, e: std::io::Error| format!("{} : {}", path.display(), e);
+	fs::create_dir_all(directory).map_err(|e| error(directory, e))?;
+	let temporary_path = path.with_extension("tmp");
+	let mut file = File::create(&temporary_path).map_err(|e| error(&temporary_path, e))?;
+	file.write_all(content.as_bytes()).and_then(|_| file.sync_all()).map_err(|e| error(&temporary_path, e))?;
+	drop(file);
+	fs::rename(&temporary_path, path).map_err(|e| error(path, e))?;
+	// Directories can't be opened as files on Windows, where the rename is flushed with the file
+	if cfg!(unix) {
+		File::open(directory).and_then(|directory| directory.sync_all()).map_err(|e| error(directory, e))?;
+	}
+	Ok(())
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct HighScore {
+	pub name: String,
+	pub score: u32,
+}
+
+/*
+	One table per game mode and difficulty, each table starts with a "[<mode>/<difficulty>]" header
+	followed by one "<name> <score>" line per entry, best score first.
+	Empty lines and lines starting with '#' are ignored.
+*/
+pub struct HighScores {
+	path: PathBuf,
+	tables: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl HighScores {
+	pub fn new(path: &Path) -> Self {
+		HighScores {
+			path: path.to_path_buf(),
+			tables: BTreeMap::new()
+		}
+	}
+
+	pub fn table_key(mode: &str, difficulty: &str) -> String {
+		format!("{}/{}", mode, difficulty)
+	}
+
+	pub fn default_path() -> PathBuf {
+		data_directory().join(HIGH_SCORES_FILE)
+	}
+
+	// Never fails : a missing file gives empty tables, a corrupt one is set aside and replaced by empty tables
+	pub fn load(path: &Path) -> Self {
+		if !path.is_file() {
+			return HighScores::new(path);
+		}
+		match fs::read_to_string(path).map_err(|e| e.to_string()).and_then(|content| Self::parse(path, &content)) {
+			Ok(high_scores) => high_scores,
+			Err(e) => {
+				let backup = path.with_extension("corrupt");
+				println!("CORRUPT HIGH SCORES FILE {} ({}), MOVED TO {}", path.display(), e, backup.display());
+				if let Err(e) = fs::rename(path, &backup) {
+					println!("HIGH SCORES BACKUP FAILED : {}", e);
+				}
+				HighScores::new(path)
+			}
+		}
+	}
+
+	pub fn parse(path: &Path, content: &str) -> Result<Self, String> {
+		let mut high_scores = HighScores::new(path);
+		let mut table: Option<String> = None;
+		for (line_index, line) in content.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+			if line.starts_with('[') && line.ends_with(']') {
+				table = Some(line[1..line.len() - 1].trim().to_string());
+				continue;
+			}
+			let error = || format!("{}: invalid line \"{}\"", line_index + 1, line);
+			let key = table.as_ref().ok_or_else(error)?;
+			let mut tokens = line.split_whitespace();
+			let (name, score) = match (tokens.next(), tokens.next().and_then(|score| score.parse::<u32>().ok()), tokens.next()) {
+				(Some(name), Some(score), None) => (name, score),
+				_ => return Err(error())
+			};
+			high_scores.insert(key, name, score);
+		}
+		Ok(high_scores)
+	}
+
+	fn format(&self) -> String {
+		let mut content = String::from("# High scores, best first\n");
+		for (key, table) in &self.tables {
+			content.push_str(&format!("[{}]\n", key));
+			for high_score in table {
+				content.push_str(&format!("{} {}\n", high_score.name, high_score.score));
+			}
+		}
+		content
+	}
+
+	pub fn save(&self) -> Result<(), String> {
+		write_atomically(&self.path, &self.format())
+	}
+
+	pub fn table(&self, key: &str) -> &[HighScore] {
+		self.tables.get(key).map_or(&[], |table| table.as_slice())
+	}
+
+	pub fn qualifies(&self, key: &str, score: u32) -> bool {
+		let table = self.table(key);
+		score > 0 && (table.len() < HIGH_SCORES_PER_TABLE || table.last().is_none_or(|lowest| score > lowest.score))
+	}
+
+	// Returns the rank of the new entry, None when it is not good enough for the table
+	pub fn insert(&mut self, key: &str, name: &str, score: u32) -> Option<usize> {
+		if !self.qualifies(key, score) {
+			return None;
+		}
+		let table = self.tables.entry(key.to_string()).or_default();
+		// Among equal scores, the oldest stays first
+		let rank = table.iter().position(|high_score| score > high_score.score).unwrap_or(table.len());
+		table.insert(rank, HighScore { name: name.to_string(), score });
+		table.truncate(HIGH_SCORES_PER_TABLE);
+		Some(rank)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const KEY: &str = "solo/normal";
+
+	fn scores(high_scores: &HighScores, key: &str) -> Vec<(String, u32)> {
+		high_scores.table(key).iter().map(|high_score| (high_score.name.clone(), high_score.score)).collect()
+	}
+
+	#[test]
+	fn parses_tables() {
+		let content = "# comment\n[solo/normal]\nAAA 300\n\nBBB 100\n[coop2/hard]\nCCC 50\n";
+		let high_scores = HighScores::parse(Path::new("unused"), content).unwrap();
+		assert_eq!(scores(&high_scores, KEY), vec![("AAA".to_string(), 300), ("BBB".to_string(), 100)]);
+		assert_eq!(scores(&high_scores, "coop2/hard"), vec![("CCC".to_string(), 50)]);
+		assert!(high_scores.table("solo/easy").is_empty());
+	}
+
+	#[test]
+	fn rejects_invalid_lines() {
+		assert_eq!(HighScores::parse(Path::new("unused"), "AAA 300").err(), Some("1: invalid line \"AAA 300\"".to_string()));
+		assert!(HighScores::parse(Path::new("unused"), "[solo/normal]\nAAA").is_err());
+		assert!(HighScores::parse(Path::new("unused"), "[solo/normal]\nAAA -5").is_err());
+		assert!(HighScores::parse(Path::new("unused"), "[solo/normal]\nAAA 300 BBB").is_err());
+	}
+
+	#[test]
+	fn qualifies_until_the_table_is_full() {
+		let mut high_scores = HighScores::new(Path::new("unused"));
+		assert!(!high_scores.qualifies(KEY, 0));
+		for score in 1..=HIGH_SCORES_PER_TABLE as u32 {
+			assert!(high_scores.qualifies(KEY, score * 10));
+			high_scores.insert(KEY, "AAA", score * 10);
+		}
+		// The lowest score is 10
+		assert!(!high_scores.qualifies(KEY, 10));
+		assert!(high_scores.qualifies(KEY, 11));
+		assert!(high_scores.qualifies("solo/hard", 1));
+	}
+
+	#[test]
+	fn inserts_in_order_oldest_first_among_ties() {
+		let mut high_scores = HighScores::new(Path::new("unused"));
+		assert_eq!(high_scores.insert(KEY, "AAA", 100), Some(0));
+		assert_eq!(high_scores.insert(KEY, "BBB", 300), Some(0));
+		assert_eq!(high_scores.insert(KEY, "CCC", 100), Some(2));
+		assert_eq!(high_scores.insert(KEY, "DDD", 200), Some(1));
+		assert_eq!(scores(&high_scores, KEY), vec![("BBB".to_string(), 300), ("DDD".to_string(), 200), ("AAA".to_string(), 100), ("CCC".to_string(), 100)]);
+
+		for _ in 0..HIGH_SCORES_PER_TABLE {
+			high_scores.insert(KEY, "EEE", 500);
+		}
+		assert_eq!(high_scores.table(KEY).len(), HIGH_SCORES_PER_TABLE);
+		assert_eq!(high_scores.insert(KEY, "FFF", 400), None);
+	}
+
+	#[test]
+	fn saves_and_loads_back() {
+		let directory = env::temp_dir().join(format!("rspace-highscores-test-{}", std::process::id()));
+		let path = directory.join(HIGH_SCORES_FILE);
+		let mut high_scores = HighScores::new(&path);
+		high_scores.insert(KEY, "AAA", 300);
+		high_scores.insert("coop2/hard", "BBB", 100);
+		high_scores.save().unwrap();
+		assert!(!path.with_extension("tmp").exists());
+
+		let loaded = HighScores::load(&path);
+		assert_eq!(scores(&loaded, KEY), scores(&high_scores, KEY));
+		assert_eq!(scores(&loaded, "coop2/hard"), scores(&high_scores, "coop2/hard"));
+		fs::remove_dir_all(&directory).unwrap();
+	}
+}
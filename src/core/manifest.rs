@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use sdl2::render::BlendMode;
+
+use super::filesystem::AssetFileSystem;
+
+pub const ASSET_MANIFEST_FILE: &str = "assets.manifest";
+
+// Grid of frames inside a texture, each frame being "width" x "height" pixels
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SpritesheetGrid {
+	pub num_width: usize,
+	pub num_height: usize,
+	pub width: u32,
+	pub height: u32,
+}
+
+pub struct TextureAsset {
+	pub path: String,
+	pub blend_mode: Option<BlendMode>,
+	pub alpha: Option<u8>,
+	pub grid: Option<SpritesheetGrid>,
+}
+
+pub struct FontAsset {
+	pub path: String,
+	pub size: u16,
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum SoundKind {
+	Effect,
+	Music
+}
+
+#[derive(Clone)]
+pub struct SoundAsset {
+	pub path: String,
+	pub kind: SoundKind,
+	pub volume: u8,
+	// Musics only : position the music restarts from once finished, instead of its beginning
+	pub loop_start_ms: Option<u32>,
+}
+
+/*
+	One asset per line : "<kind> <id> <path> [property=value ...]"
+	Available kinds are "texture", "font", "sound" and "music".
+	Texture properties : blend=none|blend|add|mod, alpha=0..255, grid=<columns>x<rows>:<width>x<height>
+	Font properties : size=<points> (mandatory)
+	Sound & music properties : volume=0..128
+	Music properties : loop_start=<milliseconds>
+		A looping music with a loop point is decoded in memory, so that the mixer jumps back to it without any gap.
+	Empty lines and lines starting with '#' are ignored.
+*/
+pub struct AssetManifest {
+	textures: HashMap<String, TextureAsset>,
+	fonts: HashMap<String, FontAsset>,
+	sounds: HashMap<String, SoundAsset>,
+}
+
+impl AssetManifest {
+	pub fn new() -> Self {
+		AssetManifest {
+			textures: HashMap::new(),
+			fonts: HashMap::new(),
+			sounds: HashMap::new()
+		}
+	}
+
+	pub fn load(files: &AssetFileSystem, filename: &str) -> Result<Self, String> {
+		let content = files.read_to_string(filename)?;
+		let manifest = Self::parse(&content).map_err(|e| format!("{}:{}", filename, e))?;
+		manifest.validate(files).map_err(|e| format!("{} : {}", filename, e))?;
+		Ok(manifest)
+	}
+
+	pub fn parse(content: &str) -> Result<Self, String> {
+		let mut manifest = AssetManifest::new();
+		for (line_index, line) in content.lines().enumerate() {
+			manifest.parse_line(line).map_err(|e| format!("{}: {}", line_index + 1, e))?;
+		}
+		Ok(manifest)
+	}
+
+	fn parse_line(&mut self, line: &str) -> Result<(), String> {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			return Ok(());
+		}
+
+		let mut tokens = line.split_whitespace();
+		let kind = tokens.next().unwrap();
+		let id = tokens.next().ok_or_else(|| "missing asset id".to_string())?;
+		let path = tokens.next().ok_or_else(|| format!("missing path for asset \"{}\"", id))?.to_string();
+		if self.contains(id) {
+			return Err(format!("duplicated asset id \"{}\"", id));
+		}
+
+		let mut properties = HashMap::new();
+		for token in tokens {
+			let (key, value) = Self::parse_property(token)?;
+			if properties.insert(key, value).is_some() {
+				return Err(format!("property \"{}\" is set twice", key));
+			}
+		}
+
+		match kind {
+			"texture" => {
+				let asset = Self::parse_texture(path, &mut properties)?;
+				self.textures.insert(id.to_string(), asset);
+			},
+			"font" => {
+				let size = properties.remove("size").ok_or_else(|| format!("font \"{}\" has no size", id))?;
+				let size = size.parse::<u16>().map_err(|_| format!("invalid font size \"{}\"", size))?;
+				self.fonts.insert(id.to_string(), FontAsset { path, size });
+			},
+			"sound" | "music" => {
+				let volume = match properties.remove("volume") {
+					Some(volume) => Self::parse_u8(volume, 128)?,
+					None => 128,
+				};
+				let loop_start_ms = match properties.remove("loop_start").filter(|_| kind == "music") {
+					Some(loop_start) => Some(loop_start.parse::<u32>().map_err(|_| format!("invalid loop start \"{}\"", loop_start))?),
+					None => None,
+				};
+				let kind = if kind == "sound" { SoundKind::Effect } else { SoundKind::Music };
+				self.sounds.insert(id.to_string(), SoundAsset { path, kind, volume, loop_start_ms });
+			},
+			_ => return Err(format!("unknown asset kind \"{}\"", kind))
+		}
+
+		if let Some(key) = properties.keys().next() {
+			return Err(format!("unknown property \"{}\" for {} \"{}\"", key, kind, id));
+		}
+		Ok(())
+	}
+
+	fn parse_property(token: &str) -> Result<(&str, &str), String> {
+		let mut key_value = token.splitn(2, '=');
+		match (key_value.next(), key_value.next()) {
+			(Some(key), Some(value)) if !key.is_empty() && !value.is_empty() => Ok((key, value)),
+			_ => Err(format!("invalid property \"{}\", expected key=value", token))
+		}
+	}
+
+	fn parse_u8(value: &str, max: u8) -> Result<u8, String> {
+		match value.parse::<u8>() {
+			Ok(v) if v <= max => Ok(v),
+			_ => Err(format!("invalid value \"{}\", expected a number between 0 and {}", value, max))
+		}
+	}
+
+	fn parse_texture(path: String, properties: &mut HashMap<&str, &str>) -> Result<TextureAsset, String> {
+		let blend_mode = match properties.remove("blend") {
+			Some("none") => Some(BlendMode::None),
+			Some("blend") => Some(BlendMode::Blend),
+			Some("add") => Some(BlendMode::Add),
+			Some("mod") => Some(BlendMode::Mod),
+			Some(other) => return Err(format!("unknown blend mode \"{}\"", other)),
+			None => None,
+		};
+		let alpha = match properties.remove("alpha") {
+			Some(alpha) => Some(Self::parse_u8(alpha, u8::MAX)?),
+			None => None,
+		};
+		let grid = match properties.remove("grid") {
+			Some(grid) => Some(Self::parse_grid(grid)?),
+			None => None,
+		};
+		Ok(TextureAsset { path, blend_mode, alpha, grid })
+	}
+
+	fn parse_pair<T: std::str::FromStr>(value: &str) -> Option<(T, T)> {
+		let mut parts = value.splitn(2, 'x');
+		let first = parts.next()?.parse::<T>().ok()?;
+		let second = parts.next()?.parse::<T>().ok()?;
+		Some((first, second))
+	}
+
+	fn parse_grid(value: &str) -> Result<SpritesheetGrid, String> {
+		let error = || format!("invalid grid \"{}\", expected <columns>x<rows>:<width>x<height>", value);
+		let mut parts = value.splitn(2, ':');
+		let (num_width, num_height) = parts.next().and_then(Self::parse_pair::<usize>).ok_or_else(error)?;
+		let (width, height) = parts.next().and_then(Self::parse_pair::<u32>).ok_or_else(error)?;
+		if num_width == 0 || num_height == 0 || width == 0 || height == 0 {
+			return Err(error());
+		}
+		Ok(SpritesheetGrid { num_width, num_height, width, height })
+	}
+
+	fn contains(&self, id: &str) -> bool {
+		self.textures.contains_key(id) || self.fonts.contains_key(id) || self.sounds.contains_key(id)
+	}
+
+	// (asset id, file path) of every asset
+	pub fn files(&self) -> impl Iterator<Item = (&String, &String)> {
+		self.textures.iter().map(|(id, asset)| (id, &asset.path))
+			.chain(self.fonts.iter().map(|(id, asset)| (id, &asset.path)))
+			.chain(self.sounds.iter().map(|(id, asset)| (id, &asset.path)))
+	}
+
+	// Every referenced file must exist, so that a bad path is reported at startup rather than in the middle of a game
+	pub fn validate(&self, files: &AssetFileSystem) -> Result<(), String> {
+		for (id, path) in self.files() {
+			if !files.exists(path) {
+				return Err(format!("file \"{}\" of asset \"{}\" not found", path, id));
+			}
+		}
+		Ok(())
+	}
+
+	pub fn texture(&self, id: &str) -> Result<&TextureAsset, String> {
+		self.textures.get(id).ok_or_else(|| format!("Unknown texture asset \"{}\"", id))
+	}
+
+	pub fn font(&self, id: &str) -> Result<&FontAsset, String> {
+		self.fonts.get(id).ok_or_else(|| format!("Unknown font asset \"{}\"", id))
+	}
+
+	pub fn sounds(&self) -> impl Iterator<Item = (&String, &SoundAsset)> {
+		self.sounds.iter()
+	}
+
+	pub fn sound(&self, id: &str) -> Result<&SoundAsset, String> {
+		self.sounds.get(id).ok_or_else(|| format!("Unknown sound asset \"{}\"", id))
+	}
+}
+
+impl Default for AssetManifest {
+	fn default() -> Self {
+		AssetManifest::new()
+	}
+}
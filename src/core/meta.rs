@@ -3,7 +3,6 @@ use std::{any::{Any}, sync::atomic::{AtomicUsize, Ordering}};
 use fixedbitset::FixedBitSet;
 use generic_static::StaticTypeMap;
 use once_cell::sync::OnceCell;
-use tuple_list::TupleList;
 
 pub trait Initable {
 	fn is_init(&self) -> bool;
@@ -104,7 +103,8 @@ impl TypeMaskSetBit for () {
 
 impl<Head, Tail> TypeMaskSetBit for (Head, Tail) where
 	Head: 'static,
-	Tail: TypeMaskSetBit + TupleList,
+	// No TupleList bound : tuple_list only implements it up to 12 elements
+	Tail: TypeMaskSetBit,
 {
 	fn set_bitset(unique_counter: &'static IdCounter, bitset: &mut FixedBitSet) {
 		let component_id = numeric_type_id::<Head>(unique_counter);
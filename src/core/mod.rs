@@ -5,4 +5,14 @@ pub mod resources;
 pub mod states;
 pub mod animation;
 pub mod events;
-pub mod meta;
\ No newline at end of file
+pub mod meta;
+pub mod manifest;
+pub mod filesystem;
+pub mod audio;
+pub mod actions;
+pub mod controllers;
+pub mod enemies;
+pub mod highscores;
+pub mod difficulty;
+pub mod rank;
+pub mod progress;
\ No newline at end of file
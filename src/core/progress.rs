@@ -0,0 +1,67 @@
+use std::{collections::BTreeSet, fs, path::{Path, PathBuf}};
+
+use super::highscores::{data_directory, write_atomically};
+
+pub const PROGRESS_FILE: &str = "progress.txt";
+
+/*
+	Levels unlocked in the campaign, one level id per line (see levels.def).
+	The first level is always unlocked. Empty lines and lines starting with '#' are ignored.
+*/
+pub struct CampaignProgress {
+	path: PathBuf,
+	unlocked: BTreeSet<String>,
+}
+
+impl CampaignProgress {
+	pub fn new(path: &Path) -> Self {
+		CampaignProgress {
+			path: path.to_path_buf(),
+			unlocked: BTreeSet::new()
+		}
+	}
+
+	// Next to the high scores
+	pub fn default_path() -> PathBuf {
+		data_directory().join(PROGRESS_FILE)
+	}
+
+	// Never fails : an unreadable file only locks the levels again
+	pub fn load(path: &Path) -> Self {
+		let mut progress = CampaignProgress::new(path);
+		if !path.is_file() {
+			return progress;
+		}
+		match fs::read_to_string(path) {
+			Ok(content) => {
+				for line in content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+					progress.unlocked.insert(line.to_string());
+				}
+			},
+			Err(e) => println!("UNREADABLE PROGRESS FILE {} : {}", path.display(), e)
+		}
+		progress
+	}
+
+	fn format(&self) -> String {
+		let mut content = String::from("# Unlocked levels\n");
+		for level in &self.unlocked {
+			content.push_str(&format!("{}\n", level));
+		}
+		content
+	}
+
+	pub fn save(&self) -> Result<(), String> {
+		write_atomically(&self.path, &self.format())
+	}
+
+	// "index" : position of the level in the campaign
+	pub fn is_unlocked(&self, index: usize, level_id: &str) -> bool {
+		index == 0 || self.unlocked.contains(level_id)
+	}
+
+	// Returns true when the level was locked
+	pub fn unlock(&mut self, level_id: &str) -> bool {
+		self.unlocked.insert(level_id.to_string())
+	}
+}
@@ -0,0 +1,155 @@
+// Rank points, from 0 when the players struggle to RANK_MAX when they dominate
+pub const RANK_MAX: u32 = 1000;
+const RANK_START: u32 = RANK_MAX / 2;
+// The rank is advanced once per game frame and only changes at fixed steps, with integer points,
+// so that the same events at the same frames give the same rank whatever the wall clock says (replays)
+pub const RANK_STEP_FRAMES: u32 = 30;
+const KILL_POINTS: u32 = 3;
+const HIT_POINTS: u32 = 40;
+const DEATH_POINTS: u32 = 150;
+// Points won at each step without losing a ship, one more every SURVIVAL_RAMP_FRAMES since the last death
+const SURVIVAL_RAMP_FRAMES: u32 = 1800;
+const MAX_SURVIVAL_POINTS: u32 = 4;
+// Multiplier at rank 0 and at RANK_MAX, 1.0 at the starting rank
+const MIN_FACTOR: f32 = 0.75;
+const MAX_FACTOR: f32 = 1.5;
+
+// Dynamic difficulty, on top of the difficulty preset
+#[derive(Clone, Copy)]
+pub struct Rank {
+	points: u32,
+	// Not yet consumed by a step
+	elapsed_frames: u32,
+	frames_since_death: u32,
+	// During the current step
+	kills: u32,
+	hits: u32,
+	deaths: u32,
+	// Whole game, for the debug readout
+	total_kills: u32,
+	total_hits: u32,
+	total_deaths: u32
+}
+
+impl Rank {
+	pub fn new() -> Self {
+		Rank {
+			points: RANK_START,
+			elapsed_frames: 0,
+			frames_since_death: 0,
+			kills: 0,
+			hits: 0,
+			deaths: 0,
+			total_kills: 0,
+			total_hits: 0,
+			total_deaths: 0
+		}
+	}
+
+	pub fn reset(&mut self) {
+		*self = Rank::new();
+	}
+
+	pub fn on_kill(&mut self) {
+		self.kills += 1;
+		self.total_kills += 1;
+	}
+
+	pub fn on_hit(&mut self) {
+		self.hits += 1;
+		self.total_hits += 1;
+	}
+
+	pub fn on_death(&mut self) {
+		self.deaths += 1;
+		self.total_deaths += 1;
+	}
+
+	// To be called once per game frame, the frames out of the game (pause...) do not count
+	pub fn advance_frame(&mut self) {
+		self.elapsed_frames += 1;
+		if self.elapsed_frames >= RANK_STEP_FRAMES {
+			self.elapsed_frames = 0;
+			self.step();
+		}
+	}
+
+	fn step(&mut self) {
+		if self.deaths > 0 {
+			self.frames_since_death = 0;
+		} else {
+			self.frames_since_death += RANK_STEP_FRAMES;
+		}
+		let survival = if self.deaths > 0 { 0 } else { (1 + self.frames_since_death / SURVIVAL_RAMP_FRAMES).min(MAX_SURVIVAL_POINTS) };
+		let gain = self.kills * KILL_POINTS + survival;
+		let loss = self.hits * HIT_POINTS + self.deaths * DEATH_POINTS;
+		self.points = (self.points + gain).saturating_sub(loss).min(RANK_MAX);
+		self.kills = 0;
+		self.hits = 0;
+		self.deaths = 0;
+	}
+
+	pub fn points(&self) -> u32 {
+		self.points
+	}
+
+	// Multiplies the spawns, the shot frequency and the bullet speed
+	pub fn factor(&self) -> f32 {
+		if self.points < RANK_START {
+			MIN_FACTOR + (1.0 - MIN_FACTOR) * self.points as f32 / RANK_START as f32
+		} else {
+			1.0 + (MAX_FACTOR - 1.0) * (self.points - RANK_START) as f32 / (RANK_MAX - RANK_START) as f32
+		}
+	}
+
+	pub fn describe(&self) -> String {
+		format!("RANK {} x{:.2} K{} H{} D{}", self.points, self.factor(), self.total_kills, self.total_hits, self.total_deaths)
+	}
+}
+
+impl Default for Rank {
+	fn default() -> Self {
+		Rank::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Frame and event
+	type FrameEvent = (u32, fn(&mut Rank));
+
+	fn play(frames: u32, events: &[FrameEvent]) -> Rank {
+		let mut rank = Rank::new();
+		for frame in 0..frames {
+			for (_, event) in events.iter().filter(|(event_frame, _)| *event_frame == frame) {
+				event(&mut rank);
+			}
+			rank.advance_frame();
+		}
+		rank
+	}
+
+	#[test]
+	fn events_count_at_the_step_they_happen_in() {
+		let early = play(3600, &[(1, Rank::on_kill), (200, Rank::on_hit), (900, Rank::on_death)]);
+		let late = play(3600, &[(RANK_STEP_FRAMES - 1, Rank::on_kill), (RANK_STEP_FRAMES * 7 - 1, Rank::on_hit), (RANK_STEP_FRAMES * 31 - 1, Rank::on_death)]);
+		assert_eq!(early.points(), late.points());
+	}
+
+	#[test]
+	fn points_change_only_at_steps() {
+		let rank = play(RANK_STEP_FRAMES - 1, &[(0, Rank::on_kill)]);
+		assert_eq!(rank.points(), RANK_START);
+		let rank = play(RANK_STEP_FRAMES, &[(0, Rank::on_kill)]);
+		assert_eq!(rank.points(), RANK_START + KILL_POINTS + 1);
+	}
+
+	#[test]
+	fn death_resets_survival_ramp() {
+		let rank = play(RANK_STEP_FRAMES, &[(0, Rank::on_death)]);
+		assert_eq!(rank.points(), RANK_START - DEATH_POINTS);
+		assert_eq!(rank.frames_since_death, 0);
+	}
+}
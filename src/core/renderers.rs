@@ -1,16 +1,28 @@
-use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
 
+use sdl2::AudioSubsystem;
+use sdl2::GameControllerSubsystem;
 use sdl2::Sdl;
 use sdl2::VideoSubsystem;
+use sdl2::image::SaveSurface;
 use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect::Rect;
 use sdl2::render::Texture;
-use sdl2::render::{Canvas, TextureCreator};
+use sdl2::render::Canvas;
+use sdl2::surface::Surface;
 use sdl2::ttf::Font;
 use sdl2::ttf::{Sdl2TtfContext};
 use sdl2::video::{Window, WindowContext};
 
-use super::resources::{FontDetails};
+use super::common;
+use super::manifest::AssetManifest;
+use super::manifest::SpritesheetGrid;
+use super::manifest::TextureAsset;
+use super::filesystem::AssetFileSystem;
+use super::resources::{FontDetails, FontLoader, ResourceHandle, ResourceStats, TextureLoader};
 use super::resources::FontManager;
 use super::resources::TextureManager;
 
@@ -52,6 +64,14 @@ impl SdlDrawContext {
 				.map_err(|e| e.to_string()).unwrap()
 	}
 
+	pub fn audio_subsystem(&self) -> Result<AudioSubsystem, String> {
+		self.sdl_context.audio()
+	}
+
+	pub fn game_controller_subsystem(&self) -> Result<GameControllerSubsystem, String> {
+		self.sdl_context.game_controller()
+	}
+
 	pub fn event_pump(&self) -> Result<sdl2::EventPump, String> {
 		self.sdl_context.event_pump()
 	}
@@ -65,33 +85,175 @@ impl SdlDrawContext {
 	}
 }
 
+// Handles of the resources loaded while a state is active
+#[derive(Default)]
+struct ResourceScope {
+	textures: HashMap<i64, ResourceHandle>,
+	fonts: HashMap<i64, ResourceHandle>
+}
+
+pub struct ResourceMemoryStats {
+	pub textures: ResourceStats,
+	pub texture_bytes: usize,
+	pub fonts: ResourceStats
+}
+
+impl std::fmt::Display for ResourceMemoryStats {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{} shared + {} unique textures ({} KiB), {} fonts ({} KiB)", self.textures.shared, self.textures.unique, self.texture_bytes / 1024, self.fonts.shared + self.fonts.unique, self.fonts.source_bytes / 1024)
+	}
+}
+
 pub struct SdlResourceManager<'sdl_all> {
 	texture_manager: TextureManager<'sdl_all, WindowContext>,
-	font_manager: FontManager<'sdl_all>
+	font_manager: FontManager<'sdl_all>,
+	manifest: AssetManifest,
+	texture_asset_ids: HashMap<i64, String>,
+	hot_reload_last_poll_ms: Option<u64>,
+	// The first scope is the global one, it is never released
+	scopes: Vec<ResourceScope>
 }
 
+const HOT_RELOAD_POLL_INTERVAL_MS: u64 = 500;
+
 impl<'sdl_all> SdlResourceManager<'sdl_all> {
-	pub fn new(draw_context: &'sdl_all SdlDrawContext, texture_creator: &'sdl_all TextureCreator<WindowContext>) -> Self {
-		SdlResourceManager {
-			texture_manager: TextureManager::new(texture_creator),
-			font_manager: FontManager::new(&draw_context.font_context)
+	pub fn new(files: &AssetFileSystem, texture_loader: &'sdl_all TextureLoader<'sdl_all, WindowContext>, font_loader: &'sdl_all FontLoader<'sdl_all>, manifest_filename: &str) -> Result<Self, String> {
+		Ok(SdlResourceManager {
+			texture_manager: TextureManager::new(texture_loader),
+			font_manager: FontManager::new(font_loader),
+			manifest: AssetManifest::load(files, manifest_filename)?,
+			texture_asset_ids: HashMap::new(),
+			hot_reload_last_poll_ms: None,
+			scopes: vec![ResourceScope::default()]
+		})
+	}
+
+	// Every resource loaded from now on is kept at least until the matching "pop_scope"
+	pub fn push_scope(&mut self) {
+		self.scopes.push(ResourceScope::default());
+	}
+
+	pub fn pop_scope(&mut self) {
+		if self.scopes.len() > 1 {
+			self.scopes.pop();
+			self.release_dropped();
+		}
+	}
+
+	// Releases the resources of the current scope, while staying in it
+	pub fn clear_scope(&mut self) {
+		if self.scopes.len() > 1 {
+			*self.scopes.last_mut().unwrap() = ResourceScope::default();
+			self.release_dropped();
 		}
 	}
 
-	pub fn load_shared_texture(&mut self, filename: &str) -> Result<i64, String> {
-		match self.texture_manager.load_shared(filename) {
-			Ok(t) => Ok(t.1),
-			Err(err) => { return Err(err); },
+	fn release_dropped(&mut self) {
+		self.release_dropped_textures();
+		self.font_manager.release_dropped();
+		if cfg!(feature = "dev-mode") {
+			println!("RESOURCES {}", self.stats());
 		}
 	}
 
-	pub fn load_font(&mut self, font_details: &FontDetails) -> Result<i64, String> {
-		match self.font_manager.load_shared(font_details) {
-			Ok(t) => Ok(t.1),
-			Err(err) => { return Err(err); },
+	// The texture manager also releases before loading, this must be called first to forget the asset ids
+	fn release_dropped_textures(&mut self) {
+		for index in self.texture_manager.release_dropped() {
+			self.texture_asset_ids.remove(&index);
 		}
 	}
 
+	// Releases a texture before the end of its scope, its index must not be used anymore
+	pub fn release_texture(&mut self, texture_index: i64) {
+		for scope in self.scopes.iter_mut() {
+			scope.textures.remove(&texture_index);
+		}
+		self.release_dropped();
+	}
+
+	fn keep_texture(&mut self, handle: ResourceHandle) -> i64 {
+		let index = handle.index();
+		self.scopes.last_mut().unwrap().textures.insert(index, handle);
+		index
+	}
+
+	fn keep_font(&mut self, handle: ResourceHandle) -> i64 {
+		let index = handle.index();
+		self.scopes.last_mut().unwrap().fonts.insert(index, handle);
+		index
+	}
+
+	pub fn stats(&self) -> ResourceMemoryStats {
+		let texture_bytes = self.texture_manager.loaded().map(|texture| {
+			let query = texture.query();
+			query.format.byte_size_of_pixels((query.width * query.height) as usize)
+		}).sum();
+		ResourceMemoryStats {
+			textures: self.texture_manager.stats(),
+			texture_bytes,
+			fonts: self.font_manager.stats()
+		}
+	}
+
+	// Development only : makes "hot_reload" watch loaded textures and fonts files
+	pub fn enable_hot_reload(&mut self) {
+		println!("HOT RELOAD ENABLED");
+		self.hot_reload_last_poll_ms = Some(0);
+	}
+
+	// Reloads the texture and font files that changed since their loading, keeping their indexes
+	pub fn hot_reload(&mut self) {
+		let now = common::current_time_ms();
+		match self.hot_reload_last_poll_ms {
+			Some(last_poll_ms) if now - last_poll_ms >= HOT_RELOAD_POLL_INTERVAL_MS => {
+				self.hot_reload_last_poll_ms = Some(now);
+			},
+			_ => return
+		}
+
+		for index in self.texture_manager.reload_modified() {
+			// Properties from the manifest are per texture, they must be applied again to the new one
+			let manifest = &self.manifest;
+			if let Some(asset) = self.texture_asset_ids.get(&index).and_then(|id| manifest.texture(id).ok()) {
+				Self::apply_texture_properties(self.texture_manager.from_index_mut(index).unwrap(), asset);
+			}
+		}
+		self.font_manager.reload_modified();
+	}
+
+	pub fn manifest(&self) -> &AssetManifest {
+		&self.manifest
+	}
+
+	fn apply_texture_properties(texture: &mut Texture<'sdl_all>, asset: &TextureAsset) {
+		if let Some(blend_mode) = asset.blend_mode {
+			texture.set_blend_mode(blend_mode);
+		}
+		if let Some(alpha) = asset.alpha {
+			texture.set_alpha_mod(alpha);
+		}
+	}
+
+	pub fn load_shared_texture(&mut self, asset_id: &str) -> Result<i64, String> {
+		self.release_dropped_textures();
+		let asset = self.manifest.texture(asset_id)?;
+		let handle = self.texture_manager.load_shared(asset.path.as_str())?;
+		let index = handle.index();
+		Self::apply_texture_properties(self.texture_manager.from_index_mut(index).unwrap(), asset);
+		self.texture_asset_ids.insert(index, asset_id.to_string());
+		Ok(self.keep_texture(handle))
+	}
+
+	pub fn texture_grid(&self, asset_id: &str) -> Result<Option<SpritesheetGrid>, String> {
+		Ok(self.manifest.texture(asset_id)?.grid)
+	}
+
+	pub fn load_font(&mut self, asset_id: &str) -> Result<i64, String> {
+		let asset = self.manifest.font(asset_id)?;
+		let handle = self.font_manager.load_shared(&FontDetails { path: asset.path.clone(), size: asset.size })?;
+		Ok(self.keep_font(handle))
+	}
+
 	pub fn get_texture(&self, texture_index: i64) -> Option<&Texture<'sdl_all>> {
 		self.texture_manager.from_index(texture_index)
 	}
@@ -104,17 +266,18 @@ impl<'sdl_all> SdlResourceManager<'sdl_all> {
 		self.font_manager.from_index(texture_index)
 	}
 
-	pub fn load_unique_texture(&mut self, filename: &str) -> Result<i64, String> {
-		match self.texture_manager.load_unique(filename) {
-			Ok(t) => Ok(t.1),
-			Err(err) => { return Err(err); },
-		}
-	}
-
-	pub fn remove_unique_texture(&mut self, texture_index: i64) {
-		self.texture_manager.remove_unique(texture_index)
+	pub fn load_unique_texture(&mut self, asset_id: &str) -> Result<i64, String> {
+		self.release_dropped_textures();
+		let asset = self.manifest.texture(asset_id)?;
+		let handle = self.texture_manager.load_unique(asset.path.as_str())?;
+		let index = handle.index();
+		Self::apply_texture_properties(self.texture_manager.from_index_mut(index).unwrap(), asset);
+		self.texture_asset_ids.insert(index, asset_id.to_string());
+		Ok(self.keep_texture(handle))
 	}
 
+	// Renders the text in a new texture, or in place of the existing one when it is still loaded
+	// (an index released in the meantime is rejected, even if its slot was reused)
 	pub fn text_to_texture(&mut self, font_index: i64, text: &str, existing_texture_index: Option<i64>) -> Result<(i64, Rect), String> {
 		if let Some(font) = self.get_font(font_index) {
 			let surface = font
@@ -122,13 +285,21 @@ impl<'sdl_all> SdlResourceManager<'sdl_all> {
 			.blended(Color::WHITE)
 			.map_err(|e| e.to_string())?;
 
-			let texture = self.texture_manager.loader
+			let texture = self.texture_manager.loader.creator
 			.create_texture_from_surface(&surface)
 			.map_err(|e| e.to_string())?;
 
-			Ok((self.texture_manager.take_from_existing(Box::new(texture), existing_texture_index), surface.rect()))
+			if let Some(index) = existing_texture_index {
+				if self.texture_manager.replace_unique(index, Box::new(texture)).is_ok() {
+					return Ok((index, surface.rect()));
+				}
+				return self.text_to_texture(font_index, text, None);
+			}
+			self.release_dropped_textures();
+			let handle = self.texture_manager.insert_unique(Box::new(texture));
+			Ok((self.keep_texture(handle), surface.rect()))
 		} else {
-			Err("Unable to find font with index {}".to_string())
+			Err(format!("Unable to find font with index {}", font_index))
 		}
 	}
 
@@ -172,10 +343,68 @@ impl Ord for Renderable {
 	}
 }
 
+pub const DEFAULT_FRAME_CAPTURE_EVERY_N_FRAMES: u32 = 2;
+// Frames read back but not encoded yet, the game waits for the encoding thread when it is full
+const CAPTURE_QUEUE_SIZE: usize = 8;
+
+// Pixels read back from the canvas, written to a PNG file by the encoding thread
+struct CapturedFrame {
+	filename: String,
+	pixels: Vec<u8>,
+	width: u32,
+	height: u32,
+	screenshot: bool
+}
+
+impl CapturedFrame {
+	const FORMAT: PixelFormatEnum = PixelFormatEnum::ABGR8888;
+
+	fn save(&mut self) -> Result<(), String> {
+		let pitch = Self::FORMAT.byte_size_of_pixels(self.width as usize) as u32;
+		let surface = Surface::from_data(self.pixels.as_mut_slice(), self.width, self.height, pitch, Self::FORMAT)?;
+		surface.save(&self.filename)
+	}
+}
+
+// Writes every Nth presented frame to "<prefix><frame number>.png"
+pub struct FrameCapture {
+	prefix: String,
+	every_n_frames: u32,
+	frame_counter: u32,
+	next_file_index: u32
+}
+
+impl FrameCapture {
+	pub fn new(prefix: &str, every_n_frames: u32) -> Self {
+		FrameCapture {
+			prefix: prefix.to_string(),
+			every_n_frames: u32::max(every_n_frames, 1),
+			frame_counter: 0,
+			next_file_index: 0
+		}
+	}
+
+	fn next_filename(&mut self) -> Option<String> {
+		let capture = self.frame_counter.is_multiple_of(self.every_n_frames);
+		self.frame_counter += 1;
+		if capture {
+			let filename = format!("{}{:06}.png", self.prefix, self.next_file_index);
+			self.next_file_index += 1;
+			Some(filename)
+		} else {
+			None
+		}
+	}
+}
+
 // TODO SceneGraph, or QuadTree, or I don't know...
 pub struct SdlRenderer {
 	canvas: Canvas<Window>,
-	renderables: Vec<Renderable>
+	renderables: Vec<Renderable>,
+	screenshot_request: Option<String>,
+	frame_capture: Option<FrameCapture>,
+	// Started with the first capture, PNG encoding is too slow to be done in the frame
+	capture_thread: Option<(SyncSender<CapturedFrame>, JoinHandle<()>)>
 }
 
 impl SdlRenderer {
@@ -183,7 +412,10 @@ impl SdlRenderer {
 		println!("Using SDL_Renderer \"{}\"", canvas.info().name);
 		let mut renderer = SdlRenderer {
 			canvas: canvas,
-			renderables: Vec::new()
+			renderables: Vec::new(),
+			screenshot_request: None,
+			frame_capture: None,
+			capture_thread: None
 		};
 		renderer.set_draw_color(Color::RGB(0, 0, 0));
 		renderer
@@ -200,7 +432,7 @@ impl SdlRenderer {
 	fn render<'sdl_all>(canvas: &mut Canvas<Window>, resource_manager: &SdlResourceManager<'sdl_all>, texture_index: i64, src: Option<Rect>, dst: Option<Rect>, angle_degrees: u64, flip_horizontal: bool, flip_vertical: bool) -> Result<(), String> {
 		let texture = resource_manager.get_texture(texture_index);
 		if let Some(value) = texture {
-			canvas.copy_ex(value.borrow(), src, dst, (angle_degrees as f64 / 180.0) * std::f64::consts::PI, None, flip_horizontal, flip_vertical)
+			canvas.copy_ex(value, src, dst, angle_degrees as f64, None, flip_horizontal, flip_vertical)
 		} else {
 			Err("No Texture".to_owned())
 		}
@@ -220,9 +452,80 @@ impl SdlRenderer {
 			}
 		}
 		self.renderables.clear();
+		self.capture();
 		self.present();
 	}
 
+	// Dumps the current content of the canvas to a PNG file, using the SDL image module.
+	// The PNG is encoded on the calling thread, which takes several frames: the frame capture
+	// goes through the encoding thread instead
+	pub fn save_screenshot(&self, filename: &str) -> Result<(), String> {
+		self.read_frame(filename, true)?.save()
+	}
+
+	fn read_frame(&self, filename: &str, screenshot: bool) -> Result<CapturedFrame, String> {
+		let (width, height) = self.canvas.output_size()?;
+		let pixels = self.canvas.read_pixels(None, CapturedFrame::FORMAT)?;
+		Ok(CapturedFrame { filename: filename.to_string(), pixels, width, height, screenshot })
+	}
+
+	fn queue_frame(&mut self, frame: CapturedFrame) -> Result<(), String> {
+		let (sender, _) = self.capture_thread.get_or_insert_with(|| {
+			let (sender, receiver) = mpsc::sync_channel::<CapturedFrame>(CAPTURE_QUEUE_SIZE);
+			let handle = thread::spawn(move || {
+				for mut frame in receiver {
+					match (frame.save(), frame.screenshot) {
+						(Ok(_), true) => println!("SCREENSHOT SAVED TO {}", frame.filename),
+						(Ok(_), false) => {},
+						(Err(err), _) => println!("Unable to save {} : {}", frame.filename, err)
+					}
+				}
+			});
+			(sender, handle)
+		});
+		sender.send(frame).map_err(|_| "the capture thread has stopped".to_string())
+	}
+
+	// The screenshot is taken at the end of the next frame rendering
+	pub fn request_screenshot(&mut self, filename: &str) {
+		self.screenshot_request = Some(filename.to_string());
+	}
+
+	pub fn start_frame_capture(&mut self, prefix: &str, every_n_frames: u32) {
+		println!("FRAME CAPTURE STARTED \"{}\" (every {} frames)", prefix, every_n_frames);
+		self.frame_capture = Some(FrameCapture::new(prefix, every_n_frames));
+	}
+
+	pub fn stop_frame_capture(&mut self) {
+		println!("FRAME CAPTURE STOPPED");
+		self.frame_capture = None;
+	}
+
+	pub fn is_capturing_frames(&self) -> bool {
+		self.frame_capture.is_some()
+	}
+
+	fn capture(&mut self) {
+		if let Some(filename) = self.screenshot_request.take() {
+			if let Err(err) = self.read_frame(filename.as_str(), true).and_then(|frame| self.queue_frame(frame)) {
+				println!("Unable to save screenshot {} : {}", filename, err);
+			}
+		}
+
+		let next_filename = self.frame_capture.as_mut().and_then(|capture| capture.next_filename());
+		if let Some(filename) = next_filename {
+			if let Err(err) = self.read_frame(filename.as_str(), false).and_then(|frame| self.queue_frame(frame)) {
+				println!("Unable to capture frame {} : {}", filename, err);
+				self.frame_capture = None;
+			}
+		}
+	}
+
+	// To be called when the textures of the pending renderables may have been released
+	pub fn clear_renderables(&mut self) {
+		self.renderables.clear();
+	}
+
 	pub fn set_renderables(&mut self, renderables: Vec<Renderable>) {
 		self.renderables = renderables
 	}
@@ -231,3 +534,13 @@ impl SdlRenderer {
 		self.renderables.push(renderable)
 	}
 }
+
+impl Drop for SdlRenderer {
+	// The captured frames still queued are written before quitting
+	fn drop(&mut self) {
+		if let Some((sender, handle)) = self.capture_thread.take() {
+			drop(sender);
+			let _ = handle.join();
+		}
+	}
+}
@@ -3,11 +3,46 @@
 	FOR THIS CODE
 */
 
-use std::{borrow::Borrow, collections::HashMap, hash::Hash, rc::Rc};
+use std::{borrow::Borrow, cell::RefCell, collections::HashMap, fs, hash::Hash, path::{Path, PathBuf}, rc::{Rc, Weak}, time::SystemTime};
 
 use sdl2::image::LoadTexture;
 
-use sdl2::{render::{Texture, TextureCreator}, ttf::{Font, Sdl2TtfContext}};
+use sdl2::{render::{Texture, TextureCreator}, rwops::RWops, ttf::{Font, Sdl2TtfContext}};
+
+use super::filesystem::{AssetFileSystem, AssetSource};
+
+type ReleaseQueue = Rc<RefCell<Vec<i64>>>;
+
+struct HandleInner {
+	index: i64,
+	release_queue: ReleaseQueue
+}
+
+impl Drop for HandleInner {
+	fn drop(&mut self) {
+		self.release_queue.borrow_mut().push(self.index);
+	}
+}
+
+// Keeps a resource loaded : the resource is unloaded by its ResourceManager once the last handle is dropped
+#[derive(Clone)]
+pub struct ResourceHandle {
+	inner: Rc<HandleInner>
+}
+
+impl ResourceHandle {
+	pub fn index(&self) -> i64 {
+		self.inner.index
+	}
+}
+
+pub struct ResourceStats {
+	pub shared: usize,
+	pub unique: usize,
+	// Size of the files the resources were loaded from, when the loader knows it
+	pub source_bytes: usize
+}
+
 // Generic struct to cache any resource loaded by a ResourceLoader
 pub struct ResourceManager<'l, K, R, L>
 where
@@ -15,9 +50,50 @@ where
 	L: ResourceLoader<'l, R>,
 {
 	pub loader: &'l L,
-	cache: Vec<Rc<R>>,
+	cache: Vec<Option<Box<R>>>,
+	cache_handles: Vec<Weak<HandleInner>>,
+	cache_generations: Vec<u32>,
+	cache_free_slots: Vec<usize>,
 	index_cache: HashMap<K, usize>,
-	unique_cache: Vec<Option<Box<R>>>
+	unique_cache: Vec<Option<Box<R>>>,
+	unique_generations: Vec<u32>,
+	unique_free_slots: Vec<usize>,
+	// By index, see ResourceLoader::source_bytes
+	source_bytes: HashMap<i64, usize>,
+	release_queue: ReleaseQueue,
+	watched: Vec<WatchedResource<K>>
+}
+
+// A file loaded resource, with the modification time of its file when it was loaded
+struct WatchedResource<K> {
+	key: K,
+	index: i64,
+	file: PathBuf,
+	modified: Option<SystemTime>
+}
+
+fn file_modified_time(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/*
+	An index is made of a slot and the generation of that slot, which changes each time the slot is released.
+	So an index kept after its resource was released never reaches the resource reusing the slot.
+	Shared resources have positive indexes, unique ones negative indexes.
+*/
+const SLOT_BITS: u32 = 32;
+const SLOT_MASK: i64 = 0xFFFF_FFFF;
+const GENERATION_MASK: u32 = 0x7FFF_FFFF;
+
+fn make_index(slot: usize, generation: u32, unique: bool) -> i64 {
+	let raw = ((generation as i64) << SLOT_BITS) | slot as i64;
+	if unique { - raw - 1 } else { raw }
+}
+
+// (slot, generation)
+fn split_index(index: i64) -> (usize, u32) {
+	let raw = if index < 0 { - (index + 1) } else { index };
+	((raw & SLOT_MASK) as usize, (raw >> SLOT_BITS) as u32)
 }
 
 impl<'l, K, R, L> ResourceManager<'l, K, R, L>
@@ -28,119 +104,265 @@ where
 	pub fn new(loader: &'l L) -> Self {
 		ResourceManager {
 			cache: Vec::new(),
+			cache_handles: Vec::new(),
+			cache_generations: Vec::new(),
+			cache_free_slots: Vec::new(),
 			loader: loader,
 			index_cache: HashMap::new(),
-			unique_cache: Vec::new()
+			unique_cache: Vec::new(),
+			unique_generations: Vec::new(),
+			unique_free_slots: Vec::new(),
+			source_bytes: HashMap::new(),
+			release_queue: Rc::new(RefCell::new(Vec::new())),
+			watched: Vec::new()
+		}
+	}
+
+	fn new_handle(&self, index: i64) -> ResourceHandle {
+		ResourceHandle { inner: Rc::new(HandleInner { index, release_queue: self.release_queue.clone() }) }
+	}
+
+	// Unloads every resource which last handle has been dropped, returns their indexes
+	pub fn release_dropped(&mut self) -> Vec<i64> {
+		let released: Vec<i64> = self.release_queue.borrow_mut().drain(..).collect();
+		for index in released.iter().copied() {
+			let (slot, generation) = split_index(index);
+			let next_generation = generation.wrapping_add(1) & GENERATION_MASK;
+			if index < 0 {
+				self.unique_cache[slot] = None;
+				self.unique_generations[slot] = next_generation;
+				self.unique_free_slots.push(slot);
+			} else {
+				self.cache[slot] = None;
+				self.cache_handles[slot] = Weak::new();
+				self.cache_generations[slot] = next_generation;
+				self.index_cache.retain(|_, cached_slot| *cached_slot != slot);
+				self.cache_free_slots.push(slot);
+			}
+			self.watched.retain(|watched| watched.index != index);
+			self.source_bytes.remove(&index);
 		}
+		released
+	}
+
+	// Slot of the resource, None when the index is stale
+	fn slot(&self, index: i64) -> Option<usize> {
+		let (slot, generation) = split_index(index);
+		let generations = if index < 0 { &self.unique_generations } else { &self.cache_generations };
+		if generations.get(slot) == Some(&generation) { Some(slot) } else { None }
 	}
 
 	// Generics magic to allow a HashMap to use String as a key
 	// while allowing it to use &str for gets
-	pub fn load_shared<D>(&mut self, details: &D) -> Result<(Rc<R>, i64), String>
+	pub fn load_shared<D>(&mut self, details: &D) -> Result<ResourceHandle, String>
 	where
 		L: ResourceLoader<'l, R, Args = D>,
 		D: Eq + Hash + ?Sized,
 		K: Borrow<D> + for<'a> From<&'a D>,
 	{
+		self.release_dropped();
 		match self.index_cache.get(details.into()) {
 			None => {
 				let t = self.loader.borrow();
-				let resource = Rc::new(t.load(details)?);
-				let index = self.cache.len();
-				self.cache.push(resource.clone());
-				self.index_cache.insert(details.into(), index);
-				//println!("SHARED {}", index);
-				Ok((self.cache.last().unwrap().clone(), index as i64))
+				let resource = Box::new(t.load(details)?);
+				let slot = match self.cache_free_slots.pop() {
+					Some(slot) => {
+						self.cache[slot] = Some(resource);
+						slot
+					},
+					None => {
+						self.cache.push(Some(resource));
+						self.cache_handles.push(Weak::new());
+						self.cache_generations.push(0);
+						self.cache.len() - 1
+					}
+				};
+				let handle = self.new_handle(make_index(slot, self.cache_generations[slot], false));
+				self.cache_handles[slot] = Rc::downgrade(&handle.inner);
+				self.index_cache.insert(details.into(), slot);
+				self.source_bytes.insert(handle.index(), self.loader.source_bytes(details));
+				self.watch(details.into(), handle.index());
+				//println!("SHARED {}", slot);
+				Ok(handle)
 			},
-			Some(v) => Ok((self.cache[*v].clone(), *v as i64)),
+			Some(slot) => Ok(ResourceHandle { inner: self.cache_handles[*slot].upgrade().unwrap() }),
 		}
 	}
 
-	pub fn load_unique<D>(&mut self, details: &D) -> Result<(&R, i64), String>
+	pub fn load_unique<D>(&mut self, details: &D) -> Result<ResourceHandle, String>
 	where
 		L: ResourceLoader<'l, R, Args = D>,
 		D: Eq + Hash + ?Sized,
 		K: Borrow<D> + for<'a> From<&'a D>,
 	{
 		let resource = Box::new(self.loader.load(details)?);
-		let index = self.unique_cache.len();
-		self.unique_cache.push(Some(resource));
-		//println!("UNIQUE {}", - (index as i64) - 1);
-		Ok((self.unique_cache.last().as_ref().unwrap().as_ref().unwrap(), - (index as i64) - 1))
+		let handle = self.insert_unique(resource);
+		self.source_bytes.insert(handle.index(), self.loader.source_bytes(details));
+		self.watch(details.into(), handle.index());
+		//println!("UNIQUE {}", handle.index());
+		Ok(handle)
 	}
 
-	pub fn remove_unique(&mut self, index: i64) {
-		self.unique_cache[i64::abs(index + 1) as usize] = None;
+	// Stores a resource that was not created by the loader, such as a rendered text
+	pub fn insert_unique(&mut self, resource: Box<R>) -> ResourceHandle {
+		self.release_dropped();
+		let slot = match self.unique_free_slots.pop() {
+			Some(slot) => {
+				self.unique_cache[slot] = Some(resource);
+				slot
+			},
+			None => {
+				self.unique_cache.push(Some(resource));
+				self.unique_generations.push(0);
+				self.unique_cache.len() - 1
+			}
+		};
+		self.new_handle(make_index(slot, self.unique_generations[slot], true))
 	}
 
-	pub fn from_index(&self, index: i64) -> Option<&R> {
-		if index < 0 {
-			if let Some(resource) = self.unique_cache.get(i64::abs(index + 1) as usize) {
-				if resource.is_some() {
-					Some(resource.as_ref().unwrap().borrow())
-				} else {
-					None
-				}
-			} else {
-				None
-			}
-		} else {
-			if let Some(resource) = self.cache.get(index as usize) {
-				Some(resource.as_ref().borrow())
-			} else {
-				None
-			}
+	// Replaces a still loaded unique resource, keeping its index
+	pub fn replace_unique(&mut self, index: i64, resource: Box<R>) -> Result<(), String> {
+		match self.slot(index).filter(|_| index < 0).and_then(|slot| self.unique_cache[slot].as_mut()) {
+			Some(existing) => {
+				*existing = resource;
+				Ok(())
+			},
+			None => Err(format!("No unique resource with index {}", index))
 		}
 	}
 
-	pub fn from_index_mut(&mut self, index: i64) -> Option<&mut R> {
-		if index < 0 {
-			if let Some(resource) = self.unique_cache.get_mut(i64::abs(index + 1) as usize) {
-				if resource.is_some() {
-					Some(resource.as_mut().unwrap())
-				} else {
-					None
-				}
-			} else {
-				None
+	// Only resources loaded from a loose file can be watched
+	fn watch<D>(&mut self, key: K, index: i64)
+	where
+		L: ResourceLoader<'l, R, Args = D>,
+		D: ?Sized,
+		K: Borrow<D>,
+	{
+		if let Some(file) = self.loader.resolve_file(key.borrow()) {
+			let modified = file_modified_time(&file);
+			self.watched.push(WatchedResource { key, index, file, modified });
+		}
+	}
+
+	// Reloads in place every resource which file changed since it was loaded, so that indexes stay valid.
+	// Returns the indexes of the reloaded resources.
+	pub fn reload_modified<D>(&mut self) -> Vec<i64>
+	where
+		L: ResourceLoader<'l, R, Args = D>,
+		D: ?Sized,
+		K: Borrow<D>,
+	{
+		let mut reloaded = Vec::new();
+		for watched in self.watched.iter_mut() {
+			let modified = file_modified_time(&watched.file);
+			if modified.is_none() || modified == watched.modified {
+				continue;
 			}
-		} else {
-			if let Some(resource) = self.cache.get_mut(index as usize) {
-				Some(Rc::get_mut(resource).unwrap())
-			} else {
-				None
+			// Even if the reload fails (the file may be being written), wait for the next change before retrying
+			watched.modified = modified;
+			match self.loader.load(watched.key.borrow()) {
+				Ok(resource) => {
+					let (slot, _) = split_index(watched.index);
+					if watched.index < 0 {
+						self.unique_cache[slot] = Some(Box::new(resource));
+					} else {
+						self.cache[slot] = Some(Box::new(resource));
+					}
+					println!("RELOADED {}", watched.file.display());
+					reloaded.push(watched.index);
+				},
+				Err(err) => println!("Unable to reload {} : {}", watched.file.display(), err),
 			}
 		}
+		reloaded
+	}
+
+	pub fn from_index(&self, index: i64) -> Option<&R> {
+		let slot = self.slot(index)?;
+		let resource = if index < 0 { &self.unique_cache[slot] } else { &self.cache[slot] };
+		resource.as_deref()
 	}
 
-	pub fn take_from_existing(&mut self, resource: Box<R>, existing_texture_index: Option<i64>) -> i64 {
-		if let Some(id) = existing_texture_index {
-			let index = if id > 0 { id } else { -id - 1 } as usize;
-			self.unique_cache[index] = Some(resource);
-			id
-		} else {
-			let id = self.unique_cache.len() + 1;
-			self.unique_cache.push(Some(resource));
-			-(id as i64)
+	pub fn from_index_mut(&mut self, index: i64) -> Option<&mut R> {
+		let slot = self.slot(index)?;
+		let resource = if index < 0 { &mut self.unique_cache[slot] } else { &mut self.cache[slot] };
+		resource.as_deref_mut()
+	}
+
+	pub fn loaded(&self) -> impl Iterator<Item = &R> {
+		self.cache.iter().chain(self.unique_cache.iter()).filter_map(|resource| resource.as_deref())
+	}
+
+	pub fn stats(&self) -> ResourceStats {
+		ResourceStats {
+			shared: self.cache.iter().filter(|resource| resource.is_some()).count(),
+			unique: self.unique_cache.iter().filter(|resource| resource.is_some()).count(),
+			source_bytes: self.source_bytes.values().sum()
 		}
 	}
 }
 
-// TextureCreator knows how to load Textures
-impl<'l, T> ResourceLoader<'l, Texture<'l>> for TextureCreator<T> {
+// Loads Textures from the files found by the asset file system
+pub struct TextureLoader<'l, T> {
+	pub creator: &'l TextureCreator<T>,
+	files: &'l AssetFileSystem
+}
+
+impl<'l, T> TextureLoader<'l, T> {
+	pub fn new(creator: &'l TextureCreator<T>, files: &'l AssetFileSystem) -> Self {
+		TextureLoader { creator, files }
+	}
+}
+
+impl<'l, T> ResourceLoader<'l, Texture<'l>> for TextureLoader<'l, T> {
 	type Args = str;
 	fn load(&'l self, path: &str) -> Result<Texture<'l>, String> {
 		println!("LOADED A TEXTURE");
-		self.load_texture(path)
+		match self.files.open(path)? {
+			AssetSource::File(file) => self.creator.load_texture(file),
+			// Goes through a SDL RWops
+			AssetSource::Memory(content) => self.creator.load_texture_bytes(content)
+		}
+	}
+
+	fn resolve_file(&self, path: &str) -> Option<PathBuf> {
+		self.files.find_file(path)
+	}
+}
+
+// Loads Fonts from the files found by the asset file system
+pub struct FontLoader<'l> {
+	context: &'l Sdl2TtfContext,
+	files: &'l AssetFileSystem
+}
+
+impl<'l> FontLoader<'l> {
+	pub fn new(context: &'l Sdl2TtfContext, files: &'l AssetFileSystem) -> Self {
+		FontLoader { context, files }
 	}
 }
 
-// Font Context knows how to load Fonts
-impl<'l> ResourceLoader<'l, Font<'l, 'static>> for Sdl2TtfContext {
+impl<'l> ResourceLoader<'l, Font<'l, 'static>> for FontLoader<'l> {
 	type Args = FontDetails;
 	fn load(&'l self, details: &FontDetails) -> Result<Font<'l, 'static>, String> {
 		println!("LOADED A FONT");
-		self.load_font(&details.path, details.size)
+		match self.files.open(&details.path)? {
+			AssetSource::File(file) => self.context.load_font(file, details.size),
+			AssetSource::Memory(content) => self.context.load_font_from_rwops(RWops::from_bytes(content)?, details.size)
+		}
+	}
+
+	fn resolve_file(&self, details: &FontDetails) -> Option<PathBuf> {
+		self.files.find_file(&details.path)
+	}
+
+	// The whole font file is kept in memory by SDL_ttf
+	fn source_bytes(&self, details: &FontDetails) -> usize {
+		match self.files.open(&details.path) {
+			Ok(AssetSource::File(file)) => fs::metadata(file).map_or(0, |metadata| metadata.len() as usize),
+			Ok(AssetSource::Memory(content)) => content.len(),
+			Err(_) => 0
+		}
 	}
 }
 
@@ -148,6 +370,16 @@ impl<'l> ResourceLoader<'l, Font<'l, 'static>> for Sdl2TtfContext {
 pub trait ResourceLoader<'l, R> {
 	type Args: ?Sized;
 	fn load(&'l self, data: &Self::Args) -> Result<R, String>;
+
+	// The file the resource is loaded from, if any, to watch its changes
+	fn resolve_file(&self, _data: &Self::Args) -> Option<PathBuf> {
+		None
+	}
+
+	// Memory held by the loaded resource that can't be queried from it, 0 when unknown
+	fn source_bytes(&self, _data: &Self::Args) -> usize {
+		0
+	}
 }
 
 // Information needed to load a Font
@@ -166,5 +398,5 @@ impl<'a> From<&'a FontDetails> for FontDetails {
 	}
 }
 
-pub type TextureManager<'sdl_module, T> = ResourceManager<'sdl_module, String, Texture<'sdl_module>, TextureCreator<T>>;
-pub type FontManager<'ttf_module> = ResourceManager<'ttf_module, FontDetails, Font<'ttf_module, 'static>, Sdl2TtfContext>;
+pub type TextureManager<'sdl_module, T> = ResourceManager<'sdl_module, String, Texture<'sdl_module>, TextureLoader<'sdl_module, T>>;
+pub type FontManager<'ttf_module> = ResourceManager<'ttf_module, FontDetails, Font<'ttf_module, 'static>, FontLoader<'ttf_module>>;
@@ -1,16 +1,14 @@
 use std::sync::{atomic::AtomicUsize};
 
-use crate::sdl2;
 use fixedbitset::FixedBitSet;
 use once_cell::sync::OnceCell;
-use sdl2::event::Event;
-use tuple_list::TupleList;
 
-use super::{common::GameServices, ecs::{SystemHolder, WeakRunnable}, meta::{self, IdCounter, TypeMaskSetBit}};
+use super::{actions::ActionEvent, common::GameServices, ecs::{SystemHolder, WeakRunnable}, meta::{self, IdCounter, TypeMaskSetBit}};
 
 pub trait State {
 	fn on_enter<'sdl_all, 'l>(&mut self, runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, last_state_id: Option<usize>);
-	fn on_event(&mut self, event: &Event) -> bool;
+	// Returns true to quit the game
+	fn on_action(&mut self, action: &ActionEvent) -> bool;
 	fn update<'sdl_all, 'l>(&mut self, next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool;
 	fn on_leave<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>, destroy: bool);
 }
@@ -18,7 +16,7 @@ pub trait State {
 const STATES_MAX_SYSTEMS: usize = 100;
 
 pub trait StateSystems : State {
-	type Systems: TupleList + TypeMaskSetBit;
+	type Systems: TypeMaskSetBit;
 }
 
 pub struct StateWithSystems {
@@ -144,6 +142,7 @@ impl StateDispatcher {
 		if let Some(next_state) = self.next_state.take() {
 			let last_state_id = self.pause_current_state(game_services, false);
 			self.push_state(next_state);
+			game_services.resource_manager.push_scope();
 			self.refresh_systems(systems, game_services, last_state_id.as_ref(), *self.top_state_id().unwrap());
 			self.resume_state(runnables, game_services, true, last_state_id);
 		}
@@ -161,6 +160,8 @@ impl StateDispatcher {
 					self.refresh_systems(systems, game_services, last_state_id.as_ref(), n);
 				}
 				self.pop_state();
+				game_services.resource_manager.pop_scope();
+				game_services.renderer.clear_renderables();
 				self.resume_state(runnables, game_services, false, last_state_id);
 				! no_more_state
 			} else {
@@ -171,9 +172,9 @@ impl StateDispatcher {
 		}
 	}
 
-	pub fn dispatch_event(&mut self, event: &Event) -> bool {
+	pub fn dispatch_action(&mut self, action: &ActionEvent) -> bool {
 		if let Some(current_state) = self.top_state_mut() {
-			current_state.state.on_event(event)
+			current_state.state.on_action(action)
 		} else {
 			false
 		}
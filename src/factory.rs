@@ -1,31 +1,40 @@
 use rand::{Rng};
 
-use crate::{components::{ai::{DestinationPoint}, animation::AnimationComponent, force::ForceComponent, health::HealthComponent, hitbox::HitboxComponent, input::InputComponent, lifetime::LifetimeComponent, shot::{ShotComponent, ShotType}, sprite::{SpriteComponent, Spritesheet, SpritesheetOrientation}, transform::TransformComponent}, core::{animation::Animation, common::{self, GameServices}, ecs::EntityId}};
+use crate::{components::{ai::{AIComponent, DestinationPoint, TrajectorySequence}, animation::AnimationComponent, bomb::{BOMB_START_STOCK, BombComponent}, boss::BossComponent, bullet_pattern::BulletPatternComponent, enemy::EnemyComponent, force::ForceComponent, health::HealthComponent, hitbox::HitboxComponent, homing::HomingComponent, input::InputComponent, player::PlayerComponent, lifetime::LifetimeComponent, powerup::{PowerUpComponent, PowerUpKind}, shot::{ShotComponent, ShotEvent, ShotType}, sprite::{SpriteComponent, Spritesheet, SpritesheetOrientation}, transform::TransformComponent, weapon::{ProjectilePrefab, WeaponComponent, WeaponType}}, core::{animation::Animation, enemies::{EnemyAnimation, EnemyType}, common::{self, GameServices}, ecs::EntityId}};
 
-pub fn create_entity<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32, z: i64, width: u32, height: u32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+// Radians per frame
+const HOMING_TURN_RATE: f32 = 0.08;
+const POWERUP_SIZE: u32 = 24;
+const POWERUP_FALL_SPEED: f32 = 2.0;
+const POWERUP_LIFETIME_MS: u64 = 8000;
+const POPUP_RISE_SPEED: f32 = 1.0;
+const POPUP_LIFETIME_MS: u64 = 800;
+const POPUP_Z_INDEX: i64 = 5;
+const FLASH_Z_INDEX: i64 = 10;
+
+pub fn create_entity<'sdl_all, 'world>(texture_id: &str, x: i32, y: i32, z: i64, width: u32, height: u32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
 	let entity = game_services.get_world_mut().create_entity();
-	if ! texture_name.is_empty() {
-		let sprite = game_services.resource_manager.load_shared_texture(&texture_name);
-		game_services.get_world_mut().add_component(&entity, SpriteComponent::new(sprite.unwrap() as i64, width, height, z));
+	if ! texture_id.is_empty() {
+		let sprite = game_services.resource_manager.load_shared_texture(texture_id);
+		let mut sprite_component = SpriteComponent::new(sprite.unwrap(), width, height, z);
+		sprite_component.spritesheet = game_services.resource_manager.texture_grid(texture_id).unwrap().map(Spritesheet::from);
+		game_services.get_world_mut().add_component(&entity, sprite_component);
 	}
 	game_services.get_world_mut().add_component(&entity, HitboxComponent::new(0, 0, width, height));
 	game_services.get_world_mut().add_component(&entity, TransformComponent::new(x as f32, y as f32));
 	entity
 }
 
-pub fn create_physics_entity<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32, z: i64, width: u32, height: u32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId  {
-	let entity = create_entity(texture_name, x, y, z, width, height, game_services);
+pub fn create_physics_entity<'sdl_all, 'world>(texture_id: &str, x: i32, y: i32, z: i64, width: u32, height: u32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId  {
+	let entity = create_entity(texture_id, x, y, z, width, height, game_services);
 	game_services.get_world_mut().add_component(&entity, ForceComponent::new());
 	entity
 }
 
-pub fn create_living_entity<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32, z: i64, width: u32, height: u32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId  {
-	let entity = create_physics_entity(texture_name, x, y, z, width, height, game_services);
+pub fn create_living_entity<'sdl_all, 'world>(texture_id: &str, x: i32, y: i32, z: i64, width: u32, height: u32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId  {
+	let entity = create_physics_entity(texture_id, x, y, z, width, height, game_services);
 	game_services.get_world_mut().add_component(&entity, HealthComponent::new(1));
 
-	let sprite_component = game_services.get_world_mut().get_component_mut::<SpriteComponent>(&entity).unwrap();
-	sprite_component.spritesheet = Some(Spritesheet::new(3, 2, SpritesheetOrientation::HORIZONTAL, 16, 16));
-
 	let mut animation_component = AnimationComponent::new();
 	let animation = Animation::new(0).frames(3).time(80).count(1).clone();
 
@@ -42,20 +51,23 @@ pub fn create_living_entity<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32
 	entity
 }
 
-pub fn create_player<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32, z: i64, width: u32, height: u32, power: f32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
-	let entity = create_living_entity(texture_name, x, y, z, width, height, game_services);
+pub fn create_player<'sdl_all, 'world>(texture_id: &str, x: i32, y: i32, z: i64, width: u32, height: u32, power: f32, player_index: usize, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = create_living_entity(texture_id, x, y, z, width, height, game_services);
 	game_services.get_world_mut().add_component(&entity, InputComponent::new(power, true));
+	game_services.get_world_mut().add_component(&entity, PlayerComponent::new(player_index));
+	game_services.get_world_mut().add_component(&entity, WeaponComponent::new(WeaponType::Single));
+	game_services.get_world_mut().add_component(&entity, BombComponent::new(BOMB_START_STOCK));
 	entity
 }
 
-pub fn create_shot<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32, z: i64, width: u32, height: u32, vx: f32, vy: f32, lifetime: u64, origin: ShotType, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
-	let entity = create_physics_entity(texture_name, x, y, z, width, height, game_services);
+pub fn create_shot<'sdl_all, 'world>(texture_id: &str, x: i32, y: i32, z: i64, width: u32, height: u32, vx: f32, vy: f32, lifetime: u64, origin: ShotType, owner: EntityId, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = create_physics_entity(texture_id, x, y, z, width, height, game_services);
 	let world = game_services.get_world_mut();
-	world.add_component(&entity, ShotComponent::new(origin, 1));
+	let mut shot = ShotComponent::new(origin, 1, owner);
+	shot.owner_player = world.get_component::<PlayerComponent>(&owner).map(|player| player.index);
+	world.add_component(&entity, shot);
 	world.add_component(&entity, LifetimeComponent::new(common::current_time_ms() + lifetime));
 
-	let sprite_component = world.get_component_mut::<SpriteComponent>(&entity).unwrap();
-	sprite_component.spritesheet = Some(Spritesheet::new(7, 1, SpritesheetOrientation::HORIZONTAL, 16, 16));
 	let mut animation_component = AnimationComponent::new();
 	let animation = Animation::new(0).frames(7).time(40).count(1).start().clone();
 	animation_component.set(vec![animation]);
@@ -70,6 +82,85 @@ pub fn create_shot<'sdl_all, 'world>(texture_name: &str, x: i32, y: i32, z: i64,
 	hitbox.h /= 2;
 	hitbox.x += hitbox.w / 2;
 	hitbox.y += hitbox.h / 2;
+	game_services.event_dispatcher.notify(&ShotEvent { entity, shot_type: origin });
+	entity
+}
+
+// Enemy built from its registry type, its trajectories are set by the caller
+pub fn create_enemy<'sdl_all, 'world>(enemy_type: &EnemyType, x: i32, y: i32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = match enemy_type.animation {
+		EnemyAnimation::Ship => create_living_entity(&enemy_type.texture_id, x, y, 0, enemy_type.width, enemy_type.height, game_services),
+		EnemyAnimation::None => {
+			let entity = create_physics_entity(&enemy_type.texture_id, x, y, 0, enemy_type.width, enemy_type.height, game_services);
+			game_services.get_world_mut().add_component(&entity, HealthComponent::new(1));
+			entity
+		}
+	};
+	let difficulty = game_services.difficulty;
+	let world = game_services.get_world_mut();
+	world.get_component_mut::<HealthComponent>(&entity).unwrap().health_points = difficulty.enemy_health(enemy_type.health);
+	world.get_component_mut::<HitboxComponent>(&entity).unwrap().hitbox = enemy_type.hitbox;
+	let mut ai = AIComponent::new();
+	ai.speed = enemy_type.speed;
+	ai.shot_power = enemy_type.shot_power * difficulty.settings().shot_power;
+	ai.shot_frequency_factor = enemy_type.shot_frequency * difficulty.settings().shot_frequency;
+	world.add_component(&entity, ai);
+	world.add_component(&entity, BulletPatternComponent::new(enemy_type.pattern.clone()));
+	world.add_component(&entity, EnemyComponent::new(&enemy_type.id));
+	entity
+}
+
+// Large enemy whose AI is driven by the phases of its BossComponent
+pub fn create_boss<'sdl_all, 'world>(enemy_type: &EnemyType, x: i32, y: i32, mut boss: BossComponent, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = create_enemy(enemy_type, x, y, game_services);
+	let world = game_services.get_world_mut();
+	// The health bar follows the health scaled by the difficulty
+	boss.max_health = world.get_component::<HealthComponent>(&entity).unwrap().health_points;
+	// Holds still until the BossSystem starts the first phase, the AISystem would remove an enemy without trajectory
+	world.get_component_mut::<AIComponent>(&entity).unwrap().add_movement_pattern(TrajectorySequence::wait(u64::MAX));
+	world.add_component(&entity, boss);
+	entity
+}
+
+// Player projectile fired by a weapon
+pub fn create_projectile<'sdl_all, 'world>(prefab: &ProjectilePrefab, damages: i32, position: (i32, i32), velocity: (f32, f32), owner: EntityId, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = create_shot(prefab.texture_id, position.0, position.1, 1, prefab.width, prefab.height, velocity.0, velocity.1, prefab.lifetime_ms, ShotType::PLAYER, owner, game_services);
+	let world = game_services.get_world_mut();
+	let shot = world.get_component_mut::<ShotComponent>(&entity).unwrap();
+	shot.damages = damages;
+	shot.piercing = prefab.piercing;
+	if prefab.homing {
+		world.add_component(&entity, HomingComponent::new(prefab.speed, HOMING_TURN_RATE));
+	}
+	entity
+}
+
+// Falling orb, its frame in the "powerup" texture depends on its kind
+pub fn create_powerup<'sdl_all, 'world>(kind: PowerUpKind, x: i32, y: i32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = create_physics_entity("powerup", x - POWERUP_SIZE as i32 / 2, y - POWERUP_SIZE as i32 / 2, 2, POWERUP_SIZE, POWERUP_SIZE, game_services);
+	let world = game_services.get_world_mut();
+	world.get_component_mut::<SpriteComponent>(&entity).unwrap().spritesheet_index = (kind.frame(), 0);
+	world.get_component_mut::<ForceComponent>(&entity).unwrap().vy = POWERUP_FALL_SPEED;
+	world.add_component(&entity, PowerUpComponent::new(kind));
+	world.add_component(&entity, LifetimeComponent::new(common::current_time_ms() + POWERUP_LIFETIME_MS));
+	entity
+}
+
+// Text rising for a short while, like a "+50" score popup
+pub fn create_text_popup<'sdl_all, 'world>(texture_index: i64, width: u32, height: u32, x: i32, y: i32, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let entity = create_physics_entity("", x, y, 0, width, height, game_services);
+	let world = game_services.get_world_mut();
+	world.add_component(&entity, SpriteComponent::new(texture_index, width, height, POPUP_Z_INDEX));
+	world.get_component_mut::<ForceComponent>(&entity).unwrap().vy = -POPUP_RISE_SPEED;
+	world.add_component(&entity, LifetimeComponent::new(common::current_time_ms() + POPUP_LIFETIME_MS));
+	entity
+}
+
+// White flash over the whole screen
+pub fn create_flash<'sdl_all, 'world>(duration_ms: u64, game_services: &mut GameServices<'sdl_all, 'world>) -> EntityId {
+	let (width, height) = (game_services.draw_context.screen_width(), game_services.draw_context.screen_height());
+	let entity = create_entity("flash", 0, 0, FLASH_Z_INDEX, width, height, game_services);
+	game_services.get_world_mut().add_component(&entity, LifetimeComponent::new(common::current_time_ms() + duration_ms));
 	entity
 }
 
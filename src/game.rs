@@ -1,28 +1,52 @@
+use std::sync::Arc;
+use std::sync::RwLock;
 use std::thread::sleep;
 use std::time::Duration;
 
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
 use sdl2::render::Canvas;
-use sdl2::render::TextureCreator;
 use sdl2::video::Window;
 use sdl2::video::WindowContext;
 
+use crate::components::health::DamageEvent;
+use crate::components::health::DeathEvent;
+use crate::components::shot::ShotEvent;
+use crate::core::audio::AudioManager;
+use crate::core::audio::NullAudioManager;
+use crate::core::audio::SdlAudioManager;
 use crate::core::common;
-use crate::core::common::GameServices;
+use crate::core::controllers::GameControllers;
+use crate::core::common::{GameServices, GameSettings};
 use crate::core::ecs::Runnable;
 use crate::core::ecs::SystemHolder;
 use crate::core::ecs::WeakRunnable;
 use crate::core::ecs::World;
+use crate::core::ecs::make_shared_runnable;
+use crate::core::filesystem::AssetFileSystem;
+use crate::core::enemies::{ENEMY_TYPES_FILE, EnemyRegistry};
+use crate::core::manifest::ASSET_MANIFEST_FILE;
 use crate::core::renderers::SdlDrawContext;
 use crate::core::renderers::SdlRenderer;
 use crate::core::renderers::SdlResourceManager;
+use crate::core::resources::FontLoader;
+use crate::core::resources::TextureLoader;
 use crate::core::states::StateDispatcher;
 use crate::core::states::StateSystems;
+use crate::levels::definitions::{LEVELS_FILE, LevelRegistry};
 use crate::sdl2;
 use crate::systems::ai::AISystem;
 use crate::systems::animation::AnimationSystem;
+use crate::systems::audio::AudioSystem;
 use crate::systems::graphics::GraphicsSystem;
 use crate::systems::health::HealthSystem;
 use crate::systems::input::InputSystem;
+use crate::systems::invulnerability::InvulnerabilitySystem;
+use crate::systems::homing::HomingSystem;
+use crate::systems::powerup::PowerUpSystem;
+use crate::systems::bullet::BulletSystem;
+use crate::systems::boss::BossSystem;
+use crate::systems::bomb::BombSystem;
 use crate::systems::lifetime::LifetimeSystem;
 use crate::systems::physics::PhysicsSystem;
 use crate::systems::shot::ShotSystem;
@@ -34,11 +58,17 @@ pub struct Game<'sdl_all, 'game> {
 	state: StateDispatcher,
 	renderer: Option<SdlRenderer>,
 	resource_manager: Option<SdlResourceManager<'sdl_all>>,
+	audio_manager: Option<Box<dyn AudioManager + 'sdl_all>>,
+	audio_system: Option<Arc<RwLock<AudioSystem>>>,
+	controllers: Option<GameControllers>,
 	game_services: Option<GameServices<'sdl_all, 'game>>,
 	global_runnables: Vec<WeakRunnable>,
 	last_ms: u64
 }
 
+const SCREENSHOT_KEY: Keycode = Keycode::F12;
+const FRAME_CAPTURE_KEY: Keycode = Keycode::F11;
+
 pub trait RunnableNewable : Runnable {
 	fn new<'sdl_all, 'game>(game_services: &mut GameServices<'sdl_all, 'game>) -> Self;
 }
@@ -52,6 +82,9 @@ impl<'sdl_all, 'game> Game<'sdl_all, 'game> {
 			state: StateDispatcher::new(),
 			renderer: Option::None,
 			resource_manager: Option::None,
+			audio_manager: Option::None,
+			audio_system: Option::None,
+			controllers: Option::None,
 			game_services: Option::None,
 			global_runnables: Vec::new(),
 			last_ms: 0
@@ -66,9 +99,32 @@ impl<'sdl_all, 'game> Game<'sdl_all, 'game> {
 		game.systems.add_system::<AISystem, ()>(&mut game.world, ());
 		game.systems.add_system::<HealthSystem, ()>(&mut game.world, ());
 		game.systems.add_system::<AnimationSystem, ()>(&mut game.world, ());
+		game.systems.add_system::<InvulnerabilitySystem, ()>(&mut game.world, ());
+		game.systems.add_system::<HomingSystem, ()>(&mut game.world, ());
+		game.systems.add_system::<PowerUpSystem, ()>(&mut game.world, ());
+		game.systems.add_system::<BulletSystem, ()>(&mut game.world, ());
+		game.systems.add_system::<BossSystem, ()>(&mut game.world, ());
+		game.systems.add_system::<BombSystem, ()>(&mut game.world, ());
 		game
 	}
 
+	// Development shortcuts that must work whatever the current state is
+	fn handle_capture_event(event: &Event, renderer: &mut SdlRenderer, frame_capture_every_n_frames: u32) {
+		match event {
+			Event::KeyDown { keycode: Some(SCREENSHOT_KEY), repeat: false, .. } => {
+				renderer.request_screenshot(format!("screenshot-{}.png", common::current_time_ms()).as_str());
+			},
+			Event::KeyDown { keycode: Some(FRAME_CAPTURE_KEY), repeat: false, .. } => {
+				if renderer.is_capturing_frames() {
+					renderer.stop_frame_capture();
+				} else {
+					renderer.start_frame_capture(format!("capture-{}-", common::current_time_ms()).as_str(), frame_capture_every_n_frames);
+				}
+			},
+			_ => {}
+		}
+	}
+
 	fn update_global_runnables<'l>(global_runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all, 'l>) {
 		let mut i = 0;
 		while i < global_runnables.len() {
@@ -82,21 +138,58 @@ impl<'sdl_all, 'game> Game<'sdl_all, 'game> {
 		}
 	}
 
-	pub fn run(&'game mut self, canvas: Canvas<Window>, draw_context: &'sdl_all SdlDrawContext, texture_creator: &'sdl_all TextureCreator<WindowContext>) -> Result<(), String> {
+	pub fn run(&'game mut self, canvas: Canvas<Window>, draw_context: &'sdl_all SdlDrawContext, files: &'sdl_all AssetFileSystem, texture_loader: &'sdl_all TextureLoader<'sdl_all, WindowContext>, font_loader: &'sdl_all FontLoader<'sdl_all>, settings: GameSettings) -> Result<(), String> {
 		self.renderer = Some(SdlRenderer::new(canvas));
 		self.renderer.as_mut().unwrap().clear();
 		self.renderer.as_mut().unwrap().present();
-		self.resource_manager = Some(SdlResourceManager::new(draw_context, texture_creator));
+		self.resource_manager = Some(SdlResourceManager::new(files, texture_loader, font_loader, ASSET_MANIFEST_FILE)?);
+		let enemy_types = EnemyRegistry::load(files, ENEMY_TYPES_FILE)?;
+		enemy_types.validate(self.resource_manager.as_ref().unwrap().manifest()).map_err(|e| format!("{} : {}", ENEMY_TYPES_FILE, e))?;
+		let levels = LevelRegistry::load(files, LEVELS_FILE, &enemy_types, self.resource_manager.as_ref().unwrap().manifest())?;
+		if cfg!(feature = "dev-mode") {
+			self.resource_manager.as_mut().unwrap().enable_hot_reload();
+		}
+		self.audio_manager = match SdlAudioManager::new(draw_context, files, self.resource_manager.as_ref().unwrap().manifest()) {
+			Ok(audio_manager) => Some(Box::new(audio_manager)),
+			Err(err) => {
+				println!("NO AUDIO : {}", err);
+				Some(Box::new(NullAudioManager::new()))
+			}
+		};
+
+		self.controllers = match GameControllers::new(draw_context) {
+			Ok(controllers) => Some(controllers),
+			Err(err) => {
+				println!("NO GAME CONTROLLER SUPPORT : {}", err);
+				None
+			}
+		};
 
-		self.game_services = Some(GameServices::new(&mut self.world, self.resource_manager.as_mut().unwrap(), self.renderer.as_mut().unwrap(), draw_context));
+		self.game_services = Some(GameServices::new(&mut self.world, self.resource_manager.as_mut().unwrap(), self.audio_manager.as_mut().unwrap().as_mut(), self.renderer.as_mut().unwrap(), draw_context, settings, Arc::new(enemy_types), Arc::new(levels)));
+		let audio_system = Arc::new(RwLock::new(AudioSystem::new()));
+		let event_dispatcher = &mut self.game_services.as_mut().unwrap().event_dispatcher;
+		event_dispatcher.register::<DeathEvent>(audio_system.clone());
+		event_dispatcher.register::<ShotEvent>(audio_system.clone());
+		event_dispatcher.register::<DamageEvent>(audio_system.clone());
+		self.global_runnables.push(Arc::downgrade(&make_shared_runnable(audio_system.clone())));
+		self.audio_system = Some(audio_system);
 		self.state.update(&mut self.systems, &mut self.global_runnables, self.game_services.as_mut().unwrap());
 
 		let mut event_pump = draw_context.event_pump()?;
 		'running: loop {
 			// get the inputs here
 			for event in event_pump.poll_iter() {
-				if self.state.dispatch_event(&event) {
-					break 'running;
+				let game_services = self.game_services.as_mut().unwrap();
+				Self::handle_capture_event(&event, game_services.renderer, game_services.frame_capture_every_n_frames);
+				let mut controller_slot = None;
+				if let Some(controllers) = self.controllers.as_mut() {
+					controllers.handle_event(&event);
+					controller_slot = controllers.event_slot(&event);
+				}
+				for action in game_services.action_map.translate(&event, controller_slot) {
+					if self.state.dispatch_action(&action) {
+						break 'running;
+					}
 				}
 			}
 
@@ -108,6 +201,8 @@ impl<'sdl_all, 'game> Game<'sdl_all, 'game> {
 
 			self.last_ms = common::current_time_ms();
 			let game_services = self.game_services.as_mut().unwrap();
+			game_services.resource_manager.hot_reload();
+			game_services.audio_manager.update();
 			self.systems.update(game_services);
 			Self::update_global_runnables(&mut self.global_runnables, game_services);
 			if ! self.state.update(&mut self.systems, &mut self.global_runnables, game_services) {
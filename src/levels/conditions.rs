@@ -0,0 +1,145 @@
+use crate::{components::health::DeathEvent, core::events::EventObserver};
+
+// What ends a level phase
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum PhaseCondition {
+	// The phase ends by itself : every spawned enemy is gone, the boss is destroyed...
+	#[default]
+	Cleared,
+	ElapsedMs(u64),
+	// Enemies destroyed during the phase
+	Kills(u32),
+	// An enemy with this tag is destroyed during the phase
+	TagKilled(String),
+	// Points scored by the players during the phase
+	Score(u32),
+	Any(Vec<PhaseCondition>),
+	All(Vec<PhaseCondition>)
+}
+
+impl PhaseCondition {
+	/*
+		<condition>|<condition>... ends the phase on the first one met, <condition>+<condition>... once they are all met ('+' is applied first)
+		Conditions : cleared, time:<ms>, kills:<enemies>, tag:<enemy tag>, score:<points> (kills and score count from the start of the phase)
+	*/
+	pub fn parse(value: &str) -> Result<Self, String> {
+		let mut any = value.split('|').map(|group| {
+			let mut all = group.split('+').map(Self::parse_single).collect::<Result<Vec<_>, String>>()?;
+			Ok(if all.len() == 1 { all.remove(0) } else { PhaseCondition::All(all) })
+		}).collect::<Result<Vec<_>, String>>()?;
+		Ok(if any.len() == 1 { any.remove(0) } else { PhaseCondition::Any(any) })
+	}
+
+	fn parse_single(value: &str) -> Result<Self, String> {
+		let number = |number: &str| number.parse::<u32>().map_err(|_| format!("invalid number \"{}\" in condition \"{}\"", number, value));
+		match value.split_once(':') {
+			None if value == "cleared" => Ok(PhaseCondition::Cleared),
+			Some(("time", ms)) => Ok(PhaseCondition::ElapsedMs(number(ms)? as u64)),
+			Some(("kills", kills)) => Ok(PhaseCondition::Kills(number(kills)?)),
+			Some(("tag", tag)) if !tag.is_empty() => Ok(PhaseCondition::TagKilled(tag.to_string())),
+			Some(("score", score)) => Ok(PhaseCondition::Score(number(score)?)),
+			_ => Err(format!("unknown condition \"{}\", expected cleared, time:<ms>, kills:<enemies>, tag:<enemy tag> or score:<points>", value))
+		}
+	}
+
+	// False when the phase could go on forever, e.g. waiting for kills while every enemy is gone : one of "cleared" or "time" must be able to end it
+	pub fn always_ends(&self) -> bool {
+		match self {
+			PhaseCondition::Cleared | PhaseCondition::ElapsedMs(_) => true,
+			PhaseCondition::Kills(_) | PhaseCondition::TagKilled(_) | PhaseCondition::Score(_) => false,
+			PhaseCondition::Any(conditions) => conditions.iter().any(PhaseCondition::always_ends),
+			PhaseCondition::All(conditions) => conditions.iter().all(PhaseCondition::always_ends)
+		}
+	}
+
+	// "cleared" : the phase itself is over
+	pub fn is_met(&self, cleared: bool, elapsed_ms: u64, start: &PhaseStart, progress: &PhaseProgress) -> bool {
+		match self {
+			PhaseCondition::Cleared => cleared,
+			PhaseCondition::ElapsedMs(ms) => elapsed_ms >= *ms,
+			PhaseCondition::Kills(kills) => progress.kills - start.kills >= *kills,
+			PhaseCondition::TagKilled(tag) => progress.killed_tags[start.killed_tags..].contains(tag),
+			PhaseCondition::Score(score) => progress.score.saturating_sub(start.score) >= *score,
+			PhaseCondition::Any(conditions) => conditions.iter().any(|condition| condition.is_met(cleared, elapsed_ms, start, progress)),
+			PhaseCondition::All(conditions) => conditions.iter().all(|condition| condition.is_met(cleared, elapsed_ms, start, progress))
+		}
+	}
+}
+
+// Counters of the progress at the start of a phase
+#[derive(Clone, Copy, Default)]
+pub struct PhaseStart {
+	kills: u32,
+	killed_tags: usize,
+	score: u32
+}
+
+// What the players did since the start of the game
+#[derive(Default)]
+pub struct PhaseProgress {
+	kills: u32,
+	// Tags of the destroyed enemies, in order
+	killed_tags: Vec<String>,
+	pub score: u32
+}
+
+impl PhaseProgress {
+	pub fn new() -> Self {
+		PhaseProgress::default()
+	}
+
+	pub fn start(&self) -> PhaseStart {
+		PhaseStart { kills: self.kills, killed_tags: self.killed_tags.len(), score: self.score }
+	}
+}
+
+impl EventObserver<DeathEvent> for PhaseProgress {
+	fn on_event_mut(&mut self, data: &DeathEvent) {
+		if data.player || data.enemy_type.is_none() {
+			return;
+		}
+		self.kills += 1;
+		if let Some(tag) = &data.tag {
+			self.killed_tags.push(tag.clone());
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_any_of_all() {
+		assert_eq!(PhaseCondition::parse("time:1000+kills:5|cleared"), Ok(PhaseCondition::Any(vec![
+			PhaseCondition::All(vec![PhaseCondition::ElapsedMs(1000), PhaseCondition::Kills(5)]),
+			PhaseCondition::Cleared
+		])));
+		assert!(PhaseCondition::parse("kills:many").is_err());
+		assert!(PhaseCondition::parse("tag:").is_err());
+	}
+
+	#[test]
+	fn only_cleared_or_time_always_end() {
+		for condition in ["cleared", "time:1000", "kills:15|cleared", "tag:tank|time:5000", "kills:3|score:100|time:1000"] {
+			assert!(PhaseCondition::parse(condition).unwrap().always_ends(), "{}", condition);
+		}
+		for condition in ["kills:15", "tag:tank", "score:100", "kills:15|tag:tank", "time:1000+kills:5"] {
+			assert!(!PhaseCondition::parse(condition).unwrap().always_ends(), "{}", condition);
+		}
+	}
+
+	#[test]
+	fn counts_from_the_phase_start() {
+		let mut progress = PhaseProgress::new();
+		progress.kills = 10;
+		progress.score = 500;
+		let start = progress.start();
+		progress.kills = 14;
+		progress.score = 900;
+		assert!(!PhaseCondition::Kills(5).is_met(false, 0, &start, &progress));
+		assert!(PhaseCondition::Kills(4).is_met(false, 0, &start, &progress));
+		assert!(!PhaseCondition::Score(500).is_met(false, 0, &start, &progress));
+		assert!(PhaseCondition::Score(400).is_met(false, 0, &start, &progress));
+	}
+}
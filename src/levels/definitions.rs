@@ -0,0 +1,409 @@
+use std::{collections::HashMap, sync::Arc};
+
+use sdl2::pixels::Color;
+
+use crate::{components::{boss::BossPhase, bullet_pattern::{BulletEmitter, BulletPattern}, spawner::{DEFAULT_FORMATION_COLUMNS, DEFAULT_FORMATION_SPACING, DEFAULT_TRAIN_DELAY_MS, SpawnerType}}, core::{enemies::EnemyRegistry, events::Observer, filesystem::AssetFileSystem, manifest::{AssetManifest, SoundKind}}};
+
+use super::{conditions::PhaseCondition, level::{Level, LevelStep}, phase_basic_spawn::{LevelPhaseBasicSpawn, SpawnerPositionProperty}, phase_boss::{self, LevelPhaseBoss}, phase_interlude::LevelPhaseInterlude, phases::{LevelPhase, TrajectoryType}};
+
+pub const LEVELS_FILE: &str = "levels.def";
+
+const DEFAULT_SPAWN_HYPERSPACE_SPEED: f64 = 30.0;
+
+// Unused phase, copied each time its level starts
+#[derive(Clone)]
+pub enum PhaseDefinition {
+	Spawn(LevelPhaseBasicSpawn),
+	Boss(LevelPhaseBoss)
+}
+
+impl PhaseDefinition {
+	fn instantiate(&self) -> Box<dyn LevelPhase> {
+		match self {
+			PhaseDefinition::Spawn(phase) => Box::new(phase.clone()),
+			PhaseDefinition::Boss(phase) => Box::new(phase.clone())
+		}
+	}
+}
+
+// Phase with what ends it and what is played before it
+struct StepDefinition {
+	phase: PhaseDefinition,
+	end: PhaseCondition,
+	interlude: Option<LevelPhaseInterlude>,
+	// Line of the "phase" keyword, to report the errors found once the level is over
+	line: usize
+}
+
+impl StepDefinition {
+	fn instantiate(&self) -> LevelStep<dyn LevelPhase> {
+		let mut step = LevelStep::new(self.phase.instantiate()).end(self.end.clone());
+		if let Some(interlude) = &self.interlude {
+			step = step.interlude(Box::new(interlude.clone()));
+		}
+		step
+	}
+}
+
+pub struct LevelDefinition {
+	pub id: String,
+	// Displayed in the level select and stage clear screens
+	pub name: String,
+	pub music: Option<String>,
+	pub background: Option<(Color, Color)>,
+	phases: Vec<StepDefinition>,
+	// Line of the "level" keyword, to report the errors found once the level is over
+	line: usize
+}
+
+impl LevelDefinition {
+	pub fn build(&self, observer: Arc<Observer<dyn LevelPhase>>) -> Level<dyn LevelPhase> {
+		let steps = self.phases.iter().map(StepDefinition::instantiate).collect();
+		let mut level = Level::new(steps, observer);
+		if let Some(music) = &self.music {
+			level = level.music(music);
+		}
+		if let Some(background) = self.background {
+			level = level.background(background);
+		}
+		level
+	}
+}
+
+/*
+	One statement per line, each one adding to the last level or phase declared above it :
+		level <id> [name=<displayed name, '_' for spaces>] [music=<music id>] [background=<r>,<g>,<b>:<r>,<g>,<b>]
+		interlude duration=<ms> [banner=<displayed text, '_' for spaces>] [hyperspace=<speed>]
+		phase spawn [hyperspace=<speed>] [music=<music id>] [until=<condition>]
+			spawner <trajectory> density=<spawns> frequency=<ms> enemies=<per spawn> [shot_frequency=<factor>] [luck=<percents>]
+				[position=random|fixed|alternate|mirror] [enemy=<enemy type>]
+				[formation=point|circle|line|grid|vshape|snake] [spacing=<pixels>] [columns=<enemies per row>] [delay=<ms between snake enemies>] [tag=<enemy tag>]
+		phase boss <enemy type> name=<displayed name, '_' for spaces> [hyperspace=<speed>] [music=<music id>]
+			hitbox_part <x>,<y>,<width>,<height>
+			boss_phase threshold=<health ratio> waypoints=<x>:<y>,... speed=<speed> [shoot_delay=<ms>] [shoot_num=<shots>] [shoot_interval=<ms>]
+				emitter <shape> [speed=<speed>] [acceleration=<acceleration>] [angular_velocity=<degrees>] [offset=<degrees>]
+	Trajectories : basic_circle, basic_linear, basic_diagonal_left, basic_diagonal_right, reverse_diagonal_left, reverse_diagonal_right, centered_circle, swoop_left, swoop_right, snake
+	Emitter shapes are the enemy type patterns (see core/enemies.rs), boss waypoints are fractions of the screen size.
+	A boss phase needs the health_bar texture and the font_small font in the asset manifest.
+	position=alternate mirrors every other enemy of a spawn horizontally, position=mirror every other spawn, its whole formation included.
+	An interlude is played before the phase declared after it. A spawn phase ends once its enemies are gone, unless an "until" condition is given (see
+	levels/conditions.rs) : e.g. until=time:30000|kills:40 ends it after 30 seconds or 40 destroyed enemies. kills, tag and score conditions may never be met :
+	they must come with a cleared or time alternative, e.g. until=score:5000|cleared. Kills and score are counted from the start of the phase.
+	Levels are played in the file order. Indentation, empty lines and lines starting with '#' are ignored.
+*/
+pub struct LevelRegistry {
+	levels: Vec<LevelDefinition>,
+	// Declared interlude, waiting for its phase
+	interlude: Option<LevelPhaseInterlude>,
+}
+
+impl LevelRegistry {
+	pub fn new() -> Self {
+		LevelRegistry {
+			levels: Vec::new(),
+			interlude: None
+		}
+	}
+
+	// Enemy types and musics are checked while loading, so that a typo is reported at startup rather than in the middle of a game
+	pub fn load(files: &AssetFileSystem, filename: &str, enemy_types: &EnemyRegistry, manifest: &AssetManifest) -> Result<Self, String> {
+		let content = files.read_to_string(filename)?;
+		Self::parse(&content, enemy_types, manifest).map_err(|e| format!("{}:{}", filename, e))
+	}
+
+	pub fn parse(content: &str, enemy_types: &EnemyRegistry, manifest: &AssetManifest) -> Result<Self, String> {
+		let mut registry = LevelRegistry::new();
+		for (line_index, line) in content.lines().enumerate() {
+			let levels = registry.levels.len();
+			registry.parse_line(line, line_index + 1, enemy_types, manifest).map_err(|e| format!("{}: {}", line_index + 1, e))?;
+			// A new level completes the previous one
+			if levels > 0 && registry.levels.len() > levels {
+				Self::check_level(&registry.levels[levels - 1])?;
+			}
+		}
+		if let Some(level) = registry.levels.last() {
+			Self::check_level(level)?;
+		}
+		if registry.interlude.is_some() {
+			return Err(" interlude with no phase after it".to_string());
+		}
+		if registry.levels.is_empty() {
+			return Err(" no level defined".to_string());
+		}
+		Ok(registry)
+	}
+
+	fn parse_line(&mut self, line: &str, line_number: usize, enemy_types: &EnemyRegistry, manifest: &AssetManifest) -> Result<(), String> {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			return Ok(());
+		}
+
+		let mut tokens = line.split_whitespace();
+		let keyword = tokens.next().unwrap();
+		let mut arguments = Vec::new();
+		let mut properties = HashMap::new();
+		for token in tokens {
+			match token.split_once('=') {
+				Some((key, value)) if !key.is_empty() && !value.is_empty() => {
+					if properties.insert(key, value).is_some() {
+						return Err(format!("property \"{}\" is set twice", key));
+					}
+				},
+				Some(_) => return Err(format!("invalid property \"{}\", expected key=value", token)),
+				None if properties.is_empty() => arguments.push(token),
+				None => return Err(format!("unexpected \"{}\" after the properties", token))
+			}
+		}
+
+		match keyword {
+			"level" => {
+				if self.interlude.is_some() {
+					return Err("interlude with no phase after it".to_string());
+				}
+				let id = Self::argument(&arguments, 0, "level id")?;
+				if self.levels.iter().any(|level| level.id == id) {
+					return Err(format!("duplicated level \"{}\"", id));
+				}
+				let name = properties.remove("name").map_or_else(|| id.to_uppercase(), |name| name.replace('_', " "));
+				let music = Self::parse_music(properties.remove("music"), manifest)?;
+				let background = match properties.remove("background") {
+					Some(background) => Some(Self::parse_background(background)?),
+					None => None
+				};
+				self.levels.push(LevelDefinition { id: id.to_string(), name, music, background, phases: Vec::new(), line: line_number });
+			},
+			"phase" => {
+				let music = Self::parse_music(properties.remove("music"), manifest)?;
+				let hyperspace_speed = Self::parse_number::<f64>(properties.remove("hyperspace"), None)?;
+				let until = properties.remove("until");
+				let end = until.map(PhaseCondition::parse).transpose()?;
+				if end.as_ref().is_some_and(|end| !end.always_ends()) {
+					return Err(format!("condition \"{}\" may never be met, add |cleared or |time:<ms> to it", until.unwrap()));
+				}
+				let phase = match Self::argument(&arguments, 0, "phase kind")? {
+					"spawn" => {
+						let mut phase = LevelPhaseBasicSpawn::new(hyperspace_speed.unwrap_or(DEFAULT_SPAWN_HYPERSPACE_SPEED));
+						phase.music = music;
+						PhaseDefinition::Spawn(phase)
+					},
+					"boss" => {
+						let enemy_type = Self::argument(&arguments, 1, "boss enemy type")?;
+						enemy_types.get(enemy_type)?;
+						manifest.texture(phase_boss::HEALTH_BAR_TEXTURE)?;
+						manifest.font(phase_boss::NAME_FONT)?;
+						let name = properties.remove("name").ok_or_else(|| "boss phase has no name".to_string())?.replace('_', " ");
+						let mut phase = LevelPhaseBoss::new(&name, enemy_type);
+						phase.music = music;
+						if let Some(hyperspace_speed) = hyperspace_speed {
+							phase.hyperspace_speed = hyperspace_speed;
+						}
+						PhaseDefinition::Boss(phase)
+					},
+					other => return Err(format!("unknown phase kind \"{}\", expected spawn or boss", other))
+				};
+				if end.is_some() && !matches!(phase, PhaseDefinition::Spawn(_)) {
+					return Err("\"until\" is only supported by spawn phases".to_string());
+				}
+				let interlude = self.interlude.take();
+				self.current_level()?.phases.push(StepDefinition { phase, end: end.unwrap_or_default(), interlude, line: line_number });
+			},
+			"interlude" => {
+				self.current_level()?;
+				let duration_ms = Self::required_number::<u64>(&mut properties, "duration")?;
+				let hyperspace_speed = Self::parse_number::<f64>(properties.remove("hyperspace"), Some(DEFAULT_SPAWN_HYPERSPACE_SPEED))?.unwrap();
+				let mut interlude = LevelPhaseInterlude::new(duration_ms, hyperspace_speed);
+				interlude.banner = properties.remove("banner").map(|banner| banner.replace('_', " "));
+				if self.interlude.replace(interlude).is_some() {
+					return Err("two interludes in a row".to_string());
+				}
+			},
+			"spawner" => {
+				let trajectory = TrajectoryType::from_name(Self::argument(&arguments, 0, "spawner trajectory")?)?;
+				let density = Self::required_number::<usize>(&mut properties, "density")?;
+				let frequency_ms = Self::required_number::<u32>(&mut properties, "frequency")?;
+				let enemy_num = Self::required_number::<u16>(&mut properties, "enemies")?;
+				let shot_frequency_factor = Self::parse_number::<f32>(properties.remove("shot_frequency"), Some(1.0))?.unwrap();
+				let luck = Self::parse_number::<f32>(properties.remove("luck"), Some(100.0))?.unwrap();
+				if !(0.0..=100.0).contains(&luck) {
+					return Err(format!("invalid luck {}, expected percents", luck));
+				}
+				let position_prop = match properties.remove("position") {
+					Some("random") | None => SpawnerPositionProperty::Random,
+					Some("fixed") => SpawnerPositionProperty::Fixed,
+					Some("alternate") => SpawnerPositionProperty::AlternateSymetric,
+					Some("mirror") => SpawnerPositionProperty::MirroredSpawns,
+					Some(other) => return Err(format!("unknown position \"{}\", expected random, fixed, alternate or mirror", other))
+				};
+				let formation = properties.remove("formation").map(SpawnerType::from_name).transpose()?;
+				let spacing = Self::parse_number::<f32>(properties.remove("spacing"), Some(DEFAULT_FORMATION_SPACING))?.unwrap();
+				let columns = Self::parse_number::<u16>(properties.remove("columns"), Some(DEFAULT_FORMATION_COLUMNS))?.unwrap();
+				if columns == 0 {
+					return Err("invalid columns 0, expected at least one enemy per row".to_string());
+				}
+				let train_delay_ms = Self::parse_number::<u64>(properties.remove("delay"), Some(DEFAULT_TRAIN_DELAY_MS))?.unwrap();
+				let tag = properties.remove("tag").map(str::to_string);
+				let enemy_type = properties.remove("enemy").map(|enemy_type| enemy_types.get(enemy_type).map(|enemy_type| enemy_type.id.clone())).transpose()?;
+				let phase = match self.current_phase()? {
+					PhaseDefinition::Spawn(phase) => phase,
+					_ => return Err("spawner outside of a spawn phase".to_string())
+				};
+				let spawner = phase.add(trajectory, density, frequency_ms, enemy_num, shot_frequency_factor);
+				spawner.luck = luck;
+				spawner.position_prop = position_prop;
+				spawner.formation = formation.unwrap_or(SpawnerType::POINT);
+				spawner.spacing = spacing;
+				spawner.columns = columns;
+				spawner.train_delay_ms = train_delay_ms;
+				spawner.tag = tag;
+				if let Some(enemy_type) = enemy_type {
+					spawner.enemy_type = enemy_type;
+				}
+			},
+			"hitbox_part" => {
+				let part = Self::parse_rect(Self::argument(&arguments, 0, "hitbox part")?)?;
+				match self.current_phase()? {
+					PhaseDefinition::Boss(phase) => phase.add_hitbox_part(part.0, part.1, part.2, part.3),
+					_ => return Err("hitbox part outside of a boss phase".to_string())
+				}
+			},
+			"boss_phase" => {
+				let health_threshold = Self::required_number::<f32>(&mut properties, "threshold")?;
+				if health_threshold <= 0.0 || health_threshold > 1.0 {
+					return Err(format!("invalid threshold {}, expected a health ratio above 0.0 and up to 1.0", health_threshold));
+				}
+				let waypoints = Self::parse_waypoints(properties.remove("waypoints").ok_or_else(|| "missing property \"waypoints\"".to_string())?)?;
+				let speed = Self::required_number::<f32>(&mut properties, "speed")?;
+				let shoot_delay_ms = Self::parse_number::<u64>(properties.remove("shoot_delay"), None)?;
+				let shoot_num = Self::parse_number::<usize>(properties.remove("shoot_num"), None)?;
+				let shoot_interval_ms = Self::parse_number::<u64>(properties.remove("shoot_interval"), None)?;
+				let phase = match self.current_phase()? {
+					PhaseDefinition::Boss(phase) => phase,
+					_ => return Err("boss phase outside of a boss level phase".to_string())
+				};
+				let boss_phase = phase.add_phase(BossPhase::new(health_threshold, waypoints, speed, BulletPattern::new()));
+				boss_phase.shoot_delay_ms = shoot_delay_ms.unwrap_or(boss_phase.shoot_delay_ms);
+				boss_phase.shoot_num = shoot_num.unwrap_or(boss_phase.shoot_num);
+				boss_phase.shoot_interval_ms = shoot_interval_ms.unwrap_or(boss_phase.shoot_interval_ms);
+			},
+			"emitter" => {
+				let mut emitter = BulletEmitter::parse(Self::argument(&arguments, 0, "emitter shape")?)?;
+				if let Some(speed) = Self::parse_number::<f32>(properties.remove("speed"), None)? {
+					emitter.speed(speed);
+				}
+				if let Some(acceleration) = Self::parse_number::<f32>(properties.remove("acceleration"), None)? {
+					emitter.acceleration(acceleration);
+				}
+				if let Some(angular_velocity) = Self::parse_number::<f32>(properties.remove("angular_velocity"), None)? {
+					emitter.angular_velocity(angular_velocity);
+				}
+				if let Some(offset) = Self::parse_number::<f32>(properties.remove("offset"), None)? {
+					emitter.offset(offset);
+				}
+				let boss_phase = match self.current_phase()? {
+					PhaseDefinition::Boss(phase) => phase.last_phase_mut(),
+					_ => None
+				};
+				let boss_phase = boss_phase.ok_or_else(|| "emitter outside of a boss phase".to_string())?;
+				boss_phase.pattern.emitter(emitter);
+			},
+			_ => return Err(format!("unknown statement \"{}\"", keyword))
+		}
+
+		if let Some(key) = properties.keys().next() {
+			return Err(format!("unknown property \"{}\" for {}", key, keyword));
+		}
+		Ok(())
+	}
+
+	// Checks what can only be checked once a level is complete, the errors are prefixed by the line of the faulty statement
+	fn check_level(level: &LevelDefinition) -> Result<(), String> {
+		if level.phases.is_empty() {
+			return Err(format!("{}: level \"{}\" has no phase", level.line, level.id));
+		}
+		for step in &level.phases {
+			match &step.phase {
+				PhaseDefinition::Spawn(phase) if phase.is_empty() => return Err(format!("{}: spawn phase has no spawner", step.line)),
+				PhaseDefinition::Boss(phase) if !phase.has_phases() => return Err(format!("{}: boss phase has no boss_phase", step.line)),
+				_ => {}
+			}
+		}
+		Ok(())
+	}
+
+	fn current_level(&mut self) -> Result<&mut LevelDefinition, String> {
+		self.levels.last_mut().ok_or_else(|| "no level declared above".to_string())
+	}
+
+	fn current_phase(&mut self) -> Result<&mut PhaseDefinition, String> {
+		self.current_level()?.phases.last_mut().map(|step| &mut step.phase).ok_or_else(|| "no phase declared above".to_string())
+	}
+
+	fn argument<'a>(arguments: &[&'a str], index: usize, what: &str) -> Result<&'a str, String> {
+		arguments.get(index).copied().ok_or_else(|| format!("missing {}", what))
+	}
+
+	fn parse_number<T: std::str::FromStr>(value: Option<&str>, default: Option<T>) -> Result<Option<T>, String> {
+		match value {
+			Some(value) => value.parse::<T>().map(Some).map_err(|_| format!("invalid number \"{}\"", value)),
+			None => Ok(default),
+		}
+	}
+
+	fn required_number<T: std::str::FromStr>(properties: &mut HashMap<&str, &str>, key: &str) -> Result<T, String> {
+		let value = properties.remove(key).ok_or_else(|| format!("missing property \"{}\"", key))?;
+		value.parse::<T>().map_err(|_| format!("invalid number \"{}\" for \"{}\"", value, key))
+	}
+
+	fn parse_music(value: Option<&str>, manifest: &AssetManifest) -> Result<Option<String>, String> {
+		match value {
+			Some(music) => match manifest.sound(music)?.kind {
+				SoundKind::Music => Ok(Some(music.to_string())),
+				SoundKind::Effect => Err(format!("\"{}\" is a sound, not a music", music))
+			},
+			None => Ok(None)
+		}
+	}
+
+	fn parse_background(value: &str) -> Result<(Color, Color), String> {
+		let error = || format!("invalid background \"{}\", expected <r>,<g>,<b>:<r>,<g>,<b>", value);
+		let colors: Vec<Color> = value.split(':').map(|color| {
+			let components: Vec<u8> = color.split(',').filter_map(|component| component.parse::<u8>().ok()).collect();
+			match components.as_slice() {
+				[r, g, b] => Some(Color::RGB(*r, *g, *b)),
+				_ => None
+			}
+		}).collect::<Option<Vec<Color>>>().ok_or_else(error)?;
+		match colors.as_slice() {
+			[start, end] => Ok((*start, *end)),
+			_ => Err(error())
+		}
+	}
+
+	fn parse_rect(value: &str) -> Result<(i32, i32, u32, u32), String> {
+		let numbers: Vec<i32> = value.split(',').filter_map(|number| number.parse::<i32>().ok()).collect();
+		match numbers.as_slice() {
+			[x, y, width, height] if *width > 0 && *height > 0 => Ok((*x, *y, *width as u32, *height as u32)),
+			_ => Err(format!("invalid rectangle \"{}\", expected <x>,<y>,<width>,<height>", value))
+		}
+	}
+
+	fn parse_waypoints(value: &str) -> Result<Vec<(f32, f32)>, String> {
+		let error = || format!("invalid waypoints \"{}\", expected <x>:<y>,...", value);
+		value.split(',').map(|waypoint| {
+			let (x, y) = waypoint.split_once(':').ok_or_else(error)?;
+			Ok((x.parse::<f32>().map_err(|_| error())?, y.parse::<f32>().map_err(|_| error())?))
+		}).collect()
+	}
+
+	// In playing order
+	pub fn levels(&self) -> &[LevelDefinition] {
+		&self.levels
+	}
+}
+
+impl Default for LevelRegistry {
+	fn default() -> Self {
+		LevelRegistry::new()
+	}
+}
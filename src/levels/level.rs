@@ -1,46 +1,155 @@
 use std::sync::{Arc};
 
-use crate::{core::{common::GameServices, events::{EventBus, EventBusBase, Observer}}};
+use sdl2::pixels::Color;
 
-use super::phases::LevelPhase;
+use crate::{core::{common::{self, GameServices}, events::{EventBus, EventBusBase, Observer}}};
 
-pub struct Level<L : LevelPhase + 'static> {
-	phases: Vec<Box<L>>,
+use super::{conditions::{PhaseCondition, PhaseProgress, PhaseStart}, phases::LevelPhase};
+
+// Phase of a level, with what ends it and what is played before it
+pub struct LevelStep<L : LevelPhase + ?Sized + 'static> {
+	phase: Box<L>,
+	end: PhaseCondition,
+	interlude: Option<Box<L>>
+}
+
+impl<L: LevelPhase + ?Sized> LevelStep<L> {
+	pub fn new(phase: Box<L>) -> Self {
+		LevelStep {
+			phase,
+			end: PhaseCondition::Cleared,
+			interlude: None
+		}
+	}
+
+	pub fn end(mut self, condition: PhaseCondition) -> Self {
+		self.end = condition;
+		self
+	}
+
+	pub fn interlude(mut self, interlude: Box<L>) -> Self {
+		self.interlude = Some(interlude);
+		self
+	}
+}
+
+pub struct Level<L : LevelPhase + ?Sized + 'static> {
+	steps: Vec<LevelStep<L>>,
 	current_phase_index: usize,
 	init: bool,
+	// The interlude of the current step is playing, its phase comes next
+	in_interlude: bool,
+	phase_start: PhaseStart,
+	// Time spent in the current phase, pauses excluded
+	elapsed_ms: u64,
+	last_update_ms: u64,
+	music: Option<String>,
+	background: Option<(Color, Color)>,
 	event_bus: EventBusBase<L>
 }
 
-impl<'playing_state, L: LevelPhase> Level<L> {
-	pub fn new(phases: Vec<Box<L>>, observer: Arc<Observer<L>>) -> Self {
+const MUSIC_CROSSFADE_MS: i32 = 1500;
+
+impl<'playing_state, L: LevelPhase + ?Sized> Level<L> {
+	pub fn new(steps: Vec<LevelStep<L>>, observer: Arc<Observer<L>>) -> Self {
 		let mut event_bus = EventBusBase::new();
 		event_bus.register(observer);
 		Level {
-			phases: phases,
+			steps,
 			current_phase_index: 0,
 			init: false,
+			in_interlude: false,
+			phase_start: PhaseStart::default(),
+			elapsed_ms: 0,
+			last_update_ms: 0,
+			music: None,
+			background: None,
 			event_bus: event_bus
 		}
 	}
 
-	fn notify_current_phase_change(&mut self) {
-		self.event_bus.notify(self.phases[self.current_phase_index].as_ref());
+	// Music asset played by the phases that don't declare their own
+	pub fn music(mut self, asset_id: &str) -> Self {
+		self.music = Some(asset_id.to_string());
+		self
+	}
+
+	// Colors the background fades between during this level
+	pub fn background(mut self, colors: (Color, Color)) -> Self {
+		self.background = Some(colors);
+		self
+	}
+
+	pub fn background_colors(&self) -> Option<(Color, Color)> {
+		self.background
+	}
+
+	fn notify_current_phase_change<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let step = &self.steps[self.current_phase_index];
+		let phase = if self.in_interlude { step.interlude.as_deref().unwrap() } else { step.phase.as_ref() };
+		if let Some(music) = phase.music().or(self.music.as_deref()) {
+			game_services.audio_manager.crossfade_music(music, -1, MUSIC_CROSSFADE_MS);
+		}
+		self.event_bus.notify(phase);
+	}
+
+	fn enter_phase<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>, progress: &PhaseProgress) {
+		self.steps[self.current_phase_index].phase.on_enter(game_services);
+		self.notify_current_phase_change(game_services);
+		self.phase_start = progress.start();
+		self.elapsed_ms = 0;
+		self.last_update_ms = common::current_time_ms();
 	}
 
-	pub fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) -> bool {
-		let not_finished = self.current_phase_index < self.phases.len();
+	// Starts the interlude of the current step if any, its phase otherwise
+	fn enter_step<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>, progress: &PhaseProgress) {
+		match self.steps[self.current_phase_index].interlude.as_mut() {
+			Some(interlude) => {
+				interlude.on_enter(game_services);
+				self.in_interlude = true;
+				self.notify_current_phase_change(game_services);
+			},
+			None => self.enter_phase(game_services, progress)
+		}
+	}
+
+	// The time spent in pause doesn't count in the phase duration
+	pub fn resume(&mut self) {
+		self.last_update_ms = common::current_time_ms();
+		if let Some(step) = self.steps.get_mut(self.current_phase_index) {
+			match step.interlude.as_mut() {
+				Some(interlude) if self.in_interlude => interlude.resume(),
+				_ => step.phase.resume()
+			}
+		}
+	}
+
+	pub fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>, progress: &PhaseProgress) -> bool {
+		let not_finished = self.current_phase_index < self.steps.len();
 		if not_finished {
 			if ! self.init {
-				self.phases[self.current_phase_index].on_enter(game_services);
-				self.notify_current_phase_change();
-
+				self.enter_step(game_services, progress);
 				self.init = true;
-			} else if ! self.phases[self.current_phase_index].update(game_services) {
-				self.current_phase_index += 1;
-				if self.current_phase_index < self.phases.len() {
-					//println!("NEXT PHASE {}", self.current_phase_index);
-					self.phases[self.current_phase_index].on_enter(game_services);
-					self.notify_current_phase_change();
+			} else if self.in_interlude {
+				let interlude = self.steps[self.current_phase_index].interlude.as_mut().unwrap();
+				if ! interlude.update(game_services) {
+					interlude.on_leave(game_services);
+					self.in_interlude = false;
+					self.enter_phase(game_services, progress);
+				}
+			} else {
+				let now = common::current_time_ms();
+				self.elapsed_ms += now.saturating_sub(self.last_update_ms);
+				self.last_update_ms = now;
+				let step = &mut self.steps[self.current_phase_index];
+				let cleared = ! step.phase.update(game_services);
+				if step.end.is_met(cleared, self.elapsed_ms, &self.phase_start, progress) {
+					step.phase.on_leave(game_services);
+					self.current_phase_index += 1;
+					if self.current_phase_index < self.steps.len() {
+						//println!("NEXT PHASE {}", self.current_phase_index);
+						self.enter_step(game_services, progress);
+					}
 				}
 			}
 		}
@@ -1,4 +1,8 @@
 pub mod phases;
 pub mod level;
-pub mod level1;
 pub mod phase_basic_spawn;
+
+pub mod phase_boss;
+pub mod definitions;
+pub mod conditions;
+pub mod phase_interlude;
\ No newline at end of file
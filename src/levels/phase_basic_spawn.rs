@@ -1,16 +1,18 @@
 use std::sync::{RwLock, Weak};
 
-use crate::{components::spawner::{SpawnerComponent, SpawnerType}, core::{common::GameServices, ecs}, factory, systems::{ai::AISystem, spawner::SpawnMobSystem}};
+use crate::{components::spawner::{DEFAULT_ENEMY_TYPE, DEFAULT_FORMATION_COLUMNS, DEFAULT_FORMATION_SPACING, DEFAULT_TRAIN_DELAY_MS, SpawnerComponent, SpawnerType}, core::{common::GameServices, ecs::{self, EntityId}}, factory, systems::{ai::AISystem, spawner::SpawnMobSystem}};
 
 use super::{phases::{LevelPhase, TrajectoryType}};
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum SpawnerPositionProperty {
 	AlternateSymetric,
+	MirroredSpawns,
 	Fixed,
 	Random
 }
 
+#[derive(Clone)]
 pub struct SpawnerProperties {
 	pattern: TrajectoryType,
 	density: usize,
@@ -19,6 +21,13 @@ pub struct SpawnerProperties {
 	shot_frequency_factor: f32,
 	pub luck: f32,
 	pub position_prop: SpawnerPositionProperty,
+	pub enemy_type: String,
+	pub formation: SpawnerType,
+	// Formation layout, see SpawnerComponent
+	pub spacing: f32,
+	pub columns: u16,
+	pub train_delay_ms: u64,
+	pub tag: Option<String>,
 }
 
 impl SpawnerProperties {
@@ -30,14 +39,22 @@ impl SpawnerProperties {
 			frequency_ms: frequency_ms,
 			enemy_num: enemy_num,
 			shot_frequency_factor: shot_frequency_factor,
-			position_prop: SpawnerPositionProperty::Random
+			position_prop: SpawnerPositionProperty::Random,
+			enemy_type: DEFAULT_ENEMY_TYPE.to_string(),
+			formation: SpawnerType::POINT,
+			spacing: DEFAULT_FORMATION_SPACING,
+			columns: DEFAULT_FORMATION_COLUMNS,
+			train_delay_ms: DEFAULT_TRAIN_DELAY_MS,
+			tag: None
 		}
 	}
 }
 
+#[derive(Clone)]
 pub struct LevelPhaseBasicSpawn {
 	properties: Vec<SpawnerProperties>,
 	pub hyperspace_speed: f64,
+	pub music: Option<String>,
 	ai_system: Option<Weak<RwLock<ecs::System>>>,
 	spawner_system: Option<Weak<RwLock<ecs::System>>>,
 }
@@ -47,6 +64,7 @@ impl LevelPhaseBasicSpawn {
 		LevelPhaseBasicSpawn {
 			properties: Vec::new(),
 			hyperspace_speed: hyperspace_speed,
+			music: None,
 			ai_system: None,
 			spawner_system: None
 		}
@@ -56,6 +74,10 @@ impl LevelPhaseBasicSpawn {
 		self.properties.push(SpawnerProperties::new(pattern, density, frequency_ms, enemy_num, shot_frequency_factor));
 		self.properties.last_mut().unwrap()
 	}
+
+	pub fn is_empty(&self) -> bool {
+		self.properties.is_empty()
+	}
 }
 
 impl LevelPhase for LevelPhaseBasicSpawn {
@@ -69,12 +91,19 @@ impl LevelPhase for LevelPhaseBasicSpawn {
 				((game_services.draw_context.screen_width() / 2) as f32, 180 as f32)
 			};
 			let spawner = factory::create_entity("",  spawn_pos.0 as i32, spawn_pos.1 as i32, 0, area_width, area_height, game_services);
-			let mut spawner_component = SpawnerComponent::new(SpawnerType::POINT, properties.frequency_ms, properties.luck, properties.enemy_num,
+			let difficulty = game_services.difficulty;
+			let mut spawner_component = SpawnerComponent::new(properties.formation, difficulty.spawn_frequency_ms(properties.frequency_ms), difficulty.spawn_luck(properties.luck), properties.enemy_num,
 				1.0, std::f32::consts::PI * 2.0, properties.pattern);
 			spawner_component.countdown = properties.density;
 			spawner_component.shot_frequency_factor = properties.shot_frequency_factor;
 			spawner_component.randomize_pos = properties.position_prop == SpawnerPositionProperty::Random;
 			spawner_component.symetric_alternate_pos = properties.position_prop == SpawnerPositionProperty::AlternateSymetric;
+			spawner_component.mirror_alternate_spawns = properties.position_prop == SpawnerPositionProperty::MirroredSpawns;
+			spawner_component.enemy_type = properties.enemy_type.clone();
+			spawner_component.spacing = properties.spacing;
+			spawner_component.columns = properties.columns;
+			spawner_component.train_delay_ms = properties.train_delay_ms;
+			spawner_component.tag = properties.tag.clone();
 			game_services.get_world_mut().add_component::<SpawnerComponent>(&spawner, spawner_component);
 		}
 		self.ai_system = game_services.get_world().get_system_base::<AISystem>();
@@ -85,6 +114,22 @@ impl LevelPhase for LevelPhaseBasicSpawn {
 		self.ai_system.as_ref().unwrap().upgrade().unwrap().read().unwrap().len_entities() > 0 ||
 		self.spawner_system.as_ref().unwrap().upgrade().unwrap().read().unwrap().len_entities() > 0
 	}
+
+	// Spawners still running are stopped, the enemies already spawned stay
+	fn on_leave<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let spawners: Vec<EntityId> = self.spawner_system.as_ref().unwrap().upgrade().unwrap().read().unwrap().iter_entities().copied().collect();
+		for spawner in spawners {
+			game_services.get_world_mut().remove_entity(&spawner);
+		}
+	}
+
+	fn music(&self) -> Option<&str> {
+		self.music.as_deref()
+	}
+
+	fn hyperspace_speed(&self) -> f64 {
+		self.hyperspace_speed
+	}
 }
 
 pub trait LevelPhaseBasic {
@@ -99,4 +144,8 @@ impl<T: LevelPhaseBasic> LevelPhase for T {
 	fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
 		self.base().update(game_services)
 	}
+
+	fn on_leave<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>) {
+		self.base().on_leave(game_services)
+	}
 }
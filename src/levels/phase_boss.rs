@@ -0,0 +1,146 @@
+use sdl2::rect::Rect;
+
+use crate::{components::{boss::{BossComponent, BossPhase}, health::HealthComponent, hitbox::HitboxComponent}, core::{common::GameServices, ecs::EntityId, renderers::Renderable}, factory};
+
+use super::phases::LevelPhase;
+
+// Assets every boss phase needs, checked when the levels are loaded
+pub const HEALTH_BAR_TEXTURE: &str = "health_bar";
+pub const NAME_FONT: &str = "font_small";
+const HEALTH_BAR_HEIGHT: u32 = 12;
+const HEALTH_BAR_TOP: i32 = 12;
+// Health bar width, as a fraction of the screen width
+const HEALTH_BAR_WIDTH_RATIO: f32 = 0.4;
+
+// Phase ending when its boss is destroyed
+#[derive(Clone)]
+pub struct LevelPhaseBoss {
+	name: String,
+	enemy_type: String,
+	phases: Vec<BossPhase>,
+	// Hitboxes added to the enemy type one, relative to the boss position
+	hitbox_parts: Vec<Rect>,
+	pub hyperspace_speed: f64,
+	pub music: Option<String>,
+	boss: Option<EntityId>,
+	health_bar_texture_index: Option<i64>,
+	name_texture: Option<(i64, Rect)>,
+}
+
+impl LevelPhaseBoss {
+	pub fn new(name: &str, enemy_type: &str) -> Self {
+		LevelPhaseBoss {
+			name: name.to_string(),
+			enemy_type: enemy_type.to_string(),
+			phases: Vec::new(),
+			hitbox_parts: Vec::new(),
+			hyperspace_speed: 20.0,
+			music: None,
+			boss: None,
+			health_bar_texture_index: None,
+			name_texture: None
+		}
+	}
+
+	pub fn add_phase(&mut self, phase: BossPhase) -> &mut BossPhase {
+		self.phases.push(phase);
+		self.phases.last_mut().unwrap()
+	}
+
+	pub fn has_phases(&self) -> bool {
+		!self.phases.is_empty()
+	}
+
+	pub fn last_phase_mut(&mut self) -> Option<&mut BossPhase> {
+		self.phases.last_mut()
+	}
+
+	pub fn add_hitbox_part(&mut self, x: i32, y: i32, width: u32, height: u32) {
+		self.hitbox_parts.push(Rect::new(x, y, width, height));
+	}
+
+	fn is_boss_alive<'sdl_all, 'l>(&self, game_services: &GameServices<'sdl_all, 'l>) -> bool {
+		// Entity ids are recycled, the BossComponent tells if it is still our boss
+		match self.boss {
+			Some(boss) => game_services.get_world().is_alive(&boss) && game_services.get_world().has_component::<BossComponent>(&boss),
+			None => false
+		}
+	}
+
+	fn draw_health_bar<'sdl_all, 'l>(&self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let (boss, health_bar_texture_index) = match (self.boss, self.health_bar_texture_index) {
+			(Some(boss), Some(health_bar_texture_index)) => (boss, health_bar_texture_index),
+			_ => return
+		};
+		let health_points = game_services.get_world().get_component::<HealthComponent>(&boss).unwrap().health_points;
+		let ratio = game_services.get_world().get_component::<BossComponent>(&boss).unwrap().health_ratio(health_points);
+
+		let bar_width = (game_services.draw_context.screen_width() as f32 * HEALTH_BAR_WIDTH_RATIO) as u32;
+		let bar_x = (game_services.draw_context.screen_width() - bar_width) as i32 / 2;
+		let background = Rect::new(bar_x, HEALTH_BAR_TOP, bar_width, HEALTH_BAR_HEIGHT);
+		game_services.renderer.push_renderable(Renderable::new(health_bar_texture_index, Some(Rect::new(0, 0, 8, 8)), Some(background), i64::MAX - 1));
+		let fill_width = ((bar_width - 4) as f32 * ratio) as u32;
+		if fill_width > 0 {
+			let fill = Rect::new(bar_x + 2, HEALTH_BAR_TOP + 2, fill_width, HEALTH_BAR_HEIGHT - 4);
+			game_services.renderer.push_renderable(Renderable::new(health_bar_texture_index, Some(Rect::new(8, 0, 8, 8)), Some(fill), i64::MAX));
+		}
+		if let Some((name_texture_index, name_rect)) = self.name_texture {
+			let dst = Rect::new(bar_x, HEALTH_BAR_TOP + HEALTH_BAR_HEIGHT as i32 + 2, name_rect.width(), name_rect.height());
+			game_services.renderer.push_renderable(Renderable::new(name_texture_index, None, Some(dst), i64::MAX - 1));
+		}
+	}
+}
+
+impl LevelPhase for LevelPhaseBoss {
+	fn on_enter<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		if cfg!(feature = "dev-mode") {
+			println!("BOSS {} ENTERS", self.name);
+		}
+		// Without its health bar or its name, the boss is still fought
+		self.health_bar_texture_index = game_services.resource_manager.load_shared_texture(HEALTH_BAR_TEXTURE)
+			.map_err(|err| println!("Unable to load the boss health bar : {}", err)).ok();
+		let resource_manager = &mut game_services.resource_manager;
+		self.name_texture = resource_manager.load_font(NAME_FONT).and_then(|font| resource_manager.text_to_texture(font, self.name.as_str(), None))
+			.map_err(|err| println!("Unable to render the boss name : {}", err)).ok();
+
+		// Enters from the top of the screen, towards the first waypoint of its first phase
+		let enemy_types = game_services.enemy_types.clone();
+		let enemy_type = match enemy_types.get(&self.enemy_type) {
+			Ok(enemy_type) => enemy_type,
+			Err(err) => {
+				// Checked when the levels are loaded, the phase ends right away
+				println!("Unable to spawn the boss {} : {}", self.name, err);
+				return;
+			}
+		};
+		let x = (game_services.draw_context.screen_width() / 2) as i32 - enemy_type.width as i32 / 2;
+		let y = - (enemy_type.height as i32);
+		let boss_component = BossComponent::new(&self.name, self.phases.clone(), enemy_type.health);
+		let boss = factory::create_boss(enemy_type, x, y, boss_component, game_services);
+		game_services.get_world_mut().get_component_mut::<HitboxComponent>(&boss).unwrap().parts = self.hitbox_parts.clone();
+		self.boss = Some(boss);
+	}
+
+	fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		if self.is_boss_alive(game_services) {
+			self.draw_health_bar(game_services);
+			true
+		} else {
+			if cfg!(feature = "dev-mode") {
+				println!("BOSS {} DEFEATED", self.name);
+			}
+			if let Some((name_texture_index, _)) = self.name_texture.take() {
+				game_services.resource_manager.release_texture(name_texture_index);
+			}
+			false
+		}
+	}
+
+	fn music(&self) -> Option<&str> {
+		self.music.as_deref()
+	}
+
+	fn hyperspace_speed(&self) -> f64 {
+		self.hyperspace_speed
+	}
+}
@@ -0,0 +1,64 @@
+use sdl2::rect::Rect;
+
+use crate::core::{common::{self, GameServices}, renderers::Renderable};
+
+use super::phases::LevelPhase;
+
+// Pause between two phases, displaying a banner (e.g. "WAVE 2") and/or speeding the stars up
+#[derive(Clone)]
+pub struct LevelPhaseInterlude {
+	pub banner: Option<String>,
+	pub hyperspace_speed: f64,
+	pub duration_ms: u64,
+	// Time spent in the interlude, pauses excluded
+	elapsed_ms: u64,
+	last_update_ms: u64,
+	banner_texture: Option<(i64, Rect)>
+}
+
+impl LevelPhaseInterlude {
+	pub fn new(duration_ms: u64, hyperspace_speed: f64) -> Self {
+		LevelPhaseInterlude {
+			banner: None,
+			hyperspace_speed,
+			duration_ms,
+			elapsed_ms: 0,
+			last_update_ms: 0,
+			banner_texture: None
+		}
+	}
+}
+
+impl LevelPhase for LevelPhaseInterlude {
+	fn on_enter<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		self.elapsed_ms = 0;
+		self.last_update_ms = common::current_time_ms();
+		if let Some(banner) = &self.banner {
+			println!("INTERLUDE {}", banner);
+			let font = game_services.resource_manager.load_font("font_big").unwrap();
+			// The texture is reused when the interlude is played again
+			let existing = self.banner_texture.map(|(texture_index, _)| texture_index);
+			self.banner_texture = game_services.resource_manager.text_to_texture(font, banner.as_str(), existing).ok();
+		}
+	}
+
+	fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		if let Some((texture_index, rect)) = self.banner_texture {
+			let x = (game_services.draw_context.screen_width() as i32 - rect.width() as i32) / 2;
+			let y = (game_services.draw_context.screen_height() as i32 - rect.height() as i32) / 3;
+			game_services.renderer.push_renderable(Renderable::new(texture_index, None, Some(Rect::new(x, y, rect.width(), rect.height())), i64::MAX - 1));
+		}
+		let now = common::current_time_ms();
+		self.elapsed_ms += now.saturating_sub(self.last_update_ms);
+		self.last_update_ms = now;
+		self.elapsed_ms < self.duration_ms
+	}
+
+	fn resume(&mut self) {
+		self.last_update_ms = common::current_time_ms();
+	}
+
+	fn hyperspace_speed(&self) -> f64 {
+		self.hyperspace_speed
+	}
+}
@@ -1,4 +1,4 @@
-use crate::{components::ai::{DestinationPoint, TrajectorySequence}, core::common::GameServices};
+use crate::{components::{ai::{DestinationPoint, TrajectorySequence}, bullet_pattern::{BulletEmitter, BulletPattern}, path::{Easing, Path}}, core::common::GameServices};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum TrajectoryType {
@@ -8,12 +8,50 @@ pub enum TrajectoryType {
 	BasicDiagonalRight,
 	ReverseDiagonalLeft,
 	ReverseDiagonalRight,
-	CenteredCircle
+	CenteredCircle,
+	// Bézier dive from the left side, back up on the right one
+	SwoopLeft,
+	// Bézier dive from the right side, back up on the left one
+	SwoopRight,
+	// Catmull-Rom zigzag down the screen
+	Snake
+}
+
+impl TrajectoryType {
+	pub fn from_name(name: &str) -> Result<Self, String> {
+		match name {
+			"basic_circle" => Ok(TrajectoryType::BasicCircle),
+			"basic_linear" => Ok(TrajectoryType::BasicLinear),
+			"basic_diagonal_left" => Ok(TrajectoryType::BasicDiagonalLeft),
+			"basic_diagonal_right" => Ok(TrajectoryType::BasicDiagonalRight),
+			"reverse_diagonal_left" => Ok(TrajectoryType::ReverseDiagonalLeft),
+			"reverse_diagonal_right" => Ok(TrajectoryType::ReverseDiagonalRight),
+			"centered_circle" => Ok(TrajectoryType::CenteredCircle),
+			"swoop_left" => Ok(TrajectoryType::SwoopLeft),
+			"swoop_right" => Ok(TrajectoryType::SwoopRight),
+			"snake" => Ok(TrajectoryType::Snake),
+			_ => Err(format!("unknown trajectory \"{}\"", name))
+		}
+	}
 }
 
 pub trait LevelPhase {
 	fn on_enter<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>);
 	fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>) -> bool;
+	// Called when the phase ends, including when a level condition ends it early
+	fn on_leave<'sdl_all, 'l>(&mut self, _game_services: &mut GameServices<'sdl_all,'l>) {
+	}
+	// Called when the game goes on after a pause
+	fn resume(&mut self) {
+	}
+	// Music asset played during this phase, the level one when None
+	fn music(&self) -> Option<&str> {
+		None
+	}
+	// Speed of the background stars during this phase
+	fn hyperspace_speed(&self) -> f64 {
+		30.0
+	}
 }
 
 pub struct TrajectoryGenerator {
@@ -43,6 +81,47 @@ impl TrajectoryGenerator {
 		sequence
 	}
 
+	// Goes to the start of the path, then follows it
+	// An invalid path is reported and skipped, the enemy going on with the next trajectory
+	fn enqueue_path(sequence: &mut Vec<TrajectorySequence>, path: Result<Path, String>, shoot_delay_ms: u64, shoot_num: usize) {
+		let path = match path {
+			Ok(path) => path,
+			Err(err) => {
+				println!("Invalid path : {}", err);
+				return;
+			}
+		};
+		let mut approach = TrajectorySequence::new();
+		approach.push(path.start());
+		sequence.push(approach);
+
+		let mut follow = TrajectorySequence::path(path);
+		follow.set_shoot_delay(shoot_delay_ms);
+		follow.shoot_num = shoot_num;
+		sequence.push(follow);
+	}
+
+	fn enqueue_pattern_swoop(sequence: &mut Vec<TrajectorySequence>, from_left: bool, screen_width: u32, screen_height: u32) {
+		let (side_start, side_end) = if from_left { (90.0, screen_width as f32 - 90.0) } else { (screen_width as f32 - 90.0, 90.0) };
+		let bottom = screen_height as f32 - 100.0;
+		let swoop = Path::bezier(&[(side_start, 90.0), (side_start, bottom), (side_end, bottom), (side_end, 90.0)])
+			.map(|path| path.easing(Easing::EaseInOut).oriented());
+		Self::enqueue_path(sequence, swoop, 2000, 2);
+
+		let mut ffinal = TrajectorySequence::new();
+		ffinal.push((if from_left { screen_width as f32 + 90.0 } else { - 90.0 }, - 90.0));
+		sequence.push(ffinal);
+	}
+
+	fn enqueue_pattern_snake(sequence: &mut Vec<TrajectorySequence>, screen_width: u32, screen_height: u32) {
+		let (left, right) = (screen_width as f32 / 4.0, screen_width as f32 * 3.0 / 4.0);
+		let step = (screen_height as f32 - 300.0) / 4.0;
+		let mut points: Vec<DestinationPoint> = (0..5).map(|i| (if i % 2 == 0 { left } else { right }, 90.0 + i as f32 * step)).collect();
+		// Leaves through the bottom of the screen
+		points.push((screen_width as f32 / 2.0, screen_height as f32 + 90.0));
+		Self::enqueue_path(sequence, Path::catmull_rom(&points).map(Path::oriented), 1500, 1);
+	}
+
 	fn enqueue_pattern_basic_circle(sequence: &mut Vec<TrajectorySequence>, start_pos: DestinationPoint) {
 		let mut line_start = TrajectorySequence::new();
 		line_start.push((start_pos.0, start_pos.1 + 100.0));
@@ -53,6 +132,7 @@ impl TrajectoryGenerator {
 		let mut circle = Self::generate_circle_pattern(&start_pos, 100, -90, 90, 10);
 		circle.set_shoot_delay(1000);
 		circle.shoot_num = 3;
+		circle.set_pattern(BulletPattern::new().emitter(BulletEmitter::aimed_fan(3, 30.0)).clone());
 		let last_circle_point = circle.last().unwrap().clone();
 		sequence.push(circle);
 		let final_pos = (start_pos.0, start_pos.1 * 2.5);
@@ -66,8 +146,14 @@ impl TrajectoryGenerator {
 	fn enqueue_pattern_centered_circle(sequence: &mut Vec<TrajectorySequence>, start_pos: DestinationPoint) {
 		let mut circle = Self::generate_circle_pattern(&start_pos, 180, 90, 432, 20);
 		circle.set_shoot_delay(2000);
-		circle.shoot_num = 1;
+		circle.shoot_num = 6;
+		circle.shoot_interval_ms = 120;
 		circle.loop_count = u16::MAX;
+		// Two spirals turning in opposite directions
+		circle.set_pattern(BulletPattern::new()
+			.emitter(*BulletEmitter::spiral(4, 15.0).speed(4.0).angular_velocity(0.3))
+			.emitter(*BulletEmitter::spiral(4, -15.0).speed(4.0).angular_velocity(-0.3).offset(45.0))
+			.clone());
 		sequence.push(circle);
 	}
 
@@ -96,6 +182,8 @@ impl TrajectoryGenerator {
 		let mut line_end = Self::generate_line_pattern(&pos_left, &final_pos);
 		line_end.set_shoot_delay(10000);
 		line_end.shoot_num = 2;
+		// Ring burst slowing down
+		line_end.set_pattern(BulletPattern::new().emitter(*BulletEmitter::ring(12).speed(6.0).acceleration(-0.05)).clone());
 		sequence.push(line_end);
 	}
 
@@ -168,6 +256,9 @@ impl TrajectoryGenerator {
 			TrajectoryType::CenteredCircle => Self::enqueue_pattern_centered_circle(&mut sequence, start_pos),
 			TrajectoryType::ReverseDiagonalLeft => Self::enqueue_pattern_reverse_diagonal_left(&mut sequence, screen_width, screen_height),
 			TrajectoryType::ReverseDiagonalRight => Self::enqueue_pattern_reverse_diagonal_right(&mut sequence, screen_width, screen_height),
+			TrajectoryType::SwoopLeft => Self::enqueue_pattern_swoop(&mut sequence, true, screen_width, screen_height),
+			TrajectoryType::SwoopRight => Self::enqueue_pattern_swoop(&mut sequence, false, screen_width, screen_height),
+			TrajectoryType::Snake => Self::enqueue_pattern_snake(&mut sequence, screen_width, screen_height),
 		}
 		sequence
 	}
@@ -1,3 +1,8 @@
+use std::{env, path::{Path, PathBuf}};
+
+use crate::levels::definitions::LEVELS_FILE;
+use crate::core::{actions::{ACTION_MAP_FILE, ActionMap}, common::GameSettings, difficulty::Difficulty, rank::Rank, enemies::ENEMY_TYPES_FILE, filesystem::{AssetFileSystem, DEFAULT_ARCHIVE_FILE, PakArchive}, manifest::{ASSET_MANIFEST_FILE, AssetManifest}, renderers::DEFAULT_FRAME_CAPTURE_EVERY_N_FRAMES, resources::{FontLoader, TextureLoader}};
+
 pub mod states;
 pub mod core;
 pub mod systems;
@@ -8,7 +13,76 @@ pub mod levels;
 pub extern crate sdl2;
 mod game;
 
+// Packs the manifest, the enemy types, the levels and every asset the manifest references in a single archive file
+fn pack_assets(files: &AssetFileSystem, archive_filename: &str) -> Result<(), String> {
+	let manifest = AssetManifest::load(files, ASSET_MANIFEST_FILE)?;
+	let mut packed_files = vec![(ASSET_MANIFEST_FILE.to_string(), files.find_file(ASSET_MANIFEST_FILE).unwrap())];
+	let enemy_types_file = files.find_file(ENEMY_TYPES_FILE).ok_or_else(|| format!("{} is not a loose file", ENEMY_TYPES_FILE))?;
+	packed_files.push((ENEMY_TYPES_FILE.to_string(), enemy_types_file));
+	let levels_file = files.find_file(LEVELS_FILE).ok_or_else(|| format!("{} is not a loose file", LEVELS_FILE))?;
+	packed_files.push((LEVELS_FILE.to_string(), levels_file));
+	for (id, path) in manifest.files() {
+		let file = files.find_file(path).ok_or_else(|| format!("Asset \"{}\" is not a loose file ({})", id, path))?;
+		if packed_files.iter().all(|(packed, _)| packed != path) {
+			packed_files.push((path.clone(), file));
+		}
+	}
+	PakArchive::write(&PathBuf::from(archive_filename), &packed_files)?;
+	println!("{} files packed in {}", packed_files.len(), archive_filename);
+	Ok(())
+}
+
 fn main() {
+	let files = match AssetFileSystem::from_env() {
+		Ok(files) => files,
+		Err(e) => panic!("Assets cannot be found : {}", e)
+	};
+
+	// "--pack [archive]" builds the assets archive to ship next to the binary instead of running the game
+	let args: Vec<String> = env::args().collect();
+	if args.get(1).map(String::as_str) == Some("--pack") {
+		let archive_filename = args.get(2).map(String::as_str).unwrap_or(DEFAULT_ARCHIVE_FILE);
+		if let Err(e) = pack_assets(&files, archive_filename) {
+			panic!("Assets cannot be packed : {}", e);
+		}
+		return;
+	}
+
+	// "--players <count>" for local co-op
+	let players = match args.iter().position(|arg| arg == "--players") {
+		Some(index) => match args.get(index + 1).and_then(|count| count.parse::<usize>().ok()).filter(|count| *count > 0) {
+			Some(count) => count,
+			None => panic!("--players expects a number of players")
+		},
+		None => 1
+	};
+	// "--difficulty <easy|normal|hard|insane>"
+	let difficulty = match args.iter().position(|arg| arg == "--difficulty") {
+		Some(index) => match args.get(index + 1).map(|name| Difficulty::from_name(name)) {
+			Some(Ok(difficulty)) => difficulty,
+			Some(Err(e)) => panic!("{}", e),
+			None => panic!("--difficulty expects a difficulty")
+		},
+		None => Difficulty::default()
+	};
+	// "--rank" adapts the difficulty to how well the players are doing
+	let rank = if args.iter().any(|arg| arg == "--rank") { Some(Rank::new()) } else { None };
+	// "--capture-every <frames>" for the frame capture started with F11
+	let frame_capture_every_n_frames = match args.iter().position(|arg| arg == "--capture-every") {
+		Some(index) => match args.get(index + 1).and_then(|count| count.parse::<u32>().ok()).filter(|count| *count > 0) {
+			Some(count) => count,
+			None => panic!("--capture-every expects a number of frames")
+		},
+		None => DEFAULT_FRAME_CAPTURE_EVERY_N_FRAMES
+	};
+	let action_map = match ActionMap::load(Path::new(ACTION_MAP_FILE), players) {
+		Ok(action_map) => action_map,
+		Err(e) => {
+			println!("INVALID INPUT CONFIGURATION, USING DEFAULT KEYS : {}", e);
+			ActionMap::default_bindings(players)
+		}
+	};
+
 	let draw_context = core::renderers::SdlDrawContext::new();
 	//let ttt = self.draw_context.as_mut().unwrap().as_mut();
 	let window = draw_context.spawn_window();
@@ -28,8 +102,11 @@ fn main() {
 	}
 
 	let texture_creator = canvas.texture_creator();
-	let mut game = game::Game::new(Box::new(states::playing::PlayingState::new()));
-	match game.run(canvas, &draw_context, &texture_creator) {
+	let texture_loader = TextureLoader::new(&texture_creator, &files);
+	let font_loader = FontLoader::new(&draw_context.font_context, &files);
+	let mut game = game::Game::new(Box::new(states::levelselect::LevelSelectState::new(players)));
+	let settings = GameSettings { action_map, difficulty, rank, frame_capture_every_n_frames };
+	match game.run(canvas, &draw_context, &files, &texture_loader, &font_loader, settings) {
 		Ok(_) => {},
 		Err(e) => panic!("Error during game execution : {}", e),
 	}
@@ -12,9 +12,20 @@ pub fn rect_overlap(rect1: &sdl2::rect::Rect, rect2: &sdl2::rect::Rect) -> bool
 }
 
 pub fn collision(world: &World, entity1: &EntityId, entity2: &EntityId) -> bool {
-	let rect1 = rect(world, entity1);
-	let rect2 = rect(world, entity2);
-	rect_overlap(&rect1, &rect2)
+	let rects1 = rects(world, entity1);
+	let rects2 = rects(world, entity2);
+	rects1.iter().any(|rect1| rects2.iter().any(|rect2| rect_overlap(rect1, rect2)))
+}
+
+// Main hitbox and additional parts, in world coordinates
+pub fn rects(world: &World, entity: &EntityId) -> Vec<sdl2::rect::Rect> {
+	let entity_pos = world.get_component::<TransformComponent>(entity).unwrap();
+	let entity_box = world.get_component::<HitboxComponent>(entity).unwrap();
+	let mut rects = vec![rect(world, entity)];
+	for part in &entity_box.parts {
+		rects.push(sdl2::rect::Rect::new((entity_pos.x + part.x as f32) as i32, (entity_pos.y + part.y as f32) as i32, part.width(), part.height()));
+	}
+	rects
 }
 
 pub fn rect(world: &World, entity: &EntityId) -> sdl2::rect::Rect {
@@ -1,7 +1,7 @@
 use fastapprox::{fast};
-use sdl2::{pixels::Color, rect::Rect, render::BlendMode};
+use sdl2::{pixels::Color, rect::Rect};
 
-use crate::{core::{common::{self, GameServices}, ecs::Runnable, events::EventObserver, renderers::Renderable}, levels::{phase_basic_spawn::LevelPhaseBasicSpawn}};
+use crate::{core::{common::{self, GameServices}, ecs::Runnable, events::EventObserver, renderers::Renderable}, levels::{phases::LevelPhase}};
 
 pub struct BackgroundStarField {
 	src_width: u32,
@@ -16,7 +16,6 @@ pub struct BackgroundStarField {
 }
 
 const BACKGROUND_COLOR_FADE_SPEED_MS: u64 = 30000;
-const SCROLL_OPACITY_LEVEL: usize = 30;
 const LAYER_INDEX_MAX: usize = 3;
 const BACKGROUND_Z_INDEX: i64 = -10;
 
@@ -28,18 +27,12 @@ impl BackgroundStarField {
 		let mut scroll_speed_ms = Vec::new();
 		scroll_speed_ms.push(next_scroll_speed_ms as f64);
 
-		let fog_texture = game_services.resource_manager.load_unique_texture("fog.png").unwrap();
-		let texture = game_services.resource_manager.get_texture_mut(fog_texture).unwrap();
-		texture.set_blend_mode(BlendMode::Blend);
-		texture.set_alpha_mod(20);
+		let fog_texture = game_services.resource_manager.load_unique_texture("fog").unwrap();
 		resources.push(fog_texture);
 
 		for layer_index in 0..LAYER_INDEX_MAX {
-			let name = format!("star_background-{}.png", (layer_index + 1));
+			let name = format!("star_background_{}", (layer_index + 1));
 			let layer_texture = game_services.resource_manager.load_unique_texture(name.as_str()).unwrap();
-			let texture = game_services.resource_manager.get_texture_mut(layer_texture).unwrap();
-			texture.set_blend_mode(BlendMode::Blend);
-			texture.set_alpha_mod(((layer_index + 1) * SCROLL_OPACITY_LEVEL) as u8);
 			scroll_speed_ms.push(next_scroll_speed_ms / (layer_index + 1) as f64);
 			resources.push(layer_texture);
 		}
@@ -57,6 +50,11 @@ impl BackgroundStarField {
 		}
 	}
 
+	pub fn set_colors(&mut self, color_start: Color, color_end: Color) {
+		self.color_start = color_start;
+		self.color_end = color_end;
+	}
+
 	fn color_mix_rgb(color_start: &Color, color_end: &Color, alpha: u8, percents: f32) -> Color {
 		Color::RGBA(
 			(color_start.r as f32 * (1.0 - percents) + color_end.r as f32 * percents) as u8,
@@ -111,8 +109,8 @@ impl Runnable for BackgroundStarField {
 	}
 }
 
-impl EventObserver<LevelPhaseBasicSpawn> for BackgroundStarField {
-	fn on_event_mut(&mut self, data: &LevelPhaseBasicSpawn) {
-		self.next_scroll_speed_ms = (30.0 / data.hyperspace_speed) * 30.0;
+impl EventObserver<dyn LevelPhase> for BackgroundStarField {
+	fn on_event_mut(&mut self, data: &dyn LevelPhase) {
+		self.next_scroll_speed_ms = (30.0 / data.hyperspace_speed()) * 30.0;
 	}
 }
@@ -1,10 +1,21 @@
-use sdl2::{event::Event, keyboard::Keycode, rect::Rect};
+use std::{cmp::Reverse, sync::{Arc, RwLock}};
+
+use sdl2::rect::Rect;
 use tuple_list::tuple_list_type;
-use crate::{core::{common::GameServices, ecs::{WeakRunnable}, renderers::Renderable, resources::FontDetails, states::{self, State, StateWithSystems}}, systems::{ai::AISystem, animation::AnimationSystem, graphics::GraphicsSystem, lifetime::LifetimeSystem, physics::PhysicsSystem}};
+use super::{highscores::HighScoresState, nameentry::NameEntryState};
+use crate::{core::{actions::{Action, ActionEvent}, common::GameServices, ecs::{WeakRunnable}, highscores::HighScores, meta, renderers::Renderable, states::{self, State, StateWithSystems, STATE_ID_COUNTER}}, systems::{ai::AISystem, animation::AnimationSystem, graphics::GraphicsSystem, lifetime::LifetimeSystem, physics::PhysicsSystem}};
+
+const END_MUSIC_FADE_MS: i32 = 800;
 
 pub struct GameOverState {
 	text: String,
-	score: u32,
+	music: &'static str,
+	scores: Vec<u32>,
+	high_scores: Option<Arc<RwLock<HighScores>>>,
+	high_score_key: String,
+	// Set once the scores are confirmed, the name entry and the high scores listing follow
+	show_high_scores: bool,
+	names_entered: bool,
 	restart: bool,
 	font_index: i64,
 	texture_index: Vec<i64>,
@@ -12,10 +23,16 @@ pub struct GameOverState {
 }
 
 impl GameOverState {
-	pub fn new(victory: bool, score: u32) -> Self {
+	// One score per player, "high_score_key" being the high scores table of the game mode
+	pub fn new(victory: bool, scores: Vec<u32>, high_score_key: String) -> Self {
 		GameOverState {
 			text: if victory { "VICTORY".to_string() } else { "GAME OVER".to_string() },
-			score: score,
+			music: if victory { "music_victory" } else { "music_game_over" },
+			scores,
+			high_scores: None,
+			high_score_key,
+			show_high_scores: false,
+			names_entered: false,
 			restart: false,
 			font_index: 0,
 			texture_index: Vec::new(),
@@ -24,41 +41,78 @@ impl GameOverState {
 	}
 }
 
+impl GameOverState {
+	fn scores_text(&self) -> String {
+		if self.scores.len() == 1 {
+			format!("SCORE {}", self.scores[0])
+		} else {
+			self.scores.iter().enumerate().map(|(index, score)| format!("P{} {}", index + 1, score)).collect::<Vec<String>>().join("   ")
+		}
+	}
+}
+
+impl GameOverState {
+	// (player index, score) of the players entering the high scores, best score first
+	fn qualifying_scores(&self, high_scores: &HighScores) -> Vec<(usize, u32)> {
+		let mut entries: Vec<(usize, u32)> = self.scores.iter().copied().enumerate()
+			.filter(|(_, score)| high_scores.qualifies(&self.high_score_key, *score)).collect();
+		entries.sort_by_key(|entry| Reverse(entry.1));
+		entries
+	}
+}
+
 impl states::StateSystems for GameOverState {
 	type Systems = tuple_list_type!(GraphicsSystem, AnimationSystem, PhysicsSystem, LifetimeSystem, AISystem);
 }
 
 impl State for GameOverState {
-	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, _last_state_id: Option<usize>) {
+	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, last_state_id: Option<usize>) {
 		println!("GAME OVER");
+		if !create {
+			// Back from the name entry the new table is listed, back from the listing the game restarts
+			if last_state_id == Some(*meta::numeric_type_id::<HighScoresState>(&STATE_ID_COUNTER)) {
+				self.restart = true;
+			} else if last_state_id == Some(*meta::numeric_type_id::<NameEntryState>(&STATE_ID_COUNTER)) {
+				self.names_entered = true;
+				self.show_high_scores = true;
+			}
+		}
 		if create {
-			self.font_index = game_services.resource_manager.load_font(&FontDetails { path: "I-pixel-u.ttf".to_string(), size: 42 }).unwrap();
+			self.high_scores = Some(Arc::new(RwLock::new(HighScores::load(&HighScores::default_path()))));
+			// Played once, the game over screen then stays silent
+			game_services.audio_manager.crossfade_music(self.music, 1, END_MUSIC_FADE_MS);
+			self.font_index = game_services.resource_manager.load_font("font_big").unwrap();
 			let (i, rect) = game_services.resource_manager.text_to_texture(self.font_index, self.text.as_str(), None).unwrap();
 			self.texture_index.push(i);
 			self.rect.push(Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, game_services.draw_context.screen_height() as i32 / 2 - (rect.h * 3 / 2), rect.w as u32, rect.h as u32));
 
-			let (i, rect) = game_services.resource_manager.text_to_texture(self.font_index, format!("SCORE {}", self.score).as_str(), None).unwrap();
+			let (i, rect) = game_services.resource_manager.text_to_texture(self.font_index, self.scores_text().as_str(), None).unwrap();
 			self.texture_index.push(i);
 			self.rect.push(Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, game_services.draw_context.screen_height() as i32 / 2 - rect.h / 2, rect.w as u32, rect.h as u32));
 		}
 	}
 
-	fn on_event(&mut self, event: &Event) -> bool {
-		match event {
-			Event::Quit { .. }
-			| Event::KeyDown {
-					keycode: Some(Keycode::Escape),
-					..
-			} | Event::KeyDown {
-				keycode: Some(Keycode::Return),
-				..
-		} => self.restart = true,
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		match (action.action, action.pressed) {
+			(Action::Quit, true) => self.restart = true,
+			(Action::Confirm, true) => self.show_high_scores = true,
 			_ => {}
 		}
 		false
 	}
 
-	fn update<'sdl_all, 'l>(&mut self, _next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+	fn update<'sdl_all, 'l>(&mut self, next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		if self.show_high_scores && !self.restart {
+			self.show_high_scores = false;
+			let high_scores = self.high_scores.clone().unwrap();
+			let entries = if self.names_entered { Vec::new() } else { self.qualifying_scores(&high_scores.read().unwrap()) };
+			if entries.is_empty() {
+				*next_state = Some(StateWithSystems::new(Box::new(HighScoresState::new(high_scores, self.high_score_key.clone(), self.scores.clone()))));
+			} else {
+				*next_state = Some(StateWithSystems::new(Box::new(NameEntryState::new(high_scores, self.high_score_key.clone(), entries))));
+			}
+			return true;
+		}
 		for i in 0.. self.texture_index.len() {
 			let renderable = Renderable::new(self.texture_index[i], None, Some(self.rect[i]), 99999);
 			game_services.renderer.push_renderable(renderable);
@@ -0,0 +1,81 @@
+use std::sync::{Arc, RwLock};
+
+use sdl2::rect::Rect;
+use tuple_list::tuple_list_type;
+use crate::{core::{actions::{Action, ActionEvent}, common::GameServices, ecs::{WeakRunnable}, highscores::HighScores, renderers::Renderable, states::{self, State, StateWithSystems}}, systems::graphics::GraphicsSystem};
+
+// Listing of one high scores table
+pub struct HighScoresState {
+	high_scores: Arc<RwLock<HighScores>>,
+	table_key: String,
+	// Scores of the last game, marked in the listing
+	highlight: Vec<u32>,
+	leave: bool,
+	texture_index: Vec<i64>,
+	rect: Vec<Rect>
+}
+
+impl HighScoresState {
+	pub fn new(high_scores: Arc<RwLock<HighScores>>, table_key: String, highlight: Vec<u32>) -> Self {
+		HighScoresState {
+			high_scores,
+			table_key,
+			highlight,
+			leave: false,
+			texture_index: Vec::new(),
+			rect: Vec::new()
+		}
+	}
+}
+
+impl states::StateSystems for HighScoresState {
+	type Systems = tuple_list_type!(GraphicsSystem);
+}
+
+impl State for HighScoresState {
+	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, _last_state_id: Option<usize>) {
+		println!("HIGH SCORES");
+		if create {
+			let font_index = game_services.resource_manager.load_font("font_big").unwrap();
+			let font_small_index = game_services.resource_manager.load_font("font_small").unwrap();
+			let mut lines = vec![("HIGH SCORES".to_string(), font_index), (self.table_key.to_uppercase(), font_small_index)];
+			let high_scores = self.high_scores.read().unwrap();
+			let table = high_scores.table(&self.table_key);
+			if table.is_empty() {
+				lines.push(("NO SCORE YET".to_string(), font_small_index));
+			}
+			for (rank, high_score) in table.iter().enumerate() {
+				let marker = if self.highlight.contains(&high_score.score) { ">" } else { " " };
+				lines.push((format!("{} {:>2}. {} {:>8}", marker, rank + 1, high_score.name, high_score.score), font_small_index));
+			}
+
+			let mut y = game_services.draw_context.screen_height() as i32 / 6;
+			for (text, font) in lines {
+				let (i, rect) = game_services.resource_manager.text_to_texture(font, text.as_str(), None).unwrap();
+				self.texture_index.push(i);
+				self.rect.push(Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, y, rect.w as u32, rect.h as u32));
+				y += rect.h * 3 / 2;
+			}
+		}
+	}
+
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		match (action.action, action.pressed) {
+			(Action::Quit, true) | (Action::Confirm, true) => self.leave = true,
+			_ => {}
+		}
+		false
+	}
+
+	fn update<'sdl_all, 'l>(&mut self, _next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		for i in 0.. self.texture_index.len() {
+			let renderable = Renderable::new(self.texture_index[i], None, Some(self.rect[i]), 99999);
+			game_services.renderer.push_renderable(renderable);
+		}
+		! self.leave
+	}
+
+	fn on_leave<'sdl_all, 'l>(&mut self, _game_services: &mut GameServices<'sdl_all,'l>, _destroy: bool) {
+		println!("HIGH SCORES END {}", _destroy);
+	}
+}
@@ -0,0 +1,151 @@
+use std::sync::{Arc, RwLock};
+
+use sdl2::rect::Rect;
+use tuple_list::tuple_list_type;
+use super::playing::PlayingState;
+use crate::{core::{actions::{Action, ActionEvent}, common::GameServices, ecs::{WeakRunnable}, progress::CampaignProgress, renderers::Renderable, states::{self, State, StateWithSystems}}};
+
+// First screen : the players choose the level the game starts from, among the unlocked ones
+pub struct LevelSelectState {
+	players_count: usize,
+	progress: Arc<RwLock<CampaignProgress>>,
+	// (id, name) of every level of the campaign
+	levels: Vec<(String, String)>,
+	cursor: usize,
+	start: bool,
+	// Sticks send a stream of events while held, so each action only counts once until released
+	held_actions: Vec<Action>,
+	dirty: bool,
+	font_index: i64,
+	font_small_index: i64,
+	texture_index: Vec<i64>,
+	rect: Vec<Rect>
+}
+
+impl LevelSelectState {
+	pub fn new(players_count: usize) -> Self {
+		LevelSelectState {
+			players_count,
+			progress: Arc::new(RwLock::new(CampaignProgress::new(&CampaignProgress::default_path()))),
+			levels: Vec::new(),
+			cursor: 0,
+			start: false,
+			held_actions: Vec::new(),
+			dirty: true,
+			font_index: 0,
+			font_small_index: 0,
+			texture_index: Vec::new(),
+			rect: Vec::new()
+		}
+	}
+
+	fn is_unlocked(&self, index: usize) -> bool {
+		self.progress.read().unwrap().is_unlocked(index, &self.levels[index].0)
+	}
+
+	// Moves to the next unlocked level in that direction, if any
+	fn move_cursor(&mut self, forward: bool) {
+		let mut index = self.cursor;
+		loop {
+			index = match (forward, index) {
+				(true, index) if index + 1 < self.levels.len() => index + 1,
+				(false, index) if index > 0 => index - 1,
+				_ => return
+			};
+			if self.is_unlocked(index) {
+				self.cursor = index;
+				self.dirty = true;
+				return;
+			}
+		}
+	}
+
+	fn lines(&self) -> Vec<(String, bool)> {
+		let mut lines = vec![("SELECT STAGE".to_string(), true)];
+		for (index, (_, name)) in self.levels.iter().enumerate() {
+			let marker = if index == self.cursor { ">" } else { " " };
+			let name = if self.is_unlocked(index) { name.as_str() } else { "LOCKED" };
+			lines.push((format!("{} STAGE {}  {}", marker, index + 1, name), false));
+		}
+		lines.push(("UP / DOWN : STAGE   CONFIRM : START".to_string(), false));
+		lines
+	}
+
+	fn set_text<'sdl_all, 'l>(&mut self, line: usize, text: &str, big: bool, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let font = if big { self.font_index } else { self.font_small_index };
+		let existing = self.texture_index.get(line).copied();
+		let (i, rect) = game_services.resource_manager.text_to_texture(font, text, existing).unwrap();
+		let y = game_services.draw_context.screen_height() as i32 / 4 + line as i32 * rect.h * 3 / 2;
+		let rect = Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, y, rect.w as u32, rect.h as u32);
+		if existing.is_some() {
+			self.rect[line] = rect;
+		} else {
+			self.texture_index.push(i);
+			self.rect.push(rect);
+		}
+	}
+}
+
+impl states::StateSystems for LevelSelectState {
+	type Systems = tuple_list_type!();
+}
+
+impl State for LevelSelectState {
+	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, _last_state_id: Option<usize>) {
+		println!("LEVEL SELECT");
+		if create {
+			self.font_index = game_services.resource_manager.load_font("font_big").unwrap();
+			self.font_small_index = game_services.resource_manager.load_font("font_small").unwrap();
+			self.levels = game_services.levels.levels().iter().map(|level| (level.id.clone(), level.name.clone())).collect();
+		}
+		// Back from a game, new levels may have been unlocked
+		*self.progress.write().unwrap() = CampaignProgress::load(&CampaignProgress::default_path());
+		self.start = false;
+		self.dirty = true;
+	}
+
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		let held = self.held_actions.contains(&action.action);
+		if !action.pressed || action.value < 0.5 {
+			self.held_actions.retain(|held_action| *held_action != action.action);
+			return false;
+		}
+		if held {
+			return false;
+		}
+		self.held_actions.push(action.action);
+		match action.action {
+			Action::MoveUp => self.move_cursor(false),
+			Action::MoveDown => self.move_cursor(true),
+			Action::Confirm => self.start = true,
+			Action::Quit => return true,
+			_ => {}
+		}
+		false
+	}
+
+	fn update<'sdl_all, 'l>(&mut self, next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		if self.start {
+			self.start = false;
+			self.held_actions.clear();
+			println!("START STAGE {}", self.cursor + 1);
+			*next_state = Some(StateWithSystems::new(Box::new(PlayingState::new(self.players_count, self.cursor, self.progress.clone()))));
+			return true;
+		}
+		if self.dirty {
+			for (line, (text, big)) in self.lines().into_iter().enumerate() {
+				self.set_text(line, text.as_str(), big, game_services);
+			}
+			self.dirty = false;
+		}
+		for i in 0.. self.texture_index.len() {
+			let renderable = Renderable::new(self.texture_index[i], None, Some(self.rect[i]), 99999);
+			game_services.renderer.push_renderable(renderable);
+		}
+		true
+	}
+
+	fn on_leave<'sdl_all, 'l>(&mut self, _game_services: &mut GameServices<'sdl_all,'l>, _destroy: bool) {
+		println!("LEVEL SELECT END {}", _destroy);
+	}
+}
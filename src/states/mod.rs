@@ -2,4 +2,11 @@ pub mod pause;
 pub mod playing;
 pub mod background;
 pub mod score;
-pub mod gameover;
\ No newline at end of file
+pub mod gameover;
+pub mod powerups;
+pub mod waves;
+pub mod nameentry;
+pub mod highscores;
+pub mod rank;
+pub mod stageclear;
+pub mod levelselect;
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::sync::{Arc, RwLock};
+
+use sdl2::rect::Rect;
+use tuple_list::tuple_list_type;
+use crate::{core::{actions::{Action, ActionEvent}, common::GameServices, ecs::{WeakRunnable}, highscores::{HIGH_SCORE_NAME_LENGTH, HighScores}, renderers::Renderable, states::{self, State, StateWithSystems}}, systems::graphics::GraphicsSystem};
+
+const NAME_LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-";
+
+// Name entry for each player whose score made it into the high scores, one player after the other
+pub struct NameEntryState {
+	high_scores: Arc<RwLock<HighScores>>,
+	table_key: String,
+	// (player index, score) of the players still to enter their name
+	entries: Vec<(usize, u32)>,
+	// Index in NAME_LETTERS of each letter of the current name
+	letters: [usize; HIGH_SCORE_NAME_LENGTH],
+	cursor: usize,
+	// Sticks send a stream of events while held, so each action only counts once until released
+	held_actions: Vec<Action>,
+	validated: bool,
+	dirty: bool,
+	font_index: i64,
+	font_small_index: i64,
+	texture_index: Vec<i64>,
+	rect: Vec<Rect>
+}
+
+impl NameEntryState {
+	pub fn new(high_scores: Arc<RwLock<HighScores>>, table_key: String, entries: Vec<(usize, u32)>) -> Self {
+		NameEntryState {
+			high_scores,
+			table_key,
+			entries,
+			letters: [0; HIGH_SCORE_NAME_LENGTH],
+			cursor: 0,
+			held_actions: Vec::new(),
+			validated: false,
+			dirty: true,
+			font_index: 0,
+			font_small_index: 0,
+			texture_index: Vec::new(),
+			rect: Vec::new()
+		}
+	}
+
+	fn name(&self) -> String {
+		self.letters.iter().map(|letter| NAME_LETTERS[*letter] as char).collect()
+	}
+
+	// "A [B] C" : the letter under the cursor is between brackets
+	fn name_text(&self) -> String {
+		self.letters.iter().enumerate().map(|(index, letter)| {
+			let letter = NAME_LETTERS[*letter] as char;
+			if index == self.cursor { format!("[{}]", letter) } else { format!(" {} ", letter) }
+		}).collect()
+	}
+
+	fn change_letter(&mut self, forward: bool) {
+		let letter = &mut self.letters[self.cursor];
+		*letter = if forward { (*letter + 1) % NAME_LETTERS.len() } else { (*letter + NAME_LETTERS.len() - 1) % NAME_LETTERS.len() };
+		self.dirty = true;
+	}
+
+	// Records the current name, and moves to the next player
+	fn validate(&mut self) {
+		let (player, score) = self.entries.remove(0);
+		let name = self.name();
+		println!("HIGH SCORE P{} {} {}", player + 1, name, score);
+		self.high_scores.write().unwrap().insert(&self.table_key, &name, score);
+		self.letters = [0; HIGH_SCORE_NAME_LENGTH];
+		self.cursor = 0;
+		self.dirty = true;
+		if self.entries.is_empty() {
+			if let Err(e) = self.high_scores.read().unwrap().save() {
+				println!("HIGH SCORES NOT SAVED : {}", e);
+			}
+			self.validated = true;
+		}
+	}
+
+	fn set_text<'sdl_all, 'l>(&mut self, line: usize, text: &str, big: bool, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let font = if big { self.font_index } else { self.font_small_index };
+		let existing = self.texture_index.get(line).copied();
+		let (i, rect) = game_services.resource_manager.text_to_texture(font, text, existing).unwrap();
+		let y = game_services.draw_context.screen_height() as i32 / 3 + line as i32 * rect.h * 3 / 2;
+		let rect = Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, y, rect.w as u32, rect.h as u32);
+		if existing.is_some() {
+			self.rect[line] = rect;
+		} else {
+			self.texture_index.push(i);
+			self.rect.push(rect);
+		}
+	}
+}
+
+impl states::StateSystems for NameEntryState {
+	type Systems = tuple_list_type!(GraphicsSystem);
+}
+
+impl State for NameEntryState {
+	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, _last_state_id: Option<usize>) {
+		println!("NAME ENTRY");
+		if create {
+			self.font_index = game_services.resource_manager.load_font("font_big").unwrap();
+			self.font_small_index = game_services.resource_manager.load_font("font_small").unwrap();
+		}
+	}
+
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		let held = self.held_actions.contains(&action.action);
+		if !action.pressed || action.value < 0.5 {
+			self.held_actions.retain(|held_action| *held_action != action.action);
+			return false;
+		}
+		if held || self.validated {
+			return false;
+		}
+		self.held_actions.push(action.action);
+		match action.action {
+			Action::MoveUp => self.change_letter(true),
+			Action::MoveDown => self.change_letter(false),
+			Action::MoveLeft => {
+				self.cursor = self.cursor.saturating_sub(1);
+				self.dirty = true;
+			},
+			Action::MoveRight if self.cursor + 1 < HIGH_SCORE_NAME_LENGTH => {
+				self.cursor += 1;
+				self.dirty = true;
+			},
+			// Not on shoot : it shares its default key and button with confirm
+			Action::Confirm => self.validate(),
+			Action::Quit => return true,
+			_ => {}
+		}
+		false
+	}
+
+	fn update<'sdl_all, 'l>(&mut self, _next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		if self.validated {
+			return false;
+		}
+		if self.dirty {
+			let (player, score) = self.entries[0];
+			self.set_text(0, "NEW HIGH SCORE", true, game_services);
+			self.set_text(1, format!("P{}   {}", player + 1, score).as_str(), false, game_services);
+			self.set_text(2, self.name_text().as_str(), true, game_services);
+			self.set_text(3, "UP / DOWN : LETTER   CONFIRM : OK", false, game_services);
+			self.dirty = false;
+		}
+		for i in 0.. self.texture_index.len() {
+			let renderable = Renderable::new(self.texture_index[i], None, Some(self.rect[i]), 99999);
+			game_services.renderer.push_renderable(renderable);
+		}
+		true
+	}
+
+	fn on_leave<'sdl_all, 'l>(&mut self, _game_services: &mut GameServices<'sdl_all,'l>, _destroy: bool) {
+		println!("NAME ENTRY END {}", _destroy);
+	}
+}
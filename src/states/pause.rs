@@ -1,6 +1,6 @@
-use sdl2::{event::Event, keyboard::Keycode, rect::Rect};
+use sdl2::rect::Rect;
 use tuple_list::tuple_list_type;
-use crate::{core::{common::GameServices, ecs::{WeakRunnable}, renderers::Renderable, resources::FontDetails, states::{self, State, StateWithSystems}}};
+use crate::{core::{actions::{Action, ActionEvent}, common::GameServices, ecs::{WeakRunnable}, renderers::Renderable, states::{self, State, StateWithSystems}}};
 
 pub struct PauseState {
 	resume: bool,
@@ -29,9 +29,10 @@ impl states::StateSystems for PauseState {
 impl State for PauseState {
 	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, _last_state_id: Option<usize>) {
 		println!("GAME PAUSED");
+		game_services.audio_manager.duck_music(true);
 		if create {
-			self.font_index = game_services.resource_manager.load_font(&FontDetails { path: "I-pixel-u.ttf".to_string(), size: 42 }).unwrap();
-			self.font_small_index = game_services.resource_manager.load_font(&FontDetails { path: "I-pixel-u.ttf".to_string(), size: 16 }).unwrap();
+			self.font_index = game_services.resource_manager.load_font("font_big").unwrap();
+			self.font_small_index = game_services.resource_manager.load_font("font_small").unwrap();
 			let (i, rect) = game_services.resource_manager.text_to_texture(self.font_index, "PAUSED", None).unwrap();
 			self.texture_index.push(i);
 			self.rect.push(Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, game_services.draw_context.screen_height() as i32 / 2 - (rect.h * 3 / 2), rect.w as u32, rect.h as u32));
@@ -42,20 +43,10 @@ impl State for PauseState {
 		}
 	}
 
-	fn on_event(&mut self, event: &Event) -> bool {
-		match event {
-			Event::Quit { .. }
-			| Event::KeyDown {
-					keycode: Some(Keycode::Escape),
-					..
-			} => return true,
-			Event::KeyDown {
-					keycode: Some(Keycode::Space),
-					repeat: false,
-					..
-			} => {
-				self.resume = true;
-			}
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		match (action.action, action.pressed) {
+			(Action::Quit, true) => return true,
+			(Action::Confirm, true) | (Action::Pause, true) => self.resume = true,
 			_ => {}
 		}
 		false
@@ -69,7 +60,8 @@ impl State for PauseState {
 		! self.resume
 	}
 
-	fn on_leave<'sdl_all, 'l>(&mut self, _game_services: &mut GameServices<'sdl_all,'l>, _destroy: bool) {
+	fn on_leave<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>, _destroy: bool) {
 		println!("GAME RESUMED {}", _destroy);
+		game_services.audio_manager.duck_music(false);
 	}
 }
@@ -1,112 +1,254 @@
 use std::sync::{Arc, RwLock};
 
-use sdl2::{event::Event, keyboard::Keycode};
 use tuple_list::{tuple_list_type};
 
-use crate::{components::{hitbox::HitboxComponent, input::{InputComponent, PlayerInput}}, core::{common::GameServices, ecs::{self, WeakRunnable, make_shared_runnable}, meta, states::{self, STATE_ID_COUNTER, StateWithSystems}}, factory, levels::{level::Level, level1::{Level1End, Level1Mid, Level1Mid2, Level1Start}, phase_basic_spawn::LevelPhaseBasicSpawn}, systems::{ai::AISystem, animation::AnimationSystem, graphics::GraphicsSystem, health::HealthSystem, input::InputSystem, lifetime::LifetimeSystem, physics::PhysicsSystem, shot::ShotSystem, spawner::SpawnMobSystem}};
+use crate::{components::{enemy::{EnemyEscapedEvent, WaveClearedEvent}, health::{DamageEvent, DeathEvent}, hitbox::HitboxComponent, input::{InputComponent, PlayerInput}, invulnerability::InvulnerabilityComponent, player::PlayerComponent}, core::{actions::{Action, ActionEvent}, common::{self, GameServices}, ecs::{self, WeakRunnable, make_shared_runnable}, difficulty::Difficulty, highscores::HighScores, meta, progress::CampaignProgress, states::{self, STATE_ID_COUNTER, StateWithSystems}}, factory, levels::{conditions::PhaseProgress, level::Level, phases::LevelPhase}, systems::{ai::AISystem, shot, animation::AnimationSystem, bomb::BombSystem, boss::BossSystem, bullet::BulletSystem, graphics::GraphicsSystem, health::HealthSystem, homing::HomingSystem, input::InputSystem, invulnerability::InvulnerabilitySystem, lifetime::LifetimeSystem, physics::PhysicsSystem, powerup::PowerUpSystem, shot::ShotSystem, spawner::SpawnMobSystem}};
 
-use super::{background::BackgroundStarField, gameover::GameOverState, pause::PauseState, score::ScoreHandler};
+use super::{background::BackgroundStarField, gameover::GameOverState, pause::PauseState, powerups::PowerUpDropper, rank::RankTracker, score::ScoreHandler, stageclear::StageClearState, waves::WaveTracker};
+
+// Ship sprite of each player, reused when there are more players than sprites
+const PLAYER_SHIPS: [&str; 2] = ["spaceship", "spaceship_2"];
+// Time between the destruction of a ship and the respawn of the next one
+const PLAYER_RESPAWN_DELAY_MS: u64 = 1500;
+const PLAYER_RESPAWN_INVULNERABILITY_MS: u64 = 2500;
+
+struct Player {
+	entity: ecs::EntityId,
+	inputs: [f32; PlayerInput::LAST as usize],
+	lives: u32,
+	// Set while the player waits to respawn
+	respawn_time_ms: Option<u64>,
+	score_handler: Arc<RwLock<ScoreHandler>>
+}
 
 pub struct PlayingState {
-	player: ecs::EntityId,
+	players_count: usize,
+	players: Vec<Player>,
 	pause: bool,
-	inputs: [bool; PlayerInput::LAST as usize],
-	levels: Vec<Level<LevelPhaseBasicSpawn>>,
+	levels: Vec<Level<dyn LevelPhase>>,
 	current_level_index: usize,
+	// Level chosen in the level select, the score, lives and weapons then carry over the next levels
+	first_level_index: usize,
+	progress: Arc<RwLock<CampaignProgress>>,
+	// Set once the game is over, back to the level select
+	finished: bool,
 	background: Option<Arc<RwLock<BackgroundStarField>>>,
-	score_handler: Option<Arc<RwLock<ScoreHandler>>>
+	powerup_dropper: Option<Arc<RwLock<PowerUpDropper>>>,
+	wave_tracker: Option<Arc<RwLock<WaveTracker>>>,
+	rank_tracker: Option<Arc<RwLock<RankTracker>>>,
+	// Kills and score, for the level phases completion conditions
+	phase_progress: Option<Arc<RwLock<PhaseProgress>>>
 }
 
 impl PlayingState  {
-	pub fn new() -> Self {
+	pub fn new(players_count: usize, first_level_index: usize, progress: Arc<RwLock<CampaignProgress>>) -> Self {
 		PlayingState {
-			player: 0,
+			players_count,
+			players: Vec::new(),
 			pause: false,
-			inputs: [false; PlayerInput::LAST as usize],
 			levels: Vec::new(),
-			current_level_index: 0,
+			current_level_index: first_level_index,
+			first_level_index,
+			progress,
+			finished: false,
 			background: None,
-			score_handler: None,
+			powerup_dropper: None,
+			wave_tracker: None,
+			rank_tracker: None,
+			phase_progress: None
+		}
+	}
+
+	fn get_current_scores(&self) -> Vec<u32> {
+		self.players.iter().map(|player| player.score_handler.read().unwrap().score()).collect()
+	}
+
+	// Unlocks the next level, and shows the result of the finished one
+	fn stage_clear<'sdl_all, 'l>(&self, game_services: &GameServices<'sdl_all, 'l>) -> StateWithSystems {
+		let levels = game_services.levels.levels();
+		let (finished, next) = (&levels[self.current_level_index - 1], &levels[self.current_level_index]);
+		let mut progress = self.progress.write().unwrap();
+		let unlocked = progress.unlock(&next.id);
+		if unlocked {
+			if let Err(e) = progress.save() {
+				println!("PROGRESS NOT SAVED : {}", e);
+			}
+		}
+		StateWithSystems::new(Box::new(StageClearState::new(finished.name.clone(), self.get_current_scores(), if unlocked { Some(next.name.clone()) } else { None })))
+	}
+
+	fn start_level(&mut self) {
+		if let (Some(level), Some(background)) = (self.levels.get(self.current_level_index), self.background.as_ref()) {
+			if let Some((color_start, color_end)) = level.background_colors() {
+				background.write().unwrap().set_colors(color_start, color_end);
+			}
+		}
+	}
+
+	// Solo and co-op games have their own high scores, for each difficulty
+	fn high_score_key(&self, difficulty: Difficulty) -> String {
+		let mode = if self.players_count > 1 { format!("coop{}", self.players_count) } else { "solo".to_string() };
+		HighScores::table_key(&mode, difficulty.name())
+	}
+
+	// Entity ids are recycled : the entity must still be this player's ship
+	fn is_player_alive<'sdl_all, 'l>(player: &Player, index: usize, game_services: &GameServices<'sdl_all, 'l>) -> bool {
+		let world = game_services.get_world();
+		world.is_alive(&player.entity) && world.get_component::<PlayerComponent>(&player.entity).is_some_and(|component| component.index == index)
+	}
+
+	// Loses a life and waits before respawning, returns false once the player has no ship left
+	fn update_dead_player<'sdl_all, 'l>(player: &mut Player, index: usize, players_count: usize, game_services: &mut GameServices<'sdl_all, 'l>) -> bool {
+		match player.respawn_time_ms {
+			None if player.lives > 0 => {
+				player.lives -= 1;
+				player.respawn_time_ms = Some(common::current_time_ms() + PLAYER_RESPAWN_DELAY_MS);
+				player.score_handler.write().unwrap().set_lives(player.lives);
+				true
+			},
+			None => false,
+			Some(respawn_time_ms) => {
+				if common::current_time_ms() >= respawn_time_ms {
+					player.entity = Self::spawn_player(index, players_count, game_services);
+					let invulnerability = InvulnerabilityComponent::new(common::current_time_ms() + PLAYER_RESPAWN_INVULNERABILITY_MS, shot::INVULNERABILITY_BLINK_PERIOD_MS);
+					game_services.get_world_mut().add_component(&player.entity, invulnerability);
+					player.score_handler.write().unwrap().set_player(player.entity);
+					player.respawn_time_ms = None;
+				}
+				true
+			}
 		}
 	}
 
-	fn get_current_score(&self) -> u32 {
-		self.score_handler.as_ref().unwrap().read().unwrap().score()
+	// Players are spread along the bottom of the screen
+	fn spawn_player<'sdl_all, 'l>(index: usize, players_count: usize, game_services: &mut GameServices<'sdl_all, 'l>) -> ecs::EntityId {
+		let src_width = 16;
+		let src_height = 16;
+		let width = src_width * 4;
+		let height = src_height * 4;
+		let x = (game_services.draw_context.screen_width() * (index as u32 + 1) / (players_count as u32 + 1)) as i32 - width as i32 / 2;
+		let y = (game_services.draw_context.screen_height() - height - 5) as i32;
+		let player = factory::create_player(PLAYER_SHIPS[index % PLAYER_SHIPS.len()], x, y, 0, width, height, 10.0, index, game_services);
+		let hitbox = game_services.get_world_mut().get_component_mut::<HitboxComponent>(&player).unwrap();
+		hitbox.hitbox.x += hitbox.hitbox.w / 2;
+		hitbox.hitbox.y += hitbox.hitbox.h;
+
+		hitbox.hitbox.w /= 8;
+		hitbox.hitbox.h /= 8;
+
+		hitbox.hitbox.x -= hitbox.hitbox.w / 2;
+		hitbox.hitbox.y -= hitbox.hitbox.h;
+		player
 	}
 }
 
 impl states::StateSystems for PlayingState {
-	type Systems = tuple_list_type!(GraphicsSystem, InputSystem, PhysicsSystem, ShotSystem, LifetimeSystem, SpawnMobSystem, AISystem, HealthSystem, AnimationSystem);
+	type Systems = tuple_list_type!(GraphicsSystem, InputSystem, PhysicsSystem, ShotSystem, LifetimeSystem, SpawnMobSystem, AISystem, HealthSystem, AnimationSystem, InvulnerabilitySystem, HomingSystem, PowerUpSystem, BulletSystem, BossSystem, BombSystem);
 }
 
 impl states::State for PlayingState  {
 	fn on_enter<'sdl_all, 'l>(&mut self, runnables: &mut Vec<WeakRunnable>, game_services: & mut GameServices<'sdl_all, 'l>, create: bool, last_state_id: Option<usize>) {
 		if !create && last_state_id.is_some() && *meta::numeric_type_id::<GameOverState>(&STATE_ID_COUNTER) == last_state_id.unwrap() {
-			self.on_leave(game_services, true);
-			self.on_enter(runnables, game_services, true, None);
+			self.finished = true;
 			return;
 		}
 
 		println!("ENTER PLAYING ! {}", create);
 
+		if let Some(level) = self.levels.get_mut(self.current_level_index) {
+			level.resume();
+		}
 		if create {
 			self.background = Some(Arc::new(RwLock::new(BackgroundStarField::new(game_services))));
 			runnables.push(Arc::downgrade(&make_shared_runnable(self.background.as_ref().unwrap().clone())));
-			self.score_handler = Some(Arc::new(RwLock::new(ScoreHandler::new(game_services.resource_manager))));
-			let src_width = 16;
-			let src_height = 16;
-			let width = src_width * 4;
-			let height = src_height * 4;
-			let x = ((game_services.draw_context.screen_width() - width) / 2)  as i32;
-			let y = (game_services.draw_context.screen_height() - height - 5) as i32;
-			self.player = factory::create_player("spaceship.png", x, y, 0, width, height, 10.0, game_services);
-			let hitbox = game_services.get_world_mut().get_component_mut::<HitboxComponent>(&self.player).unwrap();
-			hitbox.hitbox.x += hitbox.hitbox.w / 2;
-			hitbox.hitbox.y += hitbox.hitbox.h;
-
-			hitbox.hitbox.w /= 8;
-			hitbox.hitbox.h /= 8;
-
-			hitbox.hitbox.x -= hitbox.hitbox.w / 2;
-			hitbox.hitbox.y -= hitbox.hitbox.h;
-
-			let level1 = Level::new(vec![Box::new(Level1Start::new()), Box::new(Level1Mid::new()), Box::new(Level1Mid2::new()), Box::new(Level1End::new())], self.background.as_ref().unwrap().clone());
-			self.levels.push(level1);
-			//self.levels.push(Level::new(vec![Box::new(Level1End::new())]));
-
-			game_services.event_dispatcher.register(self.score_handler.as_ref().unwrap().clone());
+			let powerup_dropper = Arc::new(RwLock::new(PowerUpDropper::new()));
+			game_services.event_dispatcher.register(powerup_dropper.clone());
+			runnables.push(Arc::downgrade(&make_shared_runnable(powerup_dropper.clone())));
+			self.powerup_dropper = Some(powerup_dropper);
+			let wave_tracker = Arc::new(RwLock::new(WaveTracker::new()));
+			game_services.event_dispatcher.register::<DeathEvent>(wave_tracker.clone());
+			game_services.event_dispatcher.register::<EnemyEscapedEvent>(wave_tracker.clone());
+			runnables.push(Arc::downgrade(&make_shared_runnable(wave_tracker.clone())));
+			self.wave_tracker = Some(wave_tracker);
+			if let Some(rank) = game_services.rank.as_mut() {
+				rank.reset();
+			}
+			let rank_tracker = Arc::new(RwLock::new(RankTracker::new(game_services.resource_manager)));
+			game_services.event_dispatcher.register::<DeathEvent>(rank_tracker.clone());
+			game_services.event_dispatcher.register::<DamageEvent>(rank_tracker.clone());
+			self.rank_tracker = Some(rank_tracker);
+			let phase_progress = Arc::new(RwLock::new(PhaseProgress::new()));
+			game_services.event_dispatcher.register::<DeathEvent>(phase_progress.clone());
+			self.phase_progress = Some(phase_progress);
+			let lives = game_services.difficulty.settings().player_lives;
+			for index in 0..self.players_count {
+				let entity = Self::spawn_player(index, self.players_count, game_services);
+				let label = if self.players_count > 1 { format!("P{}", index + 1) } else { "SCORE".to_string() };
+				let ship = PLAYER_SHIPS[index % PLAYER_SHIPS.len()];
+				let score_handler = Arc::new(RwLock::new(ScoreHandler::new(game_services.resource_manager, game_services.enemy_types.clone(), entity, index, label, ship, lives)));
+				game_services.event_dispatcher.register::<DeathEvent>(score_handler.clone());
+				game_services.event_dispatcher.register::<WaveClearedEvent>(score_handler.clone());
+				self.players.push(Player { entity, inputs: [0.0; PlayerInput::LAST as usize], lives, respawn_time_ms: None, score_handler });
+			}
+
+			let level_definitions = game_services.levels.clone();
+			for definition in level_definitions.levels() {
+				self.levels.push(definition.build(self.background.as_ref().unwrap().clone()));
+			}
+			self.start_level();
 		}
 	}
 
 	fn update<'sdl_all, 'l>(&mut self, next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		if self.finished {
+			return false;
+		}
 		if self.pause {
 			let pause_state: Option<StateWithSystems> = Some(StateWithSystems::new(Box::new(PauseState::new())));
 			*next_state = pause_state;
 			self.pause = false;
 		}
-		let player_alive = game_services.get_world().is_alive(&self.player);
-		if player_alive {
-			if let Some(input) = game_services.get_world_mut().get_component_mut::<InputComponent>(&self.player) {
-				input.inputs = self.inputs.clone();
+		let mut players_alive = false;
+		for (index, player) in self.players.iter_mut().enumerate() {
+			if Self::is_player_alive(player, index, game_services) {
+				players_alive = true;
+				if let Some(input) = game_services.get_world_mut().get_component_mut::<InputComponent>(&player.entity) {
+					input.inputs = player.inputs;
+				}
+			} else if Self::update_dead_player(player, index, self.players_count, game_services) {
+				players_alive = true;
 			}
-		} else {
-			println!("PLAYER DEAD");
-			let game_over_state: Option<StateWithSystems> = Some(StateWithSystems::new(Box::new(GameOverState::new(false, self.get_current_score()))));
+		}
+		// Game over once every player is dead with no ship left
+		if !players_alive {
+			println!("PLAYERS DEAD");
+			let game_over_state: Option<StateWithSystems> = Some(StateWithSystems::new(Box::new(GameOverState::new(false, self.get_current_scores(), self.high_score_key(game_services.difficulty)))));
 			*next_state = game_over_state;
 		}
 
 		//self.background.as_mut().unwrap().write().unwrap().update(game_services);
-		self.score_handler.as_mut().unwrap().write().unwrap().update(&mut game_services.resource_manager, &mut game_services.renderer);
+		for player in &self.players {
+			player.score_handler.write().unwrap().update(game_services);
+		}
+		if let Some(rank_tracker) = self.rank_tracker.as_ref() {
+			rank_tracker.write().unwrap().update(game_services);
+		}
 
 		if self.current_level_index < self.levels.len() {
-			if ! self.levels[self.current_level_index].update(game_services) {
+			let phase_progress = self.phase_progress.as_ref().unwrap().clone();
+			phase_progress.write().unwrap().score = self.get_current_scores().iter().sum();
+			if ! self.levels[self.current_level_index].update(game_services, &phase_progress.read().unwrap()) {
 				self.current_level_index += 1;
+				self.start_level();
+				if players_alive && self.current_level_index < self.levels.len() {
+					*next_state = Some(self.stage_clear(game_services));
+				}
 			}
 			true
 		} else {
 			// No more levels
 			// Victory if player_alive
-			let game_over_state: Option<StateWithSystems> = Some(StateWithSystems::new(Box::new(GameOverState::new(player_alive, self.get_current_score()))));
+			let game_over_state: Option<StateWithSystems> = Some(StateWithSystems::new(Box::new(GameOverState::new(players_alive, self.get_current_scores(), self.high_score_key(game_services.difficulty)))));
 			*next_state = game_over_state;
 			true
 		}
@@ -115,93 +257,53 @@ impl states::State for PlayingState  {
 	fn on_leave<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all,'l>, destroy: bool) {
 		println!("LEAVE PLAYING ! {}", destroy);
 		if destroy {
-			game_services.event_dispatcher.unregister(self.score_handler.as_ref().unwrap().clone());
+			for player in self.players.drain(..) {
+				game_services.event_dispatcher.unregister::<DeathEvent>(player.score_handler.clone());
+				game_services.event_dispatcher.unregister::<WaveClearedEvent>(player.score_handler);
+			}
+			if let Some(powerup_dropper) = self.powerup_dropper.take() {
+				game_services.event_dispatcher.unregister(powerup_dropper);
+			}
+			if let Some(wave_tracker) = self.wave_tracker.take() {
+				game_services.event_dispatcher.unregister::<DeathEvent>(wave_tracker.clone());
+				game_services.event_dispatcher.unregister::<EnemyEscapedEvent>(wave_tracker);
+			}
+			if let Some(rank_tracker) = self.rank_tracker.take() {
+				game_services.event_dispatcher.unregister::<DeathEvent>(rank_tracker.clone());
+				game_services.event_dispatcher.unregister::<DamageEvent>(rank_tracker);
+			}
+			if let Some(phase_progress) = self.phase_progress.take() {
+				game_services.event_dispatcher.unregister::<DeathEvent>(phase_progress);
+			}
 			self.background = None;
-			self.score_handler = None;
-			self.current_level_index = 0;
+			self.current_level_index = self.first_level_index;
 			self.levels.clear();
 
 			game_services.get_world_mut().reset();
+			game_services.resource_manager.clear_scope();
+			game_services.renderer.clear_renderables();
 		}
 	}
 
-	fn on_event(&mut self, event: &Event) -> bool {
-		match event {
-				Event::Quit { .. }
-				| Event::KeyDown {
-						keycode: Some(Keycode::Escape),
-						..
-				} => return true,
-				Event::KeyDown {
-						keycode: Some(Keycode::Return),
-						repeat: false,
-						..
-				} => {
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		let player_input = match action.action {
+			Action::Quit => return action.pressed,
+			Action::Pause => {
+				if action.pressed {
 					self.pause = true;
-				},
-				Event::KeyDown {
-					keycode: Some(Keycode::Q),
-					..
-				} => {
-					self.inputs[PlayerInput::LEFT as usize] = true;
-				},
-				Event::KeyDown {
-					keycode: Some(Keycode::D),
-					..
-				} => {
-					self.inputs[PlayerInput::RIGHT as usize] = true;
-				},
-				Event::KeyDown {
-					keycode: Some(Keycode::Z),
-					..
-				} => {
-					self.inputs[PlayerInput::UP as usize] = true;
-				},
-				Event::KeyDown {
-					keycode: Some(Keycode::S),
-					..
-				} => {
-					self.inputs[PlayerInput::DOWN as usize] = true;
-				},
-				Event::KeyDown {
-					keycode: Some(Keycode::Space),
-					repeat: false,
-					..
-				} => {
-					self.inputs[PlayerInput::SHOOT as usize] = true;
-				},
-				Event::KeyUp {
-					keycode: Some(Keycode::Q),
-					..
-				} => {
-					self.inputs[PlayerInput::LEFT as usize] = false;
-				},
-				Event::KeyUp {
-					keycode: Some(Keycode::D),
-					..
-				} => {
-					self.inputs[PlayerInput::RIGHT as usize] = false;
-				},
-				Event::KeyUp {
-					keycode: Some(Keycode::Z),
-					..
-				} => {
-					self.inputs[PlayerInput::UP as usize] = false;
-				},
-				Event::KeyUp {
-					keycode: Some(Keycode::S),
-					..
-				} => {
-					self.inputs[PlayerInput::DOWN as usize] = false;
-				},
-				Event::KeyUp {
-					keycode: Some(Keycode::Space),
-					repeat: false,
-					..
-				} => {
-					self.inputs[PlayerInput::SHOOT as usize] = false;
-				},
-				_ => {}
+				}
+				return false;
+			},
+			Action::MoveLeft => PlayerInput::LEFT,
+			Action::MoveRight => PlayerInput::RIGHT,
+			Action::MoveUp => PlayerInput::UP,
+			Action::MoveDown => PlayerInput::DOWN,
+			Action::Shoot => PlayerInput::SHOOT,
+			Action::Bomb => PlayerInput::BOMB,
+			_ => return false
+		};
+		if let Some(player) = self.players.get_mut(action.player) {
+			player.inputs[player_input as usize] = action.value;
 		}
 		false
 	}
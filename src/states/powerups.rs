@@ -0,0 +1,46 @@
+use rand::{Rng};
+
+use crate::{components::{health::DeathEvent, powerup::PowerUpKind, weapon::WeaponType}, core::{common::GameServices, ecs::Runnable, events::EventObserver}, factory};
+
+// Chance for a destroyed enemy to drop a power-up
+const POWERUP_DROP_CHANCE: f64 = 0.15;
+
+// Drops power-ups where enemies killed by a player die
+pub struct PowerUpDropper {
+	drops: Vec<(f32, f32)>
+}
+
+impl PowerUpDropper {
+	pub fn new() -> Self {
+		PowerUpDropper {
+			drops: Vec::new()
+		}
+	}
+}
+
+impl Default for PowerUpDropper {
+	fn default() -> Self {
+		PowerUpDropper::new()
+	}
+}
+
+impl EventObserver<DeathEvent> for PowerUpDropper {
+	fn on_event_mut(&mut self, data: &DeathEvent) {
+		if data.killer.is_some() && !data.player && rand::thread_rng().gen_bool(POWERUP_DROP_CHANCE) {
+			self.drops.push(data.position);
+		}
+	}
+}
+
+impl Runnable for PowerUpDropper {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let mut rng = rand::thread_rng();
+		for position in self.drops.drain(..) {
+			// As likely as each weapon type
+			let index = rng.gen_range(0, WeaponType::ALL.len() + 1);
+			let kind = WeaponType::ALL.get(index).map_or(PowerUpKind::Bomb, |weapon_type| PowerUpKind::Weapon(*weapon_type));
+			println!("POWERUP DROP");
+			factory::create_powerup(kind, position.0 as i32, position.1 as i32, game_services);
+		}
+	}
+}
@@ -0,0 +1,71 @@
+use sdl2::rect::Rect;
+
+use crate::{components::health::{DamageEvent, DeathEvent}, core::{common::GameServices, events::EventObserver, renderers::{Renderable, SdlResourceManager}}};
+
+// Feeds the kills, hits and deaths of the players to the rank of the game services, and displays it in dev mode
+pub struct RankTracker {
+	kills: u32,
+	hits: u32,
+	deaths: u32,
+	font_index: i64,
+	readout: Option<(i64, Rect, String)>
+}
+
+impl RankTracker {
+	pub fn new<'current, 'sdl_all>(resource_manager: &'current mut SdlResourceManager<'sdl_all>) -> Self {
+		RankTracker {
+			kills: 0,
+			hits: 0,
+			deaths: 0,
+			font_index: resource_manager.load_font("font_small").unwrap(),
+			readout: None
+		}
+	}
+
+	// Called once per playing frame, which drives the rank steps
+	pub fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let rank = match game_services.rank.as_mut() {
+			Some(rank) => rank,
+			None => return
+		};
+		for _ in 0..self.kills { rank.on_kill(); }
+		for _ in 0..self.hits { rank.on_hit(); }
+		for _ in 0..self.deaths { rank.on_death(); }
+		(self.kills, self.hits, self.deaths) = (0, 0, 0);
+		rank.advance_frame();
+
+		if cfg!(feature = "dev-mode") {
+			self.draw_readout(game_services);
+		}
+	}
+
+	fn draw_readout<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let text = game_services.rank.as_ref().unwrap().describe();
+		if self.readout.as_ref().is_none_or(|(_, _, last_text)| *last_text != text) {
+			let existing = self.readout.as_ref().map(|(index, _, _)| *index);
+			let (index, rect) = game_services.resource_manager.text_to_texture(self.font_index, text.as_str(), existing).unwrap();
+			let rect = Rect::new(game_services.draw_context.screen_width() as i32 - rect.w, 0, rect.w as u32, rect.h as u32);
+			self.readout = Some((index, rect, text));
+		}
+		let (index, rect, _) = self.readout.as_ref().unwrap();
+		game_services.renderer.push_renderable(Renderable::new(*index, None, Some(*rect), i64::MAX - 1));
+	}
+}
+
+impl EventObserver<DeathEvent> for RankTracker {
+	fn on_event_mut(&mut self, data: &DeathEvent) {
+		if data.player {
+			self.deaths += 1;
+		} else if data.killer.is_some() {
+			self.kills += 1;
+		}
+	}
+}
+
+impl EventObserver<DamageEvent> for RankTracker {
+	fn on_event_mut(&mut self, data: &DamageEvent) {
+		if data.player {
+			self.hits += 1;
+		}
+	}
+}
@@ -1,30 +1,132 @@
+use std::{collections::HashMap, sync::Arc};
+
 use sdl2::{rect::Rect};
 
-use crate::{components::health::DeathEvent, core::{events::EventObserver, renderers::{Renderable, SdlRenderer, SdlResourceManager}, resources::{FontDetails}}};
+use crate::{components::{bomb::{BOMB_START_STOCK, BombComponent}, enemy::WaveClearedEvent, health::DeathEvent, powerup::PowerUpKind}, core::{common::{self, GameServices}, ecs::EntityId, enemies::EnemyRegistry, events::EventObserver, renderers::{Renderable, SdlResourceManager}}, factory};
+
+const LIVES_ICON_SIZE: u32 = 16;
+const LIVES_ICON_MARGIN: i32 = 8;
+// A kill within this delay after the previous one keeps the combo chain going
+const COMBO_DECAY_MS: u64 = 2000;
+// The multiplier goes up every COMBO_STEP chained kills
+const COMBO_STEP: u32 = 5;
+const MAX_MULTIPLIER: u32 = 8;
+const WAVE_BONUS_PER_ENEMY: u32 = 20;
 
+// Score and remaining lives of one player, displayed at the top left of the screen (one line per player)
 pub struct ScoreHandler {
+	player: EntityId,
+	// Index of the player (see PlayerComponent), which is also its line on the screen
+	player_index: usize,
+	label: String,
 	score: u32,
 	score_texture_index: i64,
 	score_rect: Rect,
 	font_index: i64,
+	lives: u32,
+	ship_texture_index: i64,
+	ship_frame: Option<Rect>,
+	// Bombs in stock, last read from the player ship
+	bombs: u32,
+	powerup_texture_index: i64,
+	bomb_frame: Option<Rect>,
+	// Score value of the enemy types
+	enemy_types: Arc<EnemyRegistry>,
+	combo: u32,
+	combo_end_ms: u64,
+	// (points, position) of the popups to display
+	pending_popups: Vec<(u32, (f32, f32))>,
+	// Popup text textures, by points value
+	popup_textures: HashMap<u32, (i64, Rect)>,
 	dirty: bool
 }
 
 impl ScoreHandler {
-	pub fn new<'current, 'sdl_all>(resource_manager: &'current mut SdlResourceManager<'sdl_all>) -> Self {
-		let font = resource_manager.load_font(&FontDetails { path: "I-pixel-u.ttf".to_string(), size: 16 }).unwrap();
-		let (texture_index, texture_rect) = resource_manager.text_to_texture(font, "SCORE 0", None).unwrap();
-		ScoreHandler { score: 0, score_texture_index: texture_index, score_rect: texture_rect, font_index: font, dirty: false }
+	// ship : texture asset of the player ship, its first frame is the lives icon
+	pub fn new<'current, 'sdl_all>(resource_manager: &'current mut SdlResourceManager<'sdl_all>, enemy_types: Arc<EnemyRegistry>, player: EntityId, player_index: usize, label: String, ship: &str, lives: u32) -> Self {
+		let font = resource_manager.load_font("font_small").unwrap();
+		let (texture_index, texture_rect) = resource_manager.text_to_texture(font, format!("{} 0", label).as_str(), None).unwrap();
+		let ship_texture_index = resource_manager.load_shared_texture(ship).unwrap();
+		let ship_frame = resource_manager.texture_grid(ship).unwrap().map(|grid| Rect::new(0, 0, grid.width, grid.height));
+		let powerup_texture_index = resource_manager.load_shared_texture("powerup").unwrap();
+		let bomb_frame = resource_manager.texture_grid("powerup").unwrap().map(|grid| Rect::new((PowerUpKind::Bomb.frame() as u32 * grid.width) as i32, 0, grid.width, grid.height));
+		let mut score_handler = ScoreHandler { player, player_index, label, score: 0, score_texture_index: texture_index, score_rect: texture_rect, font_index: font,
+			lives, ship_texture_index, ship_frame, bombs: BOMB_START_STOCK, powerup_texture_index, bomb_frame, enemy_types, combo: 0, combo_end_ms: 0,
+			pending_popups: Vec::new(), popup_textures: HashMap::new(), dirty: false };
+		score_handler.place();
+		score_handler
+	}
+
+	fn place(&mut self) {
+		self.score_rect.set_y(self.player_index as i32 * self.score_rect.height() as i32);
+	}
+
+	pub fn multiplier(&self) -> u32 {
+		(1 + self.combo / COMBO_STEP).min(MAX_MULTIPLIER)
+	}
+
+	fn add_points(&mut self, points: u32, position: (f32, f32)) {
+		if points > 0 {
+			self.score += points;
+			self.pending_popups.push((points, position));
+			self.dirty = true;
+		}
 	}
 
-	pub fn update<'sdl_all>(&mut self, resource_manager: &mut SdlResourceManager<'sdl_all>, renderer: &mut SdlRenderer) {
+	fn spawn_popups<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		for (points, position) in self.pending_popups.drain(..) {
+			let font_index = self.font_index;
+			let (texture_index, rect) = *self.popup_textures.entry(points).or_insert_with(|| {
+				game_services.resource_manager.text_to_texture(font_index, format!("+{}", points).as_str(), None).unwrap()
+			});
+			factory::create_text_popup(texture_index, rect.width(), rect.height(), position.0 as i32 - rect.width() as i32 / 2, position.1 as i32 - rect.height() as i32 / 2, game_services);
+		}
+	}
+
+	pub fn update<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		// The chain is broken once the delay is over
+		if self.combo > 0 && common::current_time_ms() > self.combo_end_ms {
+			self.dirty = self.dirty || self.multiplier() > 1;
+			self.combo = 0;
+		}
 		if self.dirty {
-			let (_, texture_rect) = resource_manager.text_to_texture(self.font_index, format!("SCORE {}", self.score).as_str(), Some(self.score_texture_index)).unwrap();
+			let text = if self.multiplier() > 1 { format!("{} {} x{}", self.label, self.score, self.multiplier()) } else { format!("{} {}", self.label, self.score) };
+			let (_, texture_rect) = game_services.resource_manager.text_to_texture(self.font_index, text.as_str(), Some(self.score_texture_index)).unwrap();
 			self.score_rect = texture_rect;
+			self.place();
 			self.dirty = false;
 		}
+		self.spawn_popups(game_services);
+
 		let renderable = Renderable::new(self.score_texture_index, None, Some(self.score_rect), i64::MAX - 1);
-		renderer.push_renderable(renderable);
+		game_services.renderer.push_renderable(renderable);
+
+		let icon_y = self.score_rect.y() + (self.score_rect.height() as i32 - LIVES_ICON_SIZE as i32) / 2;
+		for life in 0..self.lives {
+			let icon_x = self.score_rect.right() + LIVES_ICON_MARGIN + (life * LIVES_ICON_SIZE) as i32;
+			let icon = Renderable::new(self.ship_texture_index, self.ship_frame, Some(Rect::new(icon_x, icon_y, LIVES_ICON_SIZE, LIVES_ICON_SIZE)), i64::MAX - 1);
+			game_services.renderer.push_renderable(icon);
+		}
+
+		// Bombs after the lives, with the frame of the bomb power-up
+		if let Some(bomb) = game_services.get_world().get_component::<BombComponent>(&self.player) {
+			self.bombs = bomb.stock;
+		}
+		let bombs_x = self.score_rect.right() + LIVES_ICON_MARGIN * 2 + (self.lives * LIVES_ICON_SIZE) as i32;
+		for bomb in 0..self.bombs {
+			let icon_x = bombs_x + (bomb * LIVES_ICON_SIZE) as i32;
+			let icon = Renderable::new(self.powerup_texture_index, self.bomb_frame, Some(Rect::new(icon_x, icon_y, LIVES_ICON_SIZE, LIVES_ICON_SIZE)), i64::MAX - 1);
+			game_services.renderer.push_renderable(icon);
+		}
+	}
+
+	// The player ship entity changes on respawn
+	pub fn set_player(&mut self, player: EntityId) {
+		self.player = player;
+	}
+
+	pub fn set_lives(&mut self, lives: u32) {
+		self.lives = lives;
 	}
 
 	pub fn score(&self) -> u32 {
@@ -33,8 +135,26 @@ impl ScoreHandler {
 }
 
 impl EventObserver<DeathEvent> for ScoreHandler {
-	fn on_event_mut(&mut self, _data: &DeathEvent) {
-		self.score += 5;
-		self.dirty = true;
+	fn on_event_mut(&mut self, data: &DeathEvent) {
+		if data.player && data.entity == self.player {
+			// Losing a ship breaks the chain
+			self.combo = 0;
+			self.dirty = true;
+		} else if data.killer == Some(self.player_index) {
+			let now = common::current_time_ms();
+			self.combo = if now <= self.combo_end_ms { self.combo + 1 } else { 1 };
+			self.combo_end_ms = now + COMBO_DECAY_MS;
+			let points = data.enemy_type.as_ref().map_or(0, |enemy_type| self.enemy_types.score(enemy_type)) * self.multiplier();
+			self.add_points(points, data.position);
+			self.dirty = true;
+		}
+	}
+}
+
+impl EventObserver<WaveClearedEvent> for ScoreHandler {
+	fn on_event_mut(&mut self, data: &WaveClearedEvent) {
+		if data.killer == self.player_index {
+			self.add_points(data.size as u32 * WAVE_BONUS_PER_ENEMY, data.position);
+		}
 	}
 }
@@ -0,0 +1,87 @@
+use sdl2::rect::Rect;
+use tuple_list::tuple_list_type;
+use crate::{core::{actions::{Action, ActionEvent}, common::GameServices, ecs::{WeakRunnable}, renderers::Renderable, states::{self, State, StateWithSystems}}, systems::{animation::AnimationSystem, graphics::GraphicsSystem}};
+
+const STAGE_CLEAR_MUSIC_FADE_MS: i32 = 800;
+
+// Result screen between two levels of the campaign, the game goes on with the next level once confirmed
+pub struct StageClearState {
+	stage_name: String,
+	scores: Vec<u32>,
+	// Name of the next level, when it has just been unlocked
+	unlocked: Option<String>,
+	leave: bool,
+	texture_index: Vec<i64>,
+	rect: Vec<Rect>
+}
+
+impl StageClearState {
+	pub fn new(stage_name: String, scores: Vec<u32>, unlocked: Option<String>) -> Self {
+		StageClearState {
+			stage_name,
+			scores,
+			unlocked,
+			leave: false,
+			texture_index: Vec::new(),
+			rect: Vec::new()
+		}
+	}
+
+	fn scores_text(&self) -> String {
+		if self.scores.len() == 1 {
+			format!("SCORE {}", self.scores[0])
+		} else {
+			self.scores.iter().enumerate().map(|(index, score)| format!("P{} {}", index + 1, score)).collect::<Vec<String>>().join("   ")
+		}
+	}
+}
+
+impl states::StateSystems for StageClearState {
+	// The ships stay still on screen
+	type Systems = tuple_list_type!(GraphicsSystem, AnimationSystem);
+}
+
+impl State for StageClearState {
+	fn on_enter<'sdl_all, 'l>(&mut self, _runnables: &mut Vec<WeakRunnable>, game_services: &mut GameServices<'sdl_all,'l>, create: bool, _last_state_id: Option<usize>) {
+		println!("STAGE CLEAR {}", self.stage_name);
+		if create {
+			game_services.audio_manager.crossfade_music("music_victory", 1, STAGE_CLEAR_MUSIC_FADE_MS);
+			let font_index = game_services.resource_manager.load_font("font_big").unwrap();
+			let font_small_index = game_services.resource_manager.load_font("font_small").unwrap();
+			let mut lines = vec![("STAGE CLEAR".to_string(), font_index), (self.stage_name.clone(), font_small_index), (self.scores_text(), font_small_index)];
+			if let Some(unlocked) = &self.unlocked {
+				lines.push((format!("{} UNLOCKED", unlocked), font_small_index));
+			}
+			lines.push(("CONFIRM : NEXT STAGE".to_string(), font_small_index));
+
+			let mut y = game_services.draw_context.screen_height() as i32 / 3;
+			for (text, font) in lines {
+				let (i, rect) = game_services.resource_manager.text_to_texture(font, text.as_str(), None).unwrap();
+				self.texture_index.push(i);
+				self.rect.push(Rect::new(game_services.draw_context.screen_width() as i32 / 2 - rect.w / 2, y, rect.w as u32, rect.h as u32));
+				y += rect.h * 3 / 2;
+			}
+		}
+	}
+
+	fn on_action(&mut self, action: &ActionEvent) -> bool {
+		match (action.action, action.pressed) {
+			(Action::Quit, true) => return true,
+			(Action::Confirm, true) => self.leave = true,
+			_ => {}
+		}
+		false
+	}
+
+	fn update<'sdl_all, 'l>(&mut self, _next_state: &mut Option<StateWithSystems>, game_services: &mut GameServices<'sdl_all,'l>) -> bool {
+		for i in 0.. self.texture_index.len() {
+			let renderable = Renderable::new(self.texture_index[i], None, Some(self.rect[i]), 99999);
+			game_services.renderer.push_renderable(renderable);
+		}
+		! self.leave
+	}
+
+	fn on_leave<'sdl_all, 'l>(&mut self, _game_services: &mut GameServices<'sdl_all,'l>, _destroy: bool) {
+		println!("STAGE CLEAR END {}", _destroy);
+	}
+}
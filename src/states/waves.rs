@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::{components::{enemy::{EnemyEscapedEvent, Wave, WaveClearedEvent}, health::DeathEvent}, core::{common::GameServices, ecs::Runnable, events::EventObserver}};
+
+// Single enemies are not worth a wave bonus
+const MIN_WAVE_SIZE: u16 = 2;
+
+// Counts the enemies destroyed in each wave, and notifies a WaveClearedEvent once a whole wave is destroyed
+// Enemies leaving the screen are not destroyed, their wave can't be cleared anymore
+pub struct WaveTracker {
+	// Destroyed and escaped enemies of each wave, forgotten once every enemy of the wave is gone
+	waves: HashMap<u32, (u16, u16)>,
+	cleared: Vec<WaveClearedEvent>
+}
+
+impl WaveTracker {
+	pub fn new() -> Self {
+		WaveTracker {
+			waves: HashMap::new(),
+			cleared: Vec::new()
+		}
+	}
+
+	// Returns true when the enemy was the last one of a wave entirely destroyed
+	fn count(&mut self, wave: Wave, destroyed: bool) -> bool {
+		let (kills, escaped) = self.waves.entry(wave.id).or_insert((0, 0));
+		if destroyed {
+			*kills += 1;
+		} else {
+			*escaped += 1;
+		}
+		let cleared = *kills >= wave.size;
+		if *kills + *escaped >= wave.size {
+			self.waves.remove(&wave.id);
+		}
+		cleared
+	}
+}
+
+impl Default for WaveTracker {
+	fn default() -> Self {
+		WaveTracker::new()
+	}
+}
+
+impl EventObserver<DeathEvent> for WaveTracker {
+	fn on_event_mut(&mut self, data: &DeathEvent) {
+		let wave = match data.wave {
+			Some(wave) if !data.player && wave.size >= MIN_WAVE_SIZE => wave,
+			_ => return
+		};
+		// Without a killer, the enemy was not destroyed by the players
+		let cleared = self.count(wave, data.killer.is_some());
+		if let (true, Some(killer)) = (cleared, data.killer) {
+			self.cleared.push(WaveClearedEvent { killer, position: data.position, size: wave.size });
+		}
+	}
+}
+
+impl EventObserver<EnemyEscapedEvent> for WaveTracker {
+	fn on_event_mut(&mut self, data: &EnemyEscapedEvent) {
+		if data.wave.size >= MIN_WAVE_SIZE {
+			self.count(data.wave, false);
+		}
+	}
+}
+
+impl Runnable for WaveTracker {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		for event in self.cleared.drain(..) {
+			println!("WAVE CLEARED ({} ENEMIES)", event.size);
+			game_services.event_dispatcher.notify(&event);
+		}
+	}
+}
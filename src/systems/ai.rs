@@ -3,7 +3,7 @@ use std::sync::{Arc, RwLock, Weak};
 use rand::Rng;
 use tuple_list::tuple_list_type;
 
-use crate::{components::{ai::AIComponent, force::ForceComponent, hitbox::HitboxComponent, input::State, shot::ShotType, sprite::SpriteComponent, transform::TransformComponent}, core::{common::{GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, factory, maths};
+use crate::{components::{ai::{AIComponent, DEFAULT_SHOT_POWER}, bullet::BulletComponent, bullet_pattern::{BulletPattern, BulletPatternComponent}, enemy::{EnemyComponent, EnemyEscapedEvent}, force::ForceComponent, hitbox::HitboxComponent, input::State, shot::ShotType, sprite::SpriteComponent, transform::TransformComponent}, core::{common::{GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, factory, maths};
 
 use super::input::InputSystem;
 
@@ -28,32 +28,48 @@ impl SystemNewable<AISystem, ()> for AISystem {
 const SHOT_LIFETIME_MS: u64 = 6000;
 
 impl AISystem {
-	fn shoot<'sdl_all, 'l>(entity_id: &EntityId, power: f32, input_system: &Weak<RwLock<System>>, game_services: &mut GameServices<'sdl_all, 'l>) {
+	fn random_target(input_system: &Weak<RwLock<System>>) -> Option<EntityId> {
+		let input_system = input_system.upgrade().unwrap();
+		let input_system = input_system.read().unwrap();
+		let input_entities_num = input_system.len_entities();
+		if input_entities_num > 0 {
+			let mut rng = rand::thread_rng();
+			input_system.iter_entities().nth(rng.gen_range(0, input_entities_num)).copied()
+		} else {
+			None
+		}
+	}
+
+	// Fires every emitter of the pattern, "power" scales the emitters speed
+	fn shoot<'sdl_all, 'l>(entity_id: &EntityId, pattern: &BulletPattern, power: f32, input_system: &Weak<RwLock<System>>, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let target = match Self::random_target(input_system) {
+			Some(target) => target,
+			None => return
+		};
 		let shot_width = 16;
 		let shot_height = 16 * 2;
 		let pos = game_services.get_world().get_component::<TransformComponent>(entity_id).unwrap();
 		let graphic_box = game_services.get_world().get_component::<SpriteComponent>(entity_id).unwrap().graphic_box;
 		let shot_pos = (pos.x as i32 + graphic_box.w / 2 + graphic_box.x - shot_width / 2, pos.y as i32 + graphic_box.h + graphic_box.y);
-		let input_entities_num = input_system.upgrade().unwrap().read().unwrap().len_entities();
-		if input_entities_num > 0 {
-			let mut rng = rand::thread_rng();
-			let mut random_target_entity: i16 = rng.gen_range(0, input_entities_num) as i16;
 
-			let mut target = 0;
-			for target_id in input_system.upgrade().unwrap().read().unwrap().iter_entities() {
-				if random_target_entity == 0 {
-					target = *target_id;
-					break;
-				} else {
-					random_target_entity -= 1;
-				}
+		let entity_center = maths::center(game_services.get_world(), entity_id);
+		let target_center = maths::center(game_services.get_world(), &target);
+		let aim_degrees = f32::atan2(target_center.1 - entity_center.1, target_center.0 - entity_center.0).to_degrees();
+		let rotation_degrees = game_services.get_world().get_component::<BulletPatternComponent>(entity_id).map_or(0.0, |component| component.rotation_degrees);
+		let speed_factor = power / DEFAULT_SHOT_POWER * game_services.rank_factor();
+
+		let mut rotation_step = 0.0;
+		for emitter in &pattern.emitters {
+			rotation_step += emitter.rotation_step();
+			for angle in emitter.angles(aim_degrees, rotation_degrees) {
+				let bullet = BulletComponent::new(emitter.speed * speed_factor, emitter.acceleration, angle, emitter.angular_velocity);
+				let velocity = bullet.velocity();
+				let shot = factory::create_shot("shot", shot_pos.0, shot_pos.1, 1, shot_width as u32, shot_height as u32, velocity.0, velocity.1, SHOT_LIFETIME_MS, ShotType::ENEMY, *entity_id, game_services);
+				game_services.get_world_mut().add_component(&shot, bullet);
 			}
-
-			let entity_center = maths::center(game_services.get_world(), entity_id);
-			let target_center = maths::center(game_services.get_world(), &target);
-
-			let velocity = maths::next_step_to_pos(entity_center, target_center, power);
-			factory::create_shot("shot.png", shot_pos.0, shot_pos.1, 1, shot_width as u32, shot_height as u32, velocity.0, velocity.1, SHOT_LIFETIME_MS, ShotType::ENEMY, game_services);
+		}
+		if let Some(component) = game_services.get_world_mut().get_component_mut::<BulletPatternComponent>(entity_id) {
+			component.rotation_degrees = (component.rotation_degrees + rotation_step) % 360.0;
 		}
 	}
 
@@ -78,16 +94,30 @@ impl Runnable for AISystem {
 			let current_pos = maths::center(game_services.get_world(), entity_id);
 
 			if game_services.get_world_mut().get_component_mut::<AIComponent>(entity_id).unwrap().can_shoot() {
-				let shot_power = game_services.get_world_mut().get_component_mut::<AIComponent>(entity_id).unwrap().shot_power;
-				Self::shoot(entity_id, shot_power, self.input_system.as_ref().unwrap(), game_services);
+				let world = game_services.get_world();
+				let ai = world.get_component::<AIComponent>(entity_id).unwrap();
+				let shot_power = ai.shot_power;
+				// The trajectory segment pattern takes precedence over the enemy one
+				let pattern = ai.current_pattern().cloned()
+					.or_else(|| world.get_component::<BulletPatternComponent>(entity_id).map(|component| component.pattern.clone()))
+					.unwrap_or_else(BulletPattern::aimed);
+				Self::shoot(entity_id, &pattern, shot_power, self.input_system.as_ref().unwrap(), game_services);
 			}
 
 			let ai = game_services.get_world_mut().get_component_mut::<AIComponent>(entity_id).unwrap();
 			let power = ai.speed;
 			let next_pos = ai.next_position(&current_pos, &power);
-			if next_pos.is_some() {
+			if let Some(next_pos) = next_pos {
+				let velocity_vector = if ai.on_path() {
+					(next_pos.0 - current_pos.0, next_pos.1 - current_pos.1)
+				} else {
+					maths::next_step_to_pos(current_pos, next_pos, power)
+				};
+				// The sprites face the bottom of the screen when not rotated
+				let angle_degrees = ai.heading_degrees().map_or(0, |heading| (heading - 90.0).rem_euclid(360.0) as u64);
+				game_services.get_world_mut().get_component_mut::<SpriteComponent>(entity_id).unwrap().angle_degrees = angle_degrees;
+
 				let force = game_services.get_world_mut().get_component_mut::<ForceComponent>(entity_id).unwrap();
-				let velocity_vector = maths::next_step_to_pos(current_pos, next_pos.unwrap(), power);
 				let state = Self::compute_next_state(&velocity_vector, 2.0);
 
 				force.vx = velocity_vector.0;
@@ -97,6 +127,10 @@ impl Runnable for AISystem {
 				ai.last_state = ai.state;
 				ai.state = state;
 			} else {
+				let wave = game_services.get_world_mut().get_component::<EnemyComponent>(entity_id).and_then(|enemy| enemy.wave);
+				if let Some(wave) = wave {
+					game_services.event_dispatcher.notify(&EnemyEscapedEvent { wave });
+				}
 				game_services.get_world_mut().remove_entity(entity_id);
 				println!("IA DEAD : {}", *entity_id);
 			}
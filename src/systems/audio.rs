@@ -0,0 +1,62 @@
+use crate::{components::{health::{DamageEvent, DeathEvent}, shot::{ShotEvent, ShotType}}, core::{common::GameServices, ecs::Runnable, events::EventObserver}};
+
+const SHOT_SOUND: &str = "shot_fired";
+const EXPLOSION_SOUND: &str = "explosion";
+const PLAYER_HIT_SOUND: &str = "player_hit";
+
+// Turns gameplay events into sound effects.
+// Events are queued and played on the next run, so that observers never need the audio manager
+pub struct AudioSystem {
+	pending_sounds: Vec<&'static str>
+}
+
+impl AudioSystem {
+	pub fn new() -> Self {
+		AudioSystem {
+			pending_sounds: Vec::new()
+		}
+	}
+
+	fn queue(&mut self, sound: &'static str) {
+		// Many identical events in the same frame (e.g. a wave dying at once) only play the sound once
+		if !self.pending_sounds.contains(&sound) {
+			self.pending_sounds.push(sound);
+		}
+	}
+}
+
+impl Default for AudioSystem {
+	fn default() -> Self {
+		AudioSystem::new()
+	}
+}
+
+impl Runnable for AudioSystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		for sound in self.pending_sounds.drain(..) {
+			game_services.audio_manager.play_sound(sound);
+		}
+	}
+}
+
+impl EventObserver<DeathEvent> for AudioSystem {
+	fn on_event_mut(&mut self, _data: &DeathEvent) {
+		self.queue(EXPLOSION_SOUND);
+	}
+}
+
+impl EventObserver<ShotEvent> for AudioSystem {
+	fn on_event_mut(&mut self, data: &ShotEvent) {
+		if data.shot_type == ShotType::PLAYER {
+			self.queue(SHOT_SOUND);
+		}
+	}
+}
+
+impl EventObserver<DamageEvent> for AudioSystem {
+	fn on_event_mut(&mut self, data: &DamageEvent) {
+		if data.player {
+			self.queue(PLAYER_HIT_SOUND);
+		}
+	}
+}
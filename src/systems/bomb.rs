@@ -0,0 +1,90 @@
+use std::sync::{Arc, RwLock, Weak};
+
+use tuple_list::tuple_list_type;
+
+use crate::{components::{bomb::BombComponent, enemy::EnemyComponent, health::{DamageEvent, HealthComponent}, input::{InputComponent, PlayerInput}, invulnerability::InvulnerabilityComponent, player::PlayerComponent, shot::{ShotComponent, ShotType}, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, factory, maths};
+
+use super::{health::HealthSystem, shot::{INVULNERABILITY_BLINK_PERIOD_MS, ShotSystem}};
+
+const BOMB_DAMAGES: i64 = 10;
+const BOMB_INVULNERABILITY_MS: u64 = 2000;
+const BOMB_FLASH_MS: u64 = 150;
+
+pub struct BombSystem {
+	base: Arc<RwLock<System>>,
+	shot_system: Option<Weak<RwLock<System>>>,
+	health_system: Option<Weak<RwLock<System>>>
+}
+
+impl SystemComponents for BombSystem {
+	type Components = tuple_list_type!(BombComponent, InputComponent, TransformComponent);
+}
+
+impl SystemNewable<BombSystem, ()> for BombSystem {
+	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
+		BombSystem {
+			base,
+			shot_system: None,
+			health_system: None
+		}
+	}
+}
+
+impl BombSystem {
+	// Clears the enemy shots and damages every enemy on screen, the bomber being credited for the kills
+	fn detonate<'sdl_all, 'l>(&self, bomber: &EntityId, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let (screen_width, screen_height) = (game_services.draw_context.screen_width() as f32, game_services.draw_context.screen_height() as f32);
+		let shots: Vec<EntityId> = self.shot_system.as_ref().unwrap().upgrade().unwrap().read().unwrap().iter_entities().copied().collect();
+		let targets: Vec<EntityId> = self.health_system.as_ref().unwrap().upgrade().unwrap().read().unwrap().iter_entities().copied().collect();
+
+		let world = game_services.get_world_mut();
+		let bomber_player = world.get_component::<PlayerComponent>(bomber).map(|player| player.index);
+		for shot in shots {
+			if world.get_component::<ShotComponent>(&shot).unwrap().shot_type == ShotType::ENEMY {
+				world.remove_entity(&shot);
+			}
+		}
+		for target in targets {
+			let world = game_services.get_world_mut();
+			if !world.has_component::<EnemyComponent>(&target) {
+				continue;
+			}
+			let center = maths::center(world, &target);
+			if center.0 < 0.0 || center.0 > screen_width || center.1 < 0.0 || center.1 > screen_height {
+				continue;
+			}
+			let health = world.get_component_mut::<HealthComponent>(&target).unwrap();
+			health.health_points -= BOMB_DAMAGES;
+			health.last_damager = bomber_player;
+			game_services.event_dispatcher.notify(&DamageEvent { entity: target, damages: BOMB_DAMAGES as i32, player: false });
+		}
+
+		game_services.get_world_mut().add_component(bomber, InvulnerabilityComponent::new(common::current_time_ms() + BOMB_INVULNERABILITY_MS, INVULNERABILITY_BLINK_PERIOD_MS));
+		factory::create_flash(BOMB_FLASH_MS, game_services);
+	}
+}
+
+impl Runnable for BombSystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		if self.shot_system.is_none() {
+			self.shot_system = game_services.get_world().get_system_base::<ShotSystem>();
+			self.health_system = game_services.get_world().get_system_base::<HealthSystem>();
+		}
+		let entities: Vec<EntityId> = self.base.read().unwrap().iter_entities().copied().collect();
+		for entity in entities {
+			let world = game_services.get_world_mut();
+			let pressed = world.get_component::<InputComponent>(&entity).unwrap().inputs[PlayerInput::BOMB as usize] > 0.0;
+			let bomb = world.get_component_mut::<BombComponent>(&entity).unwrap();
+			if !pressed {
+				bomb.armed = true;
+			} else if bomb.armed {
+				bomb.armed = false;
+				if bomb.stock > 0 {
+					bomb.stock -= 1;
+					println!("BOMB ({} LEFT)", bomb.stock);
+					self.detonate(&entity, game_services);
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,51 @@
+use std::sync::{Arc, RwLock};
+
+use tuple_list::tuple_list_type;
+
+use crate::{components::{ai::AIComponent, boss::BossComponent, bullet_pattern::BulletPatternComponent, health::HealthComponent}, core::{common::GameServices, ecs::{Runnable, System, SystemComponents, SystemNewable}}};
+
+// Switches the boss phases as its health goes down
+pub struct BossSystem {
+	base: Arc<RwLock<System>>
+}
+
+impl SystemComponents for BossSystem {
+	type Components = tuple_list_type!(BossComponent, HealthComponent, AIComponent);
+}
+
+impl SystemNewable<BossSystem, ()> for BossSystem {
+	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
+		BossSystem {
+			base
+		}
+	}
+}
+
+impl Runnable for BossSystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let (screen_width, screen_height) = (game_services.draw_context.screen_width(), game_services.draw_context.screen_height());
+		for entity in self.base.read().unwrap().iter_entities() {
+			let world = game_services.get_world_mut();
+			let health_points = world.get_component::<HealthComponent>(entity).unwrap().health_points;
+			let boss = world.get_component_mut::<BossComponent>(entity).unwrap();
+			let phase_index = boss.phase_for(health_points);
+			if boss.current_phase == Some(phase_index) {
+				continue;
+			}
+			boss.current_phase = Some(phase_index);
+			if cfg!(feature = "dev-mode") {
+				println!("BOSS {} PHASE {}", boss.name, phase_index);
+			}
+			let phase = &boss.phases[phase_index];
+			let (trajectory, speed, pattern) = (phase.trajectory(screen_width, screen_height), phase.speed, phase.pattern.clone());
+
+			let ai = world.get_component_mut::<AIComponent>(entity).unwrap();
+			ai.set_movement_patterns(vec![trajectory]);
+			ai.speed = speed;
+			if let Some(bullet_pattern) = world.get_component_mut::<BulletPatternComponent>(entity) {
+				bullet_pattern.pattern = pattern;
+				bullet_pattern.rotation_degrees = 0.0;
+			}
+		}
+	}
+}
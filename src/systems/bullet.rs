@@ -0,0 +1,43 @@
+use std::sync::{Arc, RwLock};
+
+use tuple_list::tuple_list_type;
+
+use crate::{components::{bullet::BulletComponent, force::ForceComponent}, core::{common::GameServices, ecs::{Runnable, System, SystemComponents, SystemNewable}}};
+
+// Slowing bullets keep drifting instead of stopping or going backward
+const BULLET_MIN_SPEED: f32 = 1.0;
+const BULLET_MAX_SPEED: f32 = 20.0;
+
+pub struct BulletSystem {
+	base: Arc<RwLock<System>>
+}
+
+impl SystemComponents for BulletSystem {
+	type Components = tuple_list_type!(BulletComponent, ForceComponent);
+}
+
+impl SystemNewable<BulletSystem, ()> for BulletSystem {
+	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
+		BulletSystem {
+			base
+		}
+	}
+}
+
+impl Runnable for BulletSystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		for entity in self.base.read().unwrap().iter_entities() {
+			let world = game_services.get_world_mut();
+			let bullet = world.get_component_mut::<BulletComponent>(entity).unwrap();
+			if bullet.acceleration == 0.0 && bullet.angular_velocity == 0.0 {
+				continue;
+			}
+			bullet.speed = (bullet.speed + bullet.acceleration).clamp(BULLET_MIN_SPEED, BULLET_MAX_SPEED);
+			bullet.direction += bullet.angular_velocity;
+			let velocity = bullet.velocity();
+			let force = world.get_component_mut::<ForceComponent>(entity).unwrap();
+			force.vx = velocity.0;
+			force.vy = velocity.1;
+		}
+	}
+}
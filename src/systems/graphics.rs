@@ -26,10 +26,14 @@ impl Runnable for GraphicsSystem {
 		let mut renderables = Vec::with_capacity(self.base.read().unwrap().len_entities());
 		for entity_id in self.base.read().unwrap().iter_entities() {
 			let sprite = game_services.get_world().get_component::<SpriteComponent>(entity_id).unwrap();
+			if !sprite.visible {
+				continue;
+			}
 			let pos = game_services.get_world().get_component::<TransformComponent>(entity_id).unwrap();
 			let rect = Rect::new(pos.x as i32 + sprite.graphic_box.x, pos.y as i32 + sprite.graphic_box.y, sprite.graphic_box.width(), sprite.graphic_box.height());
 			let sprite_index = sprite.sprite;
 			let z = sprite.z;
+			let angle_degrees = sprite.angle_degrees;
 			let src;
 			if sprite.spritesheet.is_some() {
 				let sprite = game_services.get_world_mut().get_component_mut::<SpriteComponent>(entity_id).unwrap();
@@ -39,7 +43,9 @@ impl Runnable for GraphicsSystem {
 			} else {
 				src = Option::None;
 			}
-			renderables.push(Renderable::new(sprite_index, src, Some(rect), z));
+			let mut renderable = Renderable::new(sprite_index, src, Some(rect), z);
+			renderable.angle_degrees = angle_degrees;
+			renderables.push(renderable);
 		}
 		game_services.renderer.set_renderables(renderables);
 	}
@@ -1,13 +1,11 @@
 use std::sync::{Arc, RwLock};
 
-use sdl2::render::BlendMode;
 use tuple_list::tuple_list_type;
 
-use crate::{components::{health::{DeathEvent, HealthComponent}, hitbox::HitboxComponent, lifetime::LifetimeComponent, sprite::SpritesheetOrientation, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{Runnable, System, SystemComponents, SystemNewable}}, factory, maths};
+use crate::{components::{enemy::EnemyComponent, health::{DeathEvent, HealthComponent}, hitbox::HitboxComponent, lifetime::LifetimeComponent, player::PlayerComponent, sprite::SpritesheetOrientation, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{Runnable, System, SystemComponents, SystemNewable}}, factory, maths};
 
 pub struct HealthSystem {
-	base: Arc<RwLock<System>>,
-	first_explosion: bool
+	base: Arc<RwLock<System>>
 }
 
 impl SystemComponents for HealthSystem {
@@ -17,8 +15,7 @@ impl SystemComponents for HealthSystem {
 impl SystemNewable<HealthSystem, ()> for HealthSystem {
 	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
 		HealthSystem {
-			base: base,
-			first_explosion : true
+			base
 		}
 	}
 }
@@ -28,19 +25,17 @@ impl Runnable for HealthSystem {
 		for entity in self.base.read().unwrap().iter_entities() {
 			let health = game_services.get_world_mut().get_component::<HealthComponent>(entity).unwrap();
 			if health.health_points <= 0 {
-				let de = DeathEvent { entity: *entity };
-				game_services.event_dispatcher.notify(&de);
+				let killer = health.last_damager;
 				let world = game_services.get_world_mut();
-				world.remove_entity(entity);
 				let position = maths::center(world, entity);
-				let explosion_sprite = game_services.resource_manager.load_shared_texture("explosion.png").unwrap();
+				let player = world.has_component::<PlayerComponent>(entity);
+				let enemy = world.get_component::<EnemyComponent>(entity);
+				let (enemy_type, wave, tag) = (enemy.map(|enemy| enemy.enemy_type.clone()), enemy.and_then(|enemy| enemy.wave), enemy.and_then(|enemy| enemy.tag.clone()));
+				let de = DeathEvent { entity: *entity, killer, position, player, enemy_type, wave, tag };
+				game_services.event_dispatcher.notify(&de);
+				game_services.get_world_mut().remove_entity(entity);
+				let explosion_sprite = game_services.resource_manager.load_shared_texture("explosion").unwrap();
 				let explosion = factory::create_animation(explosion_sprite, position.0 as i32 - 16, position.1 as i32 - 16, 10, 16, 16, 16*2, 16*2, 8, SpritesheetOrientation::HORIZONTAL, 30, 8, 1, game_services);
-				if self.first_explosion {
-					let texture = game_services.resource_manager.get_texture_mut(explosion_sprite).unwrap();
-					texture.set_blend_mode(BlendMode::Blend);
-					texture.set_alpha_mod(170);
-					self.first_explosion = false;
-				}
 				game_services.get_world_mut().add_component::<LifetimeComponent>(&explosion, LifetimeComponent::new(common::current_time_ms() + 300));
 			}
 		}
@@ -0,0 +1,66 @@
+use std::sync::{Arc, RwLock, Weak};
+
+use tuple_list::tuple_list_type;
+
+use crate::{components::{force::ForceComponent, homing::HomingComponent, transform::TransformComponent}, core::{common::GameServices, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, maths};
+
+use super::ai::AISystem;
+
+pub struct HomingSystem {
+	base: Arc<RwLock<System>>,
+	ai_system: Option<Weak<RwLock<System>>>
+}
+
+impl SystemComponents for HomingSystem {
+	type Components = tuple_list_type!(HomingComponent, ForceComponent, TransformComponent);
+}
+
+impl SystemNewable<HomingSystem, ()> for HomingSystem {
+	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
+		HomingSystem {
+			base,
+			ai_system: None
+		}
+	}
+}
+
+impl HomingSystem {
+	// Rotates the velocity towards the target, by "turn_rate" radians at most
+	fn steer(velocity: (f32, f32), position: (f32, f32), target: (f32, f32), speed: f32, turn_rate: f32) -> (f32, f32) {
+		let current_angle = f32::atan2(velocity.1, velocity.0);
+		let target_angle = f32::atan2(target.1 - position.1, target.0 - position.0);
+		let mut delta = target_angle - current_angle;
+		while delta > std::f32::consts::PI { delta -= 2.0 * std::f32::consts::PI; }
+		while delta < -std::f32::consts::PI { delta += 2.0 * std::f32::consts::PI; }
+		let angle = current_angle + delta.max(-turn_rate).min(turn_rate);
+		(speed * f32::cos(angle), speed * f32::sin(angle))
+	}
+}
+
+impl Runnable for HomingSystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		if self.ai_system.is_none() {
+			self.ai_system = game_services.get_world().get_system_base::<AISystem>();
+		}
+		let targets: Vec<EntityId> = match self.ai_system.as_ref().and_then(|system| system.upgrade()) {
+			Some(system) => system.read().unwrap().iter_entities().copied().collect(),
+			None => return
+		};
+
+		for entity in self.base.read().unwrap().iter_entities() {
+			let world = game_services.get_world_mut();
+			let position = maths::center(world, entity);
+			let closest = targets.iter()
+				.map(|target| maths::center(world, target))
+				.min_by(|a, b| maths::distance_squared(position, *a).partial_cmp(&maths::distance_squared(position, *b)).unwrap());
+			if let Some(target) = closest {
+				let homing = world.get_component::<HomingComponent>(entity).unwrap();
+				let (speed, turn_rate) = (homing.speed, homing.turn_rate);
+				let force = world.get_component_mut::<ForceComponent>(entity).unwrap();
+				let velocity = Self::steer((force.vx, force.vy), position, target, speed, turn_rate);
+				force.vx = velocity.0;
+				force.vy = velocity.1;
+			}
+		}
+	}
+}
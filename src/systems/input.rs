@@ -2,11 +2,9 @@ use std::{convert::TryFrom, sync::{Arc, RwLock}};
 
 use tuple_list::tuple_list_type;
 
-use crate::{components::{force::ForceComponent, input::{InputComponent, PlayerInput, State}, shot::ShotType, sprite::SpriteComponent, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, factory};
+use crate::{components::{force::ForceComponent, input::{InputComponent, PlayerInput, State}, sprite::SpriteComponent, transform::TransformComponent, weapon::WeaponComponent}, core::{common::{self, GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, factory};
 
 
-const SHOT_LIFETIME_MS: u64 = 2000;
-
 pub struct InputSystem {
 	base: Arc<RwLock<System>>
 }
@@ -23,14 +21,28 @@ impl SystemNewable<InputSystem, ()> for InputSystem {
 	}
 }
 
+// Space between the projectiles of a weapon firing side by side
+const PARALLEL_SHOTS_SPACING: f32 = 16.0;
+
 impl InputSystem {
-	fn shoot<'sdl_all, 'l>(entity_id: &EntityId, game_services: &mut GameServices<'sdl_all, 'l>) {
-		let shot_width = 16;
-		let shot_height = 16 * 2;
+	fn shoot<'sdl_all, 'l>(entity_id: &EntityId, weapon: &WeaponComponent, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let projectile = &weapon.definition().projectile;
+		let tier = weapon.tier();
 		let pos = game_services.get_world().get_component::<TransformComponent>(entity_id).unwrap();
 		let graphic_box = game_services.get_world().get_component::<SpriteComponent>(entity_id).unwrap().graphic_box;
-		let shot_pos = (pos.x as i32 + graphic_box.w / 2 + graphic_box.x - shot_width / 2, pos.y as i32 - graphic_box.h / 2 - graphic_box.y);
-		factory::create_shot("shot.png", shot_pos.0, shot_pos.1, 1, shot_width as u32, shot_height as u32, 0.0, -20.0, SHOT_LIFETIME_MS, ShotType::PLAYER, game_services);
+		let shot_pos = (pos.x + (graphic_box.w / 2 + graphic_box.x) as f32 - projectile.width as f32 / 2.0, pos.y - (graphic_box.h / 2 + graphic_box.y) as f32);
+
+		for i in 0..tier.projectiles {
+			// -0.5 for the leftmost projectile, 0.5 for the rightmost one
+			let ratio = if tier.projectiles > 1 { i as f32 / (tier.projectiles - 1) as f32 - 0.5 } else { 0.0 };
+			let (x, velocity) = if tier.spread_degrees > 0.0 {
+				let angle = (-90.0 + tier.spread_degrees * ratio).to_radians();
+				(shot_pos.0, (projectile.speed * f32::cos(angle), projectile.speed * f32::sin(angle)))
+			} else {
+				(shot_pos.0 + ratio * PARALLEL_SHOTS_SPACING * (tier.projectiles - 1) as f32, (0.0, -projectile.speed))
+			};
+			factory::create_projectile(projectile, tier.damages, (x as i32, shot_pos.1 as i32), velocity, *entity_id, game_services);
+		}
 	}
 }
 
@@ -46,32 +58,36 @@ impl Runnable for InputSystem {
 			let mut direction_x = 0.0;
 			let mut direction_y = 0.0;
 
-			let mut left_move = false;
-			let mut right_move = false;
-
 			let mut shoot = false;
-			for (i, vkey) in inputs_iter {
-				if *vkey {
+			for (i, value) in inputs_iter {
+				if *value > 0.0 {
 					match PlayerInput::try_from(&i).unwrap() {
-						PlayerInput::LEFT => { direction_x = -1.0; left_move = true; },
-						PlayerInput::RIGHT => { direction_x = 1.0; right_move = true },
-						PlayerInput::UP => direction_y = -1.0,
-						PlayerInput::DOWN => direction_y = 1.0,
+						PlayerInput::LEFT => direction_x -= *value,
+						PlayerInput::RIGHT => direction_x += *value,
+						PlayerInput::UP => direction_y -= *value,
+						PlayerInput::DOWN => direction_y += *value,
 						PlayerInput::SHOOT => shoot = true,
 						_ => {},
 					}
 				}
 			}
+			let left_move = direction_x < 0.0;
+			let right_move = direction_x > 0.0;
+
+			// Force is scaled by the stick tilt, but moving diagonally is never faster than moving straight
+			let magnitude: f32 = f32::sqrt(direction_x * direction_x + direction_y * direction_y);
+			let factor = if magnitude > 1.0 { 1.0 / magnitude } else { 1.0 };
 			let force = game_services.get_world_mut().get_component_mut::<ForceComponent>(entity).unwrap();
-			let factor = if direction_x != 0.0 && direction_y != 0.0 { 0.7071 } else { 1.0 };
 			force.vx = power * direction_x * factor;
 			force.vy = power * direction_y * factor;
 
 			if shoot {
-				let input = game_services.get_world_mut().get_component_mut::<InputComponent>(entity).unwrap();
-				if (common::current_time_ms() - input.shot_timer_start) >= input.shot_frequency_ms {
-					input.shot_timer_start = common::current_time_ms();
-					Self::shoot(entity, game_services);
+				if let Some(weapon) = game_services.get_world_mut().get_component_mut::<WeaponComponent>(entity) {
+					if (common::current_time_ms() - weapon.shot_timer_start) >= weapon.tier().fire_rate_ms {
+						weapon.shot_timer_start = common::current_time_ms();
+						let weapon = *weapon;
+						Self::shoot(entity, &weapon, game_services);
+					}
 				}
 			}
 
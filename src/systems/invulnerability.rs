@@ -0,0 +1,37 @@
+use std::sync::{Arc, RwLock};
+
+use tuple_list::tuple_list_type;
+
+use crate::{components::{invulnerability::InvulnerabilityComponent, sprite::SpriteComponent}, core::{common::{self, GameServices}, ecs::{Runnable, System, SystemComponents, SystemNewable}}};
+
+pub struct InvulnerabilitySystem {
+	base: Arc<RwLock<System>>
+}
+
+impl SystemComponents for InvulnerabilitySystem {
+	type Components = tuple_list_type!(InvulnerabilityComponent, SpriteComponent);
+}
+
+impl SystemNewable<InvulnerabilitySystem, ()> for InvulnerabilitySystem {
+	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
+		InvulnerabilitySystem {
+			base
+		}
+	}
+}
+
+impl Runnable for InvulnerabilitySystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		let now = common::current_time_ms();
+		for entity in self.base.read().unwrap().iter_entities() {
+			let world = game_services.get_world_mut();
+			let invulnerability = world.get_component::<InvulnerabilityComponent>(entity).unwrap();
+			let ended = now >= invulnerability.end_ms;
+			let visible = ended || (now / invulnerability.blink_period_ms).is_multiple_of(2);
+			world.get_component_mut::<SpriteComponent>(entity).unwrap().visible = visible;
+			if ended {
+				world.remove_component::<InvulnerabilityComponent>(entity);
+			}
+		}
+	}
+}
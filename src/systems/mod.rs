@@ -7,3 +7,11 @@ pub mod spawner;
 pub mod ai;
 pub mod health;
 pub mod animation;
+
+pub mod audio;
+pub mod invulnerability;
+pub mod homing;
+pub mod powerup;
+pub mod bullet;
+pub mod boss;
+pub mod bomb;
\ No newline at end of file
@@ -0,0 +1,51 @@
+use std::sync::{Arc, RwLock, Weak};
+
+use tuple_list::tuple_list_type;
+
+use crate::{components::{bomb::BombComponent, hitbox::HitboxComponent, powerup::{PowerUpComponent, PowerUpKind}, transform::TransformComponent, weapon::WeaponComponent}, core::{common::GameServices, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, maths};
+
+use super::input::InputSystem;
+
+pub struct PowerUpSystem {
+	base: Arc<RwLock<System>>,
+	input_system: Option<Weak<RwLock<System>>>
+}
+
+impl SystemComponents for PowerUpSystem {
+	type Components = tuple_list_type!(PowerUpComponent, TransformComponent, HitboxComponent);
+}
+
+impl SystemNewable<PowerUpSystem, ()> for PowerUpSystem {
+	fn new(base: Arc<RwLock<System>>, _none: ()) -> Self {
+		PowerUpSystem {
+			base,
+			input_system: None
+		}
+	}
+}
+
+impl Runnable for PowerUpSystem {
+	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
+		if self.input_system.is_none() {
+			self.input_system = game_services.get_world().get_system_base::<InputSystem>();
+		}
+		let players: Vec<EntityId> = match self.input_system.as_ref().and_then(|system| system.upgrade()) {
+			Some(system) => system.read().unwrap().iter_entities().copied().collect(),
+			None => return
+		};
+
+		for entity in self.base.read().unwrap().iter_entities() {
+			let world = game_services.get_world_mut();
+			let picker = players.iter().find(|player| world.has_component::<WeaponComponent>(player) && maths::collision(world, entity, player));
+			if let Some(player) = picker {
+				match world.get_component::<PowerUpComponent>(entity).unwrap().kind {
+					PowerUpKind::Weapon(weapon_type) => world.get_component_mut::<WeaponComponent>(player).unwrap().pick(weapon_type),
+					PowerUpKind::Bomb => if let Some(bomb) = world.get_component_mut::<BombComponent>(player) {
+						bomb.replenish();
+					}
+				}
+				world.remove_entity(entity);
+			}
+		}
+	}
+}
@@ -2,10 +2,13 @@ use std::sync::{Arc, RwLock, Weak};
 
 use tuple_list::tuple_list_type;
 
-use crate::{components::{health::HealthComponent, hitbox::HitboxComponent, input::InputComponent, shot::{ShotComponent, ShotType}, transform::TransformComponent}, core::{common::{GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable, World}}, maths};
+use crate::{components::{health::{DamageEvent, HealthComponent}, invulnerability::InvulnerabilityComponent, hitbox::HitboxComponent, player::PlayerComponent, shot::{ShotComponent, ShotType}, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable, World}}, maths};
 
 use super::health::HealthSystem;
 
+pub const PLAYER_HIT_INVULNERABILITY_MS: u64 = 1000;
+pub const INVULNERABILITY_BLINK_PERIOD_MS: u64 = 100;
+
 pub struct ShotSystem {
 	base: Arc<RwLock<System>>,
 	health_system: Option<Weak<RwLock<System>>>
@@ -47,18 +50,23 @@ impl Runnable for ShotSystem {
 					let world = game_services.get_world_mut();
 					let shot = world.get_component::<ShotComponent>(entity).unwrap();
 					let damages = shot.damages;
+					let owner_player = shot.owner_player;
+					let piercing = shot.piercing;
+					if shot.hit_entities.contains(health_entity) {
+						continue;
+					}
 					let target_entity: Option<EntityId> = match shot.shot_type {
 						ShotType::PLAYER => {
-							// No input => This is an enemy
-							if ! world.has_component::<InputComponent>(health_entity) {
+							// Players don't hurt each other
+							if ! world.has_component::<PlayerComponent>(health_entity) {
 								Self::check_collision(world, entity, health_entity)
 							} else {
 								None
 							}
 						},
 						ShotType::ENEMY => {
-							// Input => This is the player
-							if world.has_component::<InputComponent>(health_entity) {
+							// Invulnerable players are not hit, the shot keeps going
+							if world.has_component::<PlayerComponent>(health_entity) && !world.has_component::<InvulnerabilityComponent>(health_entity) {
 								Self::check_collision(world, entity, health_entity)
 							} else {
 								None
@@ -66,11 +74,24 @@ impl Runnable for ShotSystem {
 						}
 					};
 
-					if target_entity.is_some() {
+					if let Some(target_entity) = target_entity {
 						//println!("DAMAGE !");
-						world.get_component_mut::<HealthComponent>(&target_entity.unwrap()).unwrap().health_points -= damages as i64;
-						world.remove_entity(entity);
-						break;
+						let player = world.has_component::<PlayerComponent>(&target_entity);
+						let health = world.get_component_mut::<HealthComponent>(&target_entity).unwrap();
+						health.health_points -= damages as i64;
+						health.last_damager = owner_player;
+						if player && health.health_points > 0 {
+							world.add_component(&target_entity, InvulnerabilityComponent::new(common::current_time_ms() + PLAYER_HIT_INVULNERABILITY_MS, INVULNERABILITY_BLINK_PERIOD_MS));
+						}
+						if piercing {
+							world.get_component_mut::<ShotComponent>(entity).unwrap().hit_entities.push(target_entity);
+						} else {
+							world.remove_entity(entity);
+						}
+						game_services.event_dispatcher.notify(&DamageEvent { entity: target_entity, damages, player });
+						if ! piercing {
+							break;
+						}
 					}
 				}
 			}
@@ -1,9 +1,11 @@
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, atomic::{AtomicU32, Ordering}};
 
 use rand::Rng;
 use tuple_list::tuple_list_type;
 
-use crate::{components::{ai::AIComponent, hitbox::HitboxComponent, spawner::{SpawnerComponent, SpawnerType}, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{Runnable, System, SystemComponents, SystemNewable}}, factory, levels::phases::{TrajectoryGenerator, TrajectoryType}};
+use crate::{components::{ai::AIComponent, enemy::{EnemyComponent, Wave}, hitbox::HitboxComponent, spawner::{FormationSlot, PendingEnemy, SpawnerComponent}, transform::TransformComponent}, core::{common::{self, GameServices}, ecs::{EntityId, Runnable, System, SystemComponents, SystemNewable}}, factory, levels::phases::{TrajectoryGenerator, TrajectoryType}};
+
+static NEXT_WAVE_ID: AtomicU32 = AtomicU32::new(0);
 
 pub struct SpawnMobSystem {
 	base: Arc<RwLock<System>>
@@ -21,63 +23,122 @@ impl SystemNewable<SpawnMobSystem, ()> for SpawnMobSystem {
 	}
 }
 
+// What every enemy of a spawner shares
+struct SpawnSettings {
+	trajectory: TrajectoryType,
+	shot_frequency_factor: f32,
+	enemy_type: String,
+	tag: Option<String>
+}
+
+impl SpawnSettings {
+	fn new(spawner: &SpawnerComponent) -> Self {
+		SpawnSettings {
+			trajectory: spawner.trajectory_type.clone(),
+			shot_frequency_factor: spawner.shot_frequency_factor,
+			enemy_type: spawner.enemy_type.clone(),
+			tag: spawner.tag.clone()
+		}
+	}
+}
+
 impl SpawnMobSystem {
-	fn spawn_enemies<'sdl_all, 'l>(game_services: &mut GameServices<'sdl_all, 'l>, origin_x: i32, origin_y: i32, angle_radian: f32, pos_offset_x: i32, pos_offset_y: i32, speed: f32, number: u16, luck_percents: f32, trajectory: TrajectoryType, shot_frequency_factor: f32, alternate_pos: bool) {
-		for index in 0..number {
+	// Rolls the luck of every slot of the formation, the enemies to spawn later are added to the pending ones of the spawner
+	fn spawn_enemies<'sdl_all, 'l>(game_services: &mut GameServices<'sdl_all, 'l>, spawner: &EntityId, origin: (i32, i32), formation: Vec<FormationSlot>, settings: &SpawnSettings) {
+		let world = game_services.get_world();
+		let component = world.get_component::<SpawnerComponent>(spawner).unwrap();
+		let luck_percents = (component.luck_percents * game_services.rank_factor()).min(100.0);
+		let (alternate_pos, delays_spawns) = (component.symetric_alternate_pos, component.delays_spawns());
+		let mirrored = component.mirror_alternate_spawns && component.spawns % 2 == 1;
+		let screen_width = game_services.draw_context.screen_width() as f32;
+
+		let mut slots = Vec::new();
+		for (index, slot) in formation.iter().enumerate() {
 			let mut rng = rand::thread_rng();
 			let random_percent= rng.gen_range(0.0, 100.0) as f32;
 			if random_percent < luck_percents {
-				let direction_vector = (fastapprox::faster::cos((index as f32 * angle_radian) % (2.0 * std::f32::consts::PI)), fastapprox::faster::sin((index as f32 * angle_radian) % (2.0 * std::f32::consts::PI)));
-				let mut position = (origin_x as f32 + direction_vector.0 * pos_offset_x as f32, origin_y as f32 + direction_vector.1 * pos_offset_y as f32);
+				let mut position = (origin.0 as f32 + slot.spawn_offset.0, origin.1 as f32 + slot.spawn_offset.1);
+				let mut trajectory_offset = slot.trajectory_offset;
 				if alternate_pos && index % 2 == 0 {
-					position.0 = game_services.draw_context.screen_width() as f32 - position.0;
+					position.0 = screen_width - position.0;
+				}
+				// The whole formation is mirrored, so that its enemies keep their places along the trajectory
+				if mirrored {
+					position.0 = screen_width - position.0;
+					trajectory_offset.0 = -trajectory_offset.0;
 				}
-				let width = 16 * 3;
-				let height = 16 * 3;
-				let enemy = factory::create_living_entity("invader_1.png", position.0 as i32, position.1 as i32, 0, width, height, game_services);
-				let hitbox = game_services.get_world_mut().get_component_mut::<HitboxComponent>(&enemy).unwrap();
-				// Real enemy hitbox has an offset from the graphical one in order to make the shot "feels" like it really landed on the enemy
-				hitbox.hitbox.h -= 5 * 3;
-				let mut ai = AIComponent::new();
-				ai.shot_frequency_factor = shot_frequency_factor;
-				ai.set_movement_patterns(TrajectoryGenerator::generate_enemy_movement_pattern(&trajectory, common::current_time_ms() + (index as f32 * 300.0 / speed) as u64,
-				((game_services.draw_context.screen_width() / 2) as f32, (game_services.draw_context.screen_height() / 2) as f32), game_services.draw_context.screen_width(), game_services.draw_context.screen_height()));
-				game_services.get_world_mut().add_component::<AIComponent>(&enemy, ai);
+				slots.push((position, trajectory_offset, slot.delay_ms));
 			}
 		}
+
+		let wave = Wave { id: NEXT_WAVE_ID.fetch_add(1, Ordering::Relaxed), size: slots.len() as u16 };
+		let now = common::current_time_ms();
+		for (position, trajectory_offset, delay_ms) in slots {
+			if delays_spawns && delay_ms > 0 {
+				let component = game_services.get_world_mut().get_component_mut::<SpawnerComponent>(spawner).unwrap();
+				component.pending.push(PendingEnemy { spawn_ms: now + delay_ms, position, trajectory_offset, wave });
+			} else {
+				Self::spawn_enemy(game_services, position, trajectory_offset, delay_ms, wave, settings);
+			}
+		}
+	}
+
+	fn spawn_pending<'sdl_all, 'l>(game_services: &mut GameServices<'sdl_all, 'l>, spawner: &EntityId) {
+		let now = common::current_time_ms();
+		let component = game_services.get_world_mut().get_component_mut::<SpawnerComponent>(spawner).unwrap();
+		if component.pending.iter().all(|enemy| enemy.spawn_ms > now) {
+			return;
+		}
+		let (due, pending): (Vec<PendingEnemy>, Vec<PendingEnemy>) = component.pending.drain(..).partition(|enemy| enemy.spawn_ms <= now);
+		component.pending = pending;
+		let settings = SpawnSettings::new(component);
+		for enemy in due {
+			Self::spawn_enemy(game_services, enemy.position, enemy.trajectory_offset, 0, enemy.wave, &settings);
+		}
+	}
+
+	// delay_ms : the enemy stays on its spawn position until then
+	fn spawn_enemy<'sdl_all, 'l>(game_services: &mut GameServices<'sdl_all, 'l>, position: (f32, f32), trajectory_offset: (f32, f32), delay_ms: u64, wave: Wave, settings: &SpawnSettings) {
+		let rank_factor = game_services.rank_factor();
+		let enemy_types = game_services.enemy_types.clone();
+		let enemy = factory::create_enemy(enemy_types.get(&settings.enemy_type).unwrap(), position.0 as i32, position.1 as i32, game_services);
+		let (screen_width, screen_height) = (game_services.draw_context.screen_width(), game_services.draw_context.screen_height());
+		let mut trajectories = TrajectoryGenerator::generate_enemy_movement_pattern(&settings.trajectory, common::current_time_ms() + delay_ms,
+			((screen_width / 2) as f32, (screen_height / 2) as f32), screen_width, screen_height);
+		for sequence in trajectories.iter_mut() {
+			sequence.translate(trajectory_offset);
+		}
+		let ai = game_services.get_world_mut().get_component_mut::<AIComponent>(&enemy).unwrap();
+		ai.shot_frequency_factor *= settings.shot_frequency_factor * rank_factor;
+		ai.set_movement_patterns(trajectories);
+		let component = game_services.get_world_mut().get_component_mut::<EnemyComponent>(&enemy).unwrap();
+		component.wave = Some(wave);
+		component.tag = settings.tag.clone();
 	}
 }
 
 impl Runnable for SpawnMobSystem {
 	fn run<'sdl_all, 'l>(&mut self, game_services: &mut GameServices<'sdl_all, 'l>) {
 		for entity in self.base.read().unwrap().iter_entities() {
+			Self::spawn_pending(game_services, entity);
 			let world = game_services.get_world_mut();
 			let spawner = world.get_component::<SpawnerComponent>(entity).unwrap();
+			// Out of spawns, kept until its pending enemies are spawned
+			if spawner.num == 0 && spawner.pending.is_empty() {
+				game_services.get_world_mut().remove_entity(entity);
+				continue;
+			}
 			if spawner.num > 0 && common::current_time_ms() - spawner.last_spawn_ms > spawner.frequency_ms as u64 {
 				let area = world.get_component::<HitboxComponent>(entity).unwrap();
 				let pos = (area.hitbox.width() as i32/ spawner.num as i32, area.hitbox.height() as i32/ spawner.num as i32);
 				let origin = world.get_component::<TransformComponent>(entity).unwrap();
 				let origin = (origin.x as i32 - (area.hitbox.width() as i32/2) + area.hitbox.x, origin.y as i32 - (area.hitbox.height() as i32/2) + area.hitbox.y);
-				let propulsion = spawner.propulsion;
-				let num = spawner.num;
-				let luck_percents = spawner.luck_percents;
-				let shot_frequency_factor = spawner.shot_frequency_factor;
-				let trajectory = spawner.trajectory_type.clone();
-				let alternate_pos = spawner.symetric_alternate_pos;
-				match spawner.spawner_type {
-					SpawnerType::CIRCLE => {
-						let angle_radian = spawner.max_angle / spawner.num as f32;
-						Self::spawn_enemies(game_services, origin.0, origin.1,
-							angle_radian, pos.0, pos.1, propulsion, num, luck_percents, trajectory, shot_frequency_factor, alternate_pos);
-					},
-					SpawnerType::POINT => {
-						Self::spawn_enemies(game_services, origin.0, origin.1,
-							0.0, pos.0, pos.1, propulsion, num, luck_percents, trajectory, shot_frequency_factor, alternate_pos);
-					},
-					SpawnerType::LINEAR => todo!(),
-				}
+				let formation = spawner.formation((pos.0 as f32, pos.1 as f32));
+				let settings = SpawnSettings::new(spawner);
+				Self::spawn_enemies(game_services, entity, origin, formation, &settings);
 				let spawner = game_services.get_world_mut().get_component_mut::<SpawnerComponent>(entity).unwrap();
 				spawner.last_spawn_ms = common::current_time_ms();
+				spawner.spawns += 1;
 				if spawner.randomize_pos {
 					let spawn_pos = factory::random_outside_spawn_pos(game_services.draw_context.screen_width(), game_services.draw_context.screen_height());
 					let pos = game_services.get_world_mut().get_component_mut::<TransformComponent>(entity).unwrap();
@@ -89,7 +150,7 @@ impl Runnable for SpawnMobSystem {
 				if spawner.countdown >= 1 {
 					spawner.countdown -= 1;
 				} else {
-					game_services.get_world_mut().remove_entity(entity);
+					spawner.num = 0;
 				}
 			}
 		}